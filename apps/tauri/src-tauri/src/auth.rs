@@ -1,10 +1,15 @@
 use qm_sync_client::{ReqwestHttpClient, QmSyncClient, SyncClientConfig};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tauri::Manager;
 use tauri_plugin_store::StoreExt;
 use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
 use tokio::sync::RwLock;
 
+use crate::database::LazyDatabase;
+use crate::error::{AppError, ErrorCode};
+
 /// Authentication service for managing user authentication with qm-sync
 pub struct AuthService {
     sync_client: Arc<RwLock<QmSyncClient<ReqwestHttpClient>>>,
@@ -58,6 +63,28 @@ const KEY_SERVER_URL: &str = "server_url";
 const KEY_APP_ID: &str = "app_id";
 const KEY_API_KEY: &str = "api_key";
 
+/// Each workspace keeps its login next to its database, so switching
+/// workspaces also switches the account it syncs with
+pub fn store_path(app_handle: &tauri::AppHandle) -> PathBuf {
+    app_handle
+        .state::<Arc<LazyDatabase>>()
+        .base_path()
+        .join(STORE_FILE)
+}
+
+/// Drop the cached login of a workspace whose folder is being deleted, so
+/// the store plugin doesn't write it back
+pub fn forget_store(app_handle: &tauri::AppHandle, workspace_dir: &Path) {
+    if let Some(store) = app_handle.get_store(workspace_dir.join(STORE_FILE)) {
+        store.close_resource();
+    }
+}
+
+/// The login store is a file in the workspace folder
+pub(crate) fn store_error(action: &str, e: impl std::fmt::Display) -> AppError {
+    AppError::new(ErrorCode::Io, format!("{}: {}", action, e))
+}
+
 /// JWT token claims for validation
 #[derive(Debug, serde::Deserialize)]
 struct TokenClaims {
@@ -69,9 +96,14 @@ struct TokenClaims {
 }
 
 /// Check if a JWT token is expired
-fn is_token_expired(token: &str) -> Result<bool, String> {
+fn is_token_expired(token: &str) -> Result<bool, AppError> {
     let header = decode_header(token)
-        .map_err(|e| format!("Failed to decode token header: {}", e))?;
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::Unauthenticated,
+                format!("Failed to decode token header: {}", e),
+            )
+        })?;
 
     let mut validation = Validation::new(header.alg);
     validation.insecure_disable_signature_validation();
@@ -82,11 +114,16 @@ fn is_token_expired(token: &str) -> Result<bool, String> {
         &DecodingKey::from_secret(&[]),
         &validation,
     )
-    .map_err(|e| format!("Failed to decode token: {}", e))?;
+    .map_err(|e| {
+        AppError::new(
+            ErrorCode::Unauthenticated,
+            format!("Failed to decode token: {}", e),
+        )
+    })?;
 
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("Failed to get current time: {}", e))?
+        .map_err(|e| AppError::internal(format!("Failed to get current time: {}", e)))?
         .as_secs() as i64;
 
     Ok(token_data.claims.exp < now)
@@ -114,7 +151,7 @@ impl AuthService {
         username: String,
         email: String,
         password: String,
-    ) -> Result<AuthResponse, String> {
+    ) -> Result<AuthResponse, AppError> {
         let app_id = self.default_app_id.clone();
         let api_key = self.default_api_key.clone();
 
@@ -122,7 +159,7 @@ impl AuthService {
         let result = client
             .register(&username, &email, &password)
             .await
-            .map_err(|e| format!("Registration failed: {}", e))?;
+            .map_err(|e| AppError::new(ErrorCode::Network, format!("Registration failed: {}", e)))?;
 
         let auth_response = AuthResponse {
             user_id: result.user_id,
@@ -143,7 +180,7 @@ impl AuthService {
         app_handle: &tauri::AppHandle,
         email: String,
         password: String,
-    ) -> Result<AuthResponse, String> {
+    ) -> Result<AuthResponse, AppError> {
         let app_id = self.default_app_id.clone();
         let api_key = self.default_api_key.clone();
 
@@ -151,7 +188,7 @@ impl AuthService {
         let result = client
             .login(&email, &password)
             .await
-            .map_err(|e| format!("Login failed: {}", e))?;
+            .map_err(|e| AppError::new(ErrorCode::Network, format!("Login failed: {}", e)))?;
 
         let auth_response = AuthResponse {
             user_id: result.user_id,
@@ -170,7 +207,7 @@ impl AuthService {
     pub async fn refresh_token(
         &self,
         app_handle: &tauri::AppHandle,
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         let refresh_token = self.get_refresh_token(app_handle).await?;
         let access_token = self.get_access_token(app_handle).await.unwrap_or_default();
 
@@ -184,7 +221,12 @@ impl AuthService {
             client
                 .refresh_token()
                 .await
-                .map_err(|e| format!("Token refresh failed: {}", e))?;
+                .map_err(|e| {
+                    AppError::new(
+                        ErrorCode::Unauthenticated,
+                        format!("Token refresh failed: {}", e),
+                    )
+                })?;
         }
 
         let (new_access, new_refresh) = {
@@ -194,18 +236,22 @@ impl AuthService {
 
         self.update_tokens_raw(
             app_handle,
-            &new_access.ok_or_else(|| "No access token after refresh".to_string())?,
-            &new_refresh.ok_or_else(|| "No refresh token after refresh".to_string())?,
+            &new_access.ok_or_else(|| {
+                AppError::new(ErrorCode::Unauthenticated, "No access token after refresh")
+            })?,
+            &new_refresh.ok_or_else(|| {
+                AppError::new(ErrorCode::Unauthenticated, "No refresh token after refresh")
+            })?,
         ).await?;
 
         Ok(())
     }
 
     /// Logout the current user
-    pub async fn logout(&self, app_handle: &tauri::AppHandle) -> Result<(), String> {
+    pub async fn logout(&self, app_handle: &tauri::AppHandle) -> Result<(), AppError> {
         let store = app_handle
-            .store(STORE_FILE)
-            .map_err(|e| format!("Failed to access store: {}", e))?;
+            .store(store_path(app_handle))
+            .map_err(|e| store_error("Failed to access store", e))?;
 
         store.delete(KEY_ACCESS_TOKEN);
         store.delete(KEY_REFRESH_TOKEN);
@@ -215,48 +261,50 @@ impl AuthService {
         store.delete(KEY_APP_ID);
         store.delete(KEY_API_KEY);
 
-        store.save().map_err(|e| format!("Failed to save store: {}", e))?;
+        store.save().map_err(|e| store_error("Failed to save store", e))?;
 
         Ok(())
     }
 
     /// Get the stored access token
-    pub async fn get_access_token(&self, app_handle: &tauri::AppHandle) -> Result<String, String> {
+    pub async fn get_access_token(&self, app_handle: &tauri::AppHandle) -> Result<String, AppError> {
         let store = app_handle
-            .store(STORE_FILE)
-            .map_err(|e| format!("Failed to access store: {}", e))?;
+            .store(store_path(app_handle))
+            .map_err(|e| store_error("Failed to access store", e))?;
 
         store
             .get(KEY_ACCESS_TOKEN)
             .and_then(|v| v.as_str().map(|s| s.to_string()))
-            .ok_or_else(|| "No access token found".to_string())
+            .ok_or_else(|| AppError::new(ErrorCode::Unauthenticated, "No access token found"))
     }
 
     /// Get the stored refresh token
     pub async fn get_refresh_token(
         &self,
         app_handle: &tauri::AppHandle,
-    ) -> Result<String, String> {
+    ) -> Result<String, AppError> {
         let store = app_handle
-            .store(STORE_FILE)
-            .map_err(|e| format!("Failed to access store: {}", e))?;
+            .store(store_path(app_handle))
+            .map_err(|e| store_error("Failed to access store", e))?;
 
         store
             .get(KEY_REFRESH_TOKEN)
             .and_then(|v| v.as_str().map(|s| s.to_string()))
-            .ok_or_else(|| "No refresh token found".to_string())
+            .ok_or_else(|| {
+                AppError::new(ErrorCode::Unauthenticated, "No refresh token found")
+            })
     }
 
     /// Get the stored API key
-    pub fn get_stored_api_key(&self, app_handle: &tauri::AppHandle) -> Result<String, String> {
+    pub fn get_stored_api_key(&self, app_handle: &tauri::AppHandle) -> Result<String, AppError> {
         let store = app_handle
-            .store(STORE_FILE)
-            .map_err(|e| format!("Failed to access store: {}", e))?;
+            .store(store_path(app_handle))
+            .map_err(|e| store_error("Failed to access store", e))?;
 
         store
             .get(KEY_API_KEY)
             .and_then(|v| v.as_str().map(|s| s.to_string()))
-            .ok_or_else(|| "No API key found".to_string())
+            .ok_or_else(|| AppError::new(ErrorCode::Unauthenticated, "No API key found"))
     }
 
     /// Check if the user is authenticated
@@ -277,7 +325,7 @@ impl AuthService {
 
     /// Get the current authentication status
     pub async fn get_auth_status(&self, app_handle: &tauri::AppHandle) -> AuthStatus {
-        let store = match app_handle.store(STORE_FILE) {
+        let store = match app_handle.store(store_path(app_handle)) {
             Ok(s) => s,
             Err(_) => {
                 return AuthStatus {
@@ -344,10 +392,10 @@ impl AuthService {
         auth_response: &AuthResponse,
         app_id: &str,
         api_key: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         let store = app_handle
-            .store(STORE_FILE)
-            .map_err(|e| format!("Failed to access store: {}", e))?;
+            .store(store_path(app_handle))
+            .map_err(|e| store_error("Failed to access store", e))?;
 
         store.set(KEY_ACCESS_TOKEN, serde_json::json!(&auth_response.access_token));
         store.set(KEY_REFRESH_TOKEN, serde_json::json!(&auth_response.refresh_token));
@@ -364,7 +412,7 @@ impl AuthService {
             store.set(KEY_IS_ADMIN, serde_json::json!(is_admin));
         }
 
-        store.save().map_err(|e| format!("Failed to save store: {}", e))?;
+        store.save().map_err(|e| store_error("Failed to save store", e))?;
 
         Ok(())
     }
@@ -375,15 +423,15 @@ impl AuthService {
         app_handle: &tauri::AppHandle,
         access_token: &str,
         refresh_token: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         let store = app_handle
-            .store(STORE_FILE)
-            .map_err(|e| format!("Failed to access store: {}", e))?;
+            .store(store_path(app_handle))
+            .map_err(|e| store_error("Failed to access store", e))?;
 
         store.set(KEY_ACCESS_TOKEN, serde_json::json!(access_token));
         store.set(KEY_REFRESH_TOKEN, serde_json::json!(refresh_token));
 
-        store.save().map_err(|e| format!("Failed to save store: {}", e))?;
+        store.save().map_err(|e| store_error("Failed to save store", e))?;
 
         Ok(())
     }
@@ -0,0 +1,45 @@
+use crate::database::models::Annotation;
+use crate::database::repository::AnnotationsRepository;
+use crate::database::{run_blocking, LazyDatabase};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn get_annotations(
+    question_id: String,
+    app: AppHandle,
+) -> Result<Vec<Annotation>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = AnnotationsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.get_by_question(&question_id)).await
+}
+
+#[tauri::command]
+pub async fn create_annotation(
+    question_id: String,
+    body: String,
+    app: AppHandle,
+) -> Result<Annotation, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = AnnotationsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.create(&question_id, &body)).await
+}
+
+#[tauri::command]
+pub async fn update_annotation(
+    id: String,
+    body: String,
+    app: AppHandle,
+) -> Result<Option<Annotation>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = AnnotationsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.update(&id, &body)).await
+}
+
+#[tauri::command]
+pub async fn delete_annotation(id: String, app: AppHandle) -> Result<bool, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = AnnotationsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.delete(&id)).await
+}
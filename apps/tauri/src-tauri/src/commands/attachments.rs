@@ -0,0 +1,68 @@
+use crate::database::models::Attachment;
+use crate::database::repository::AttachmentsRepository;
+use crate::database::{run_blocking, LazyDatabase};
+use crate::error::AppError;
+use crate::utils::mime::mime_from_file_name;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+/// Attach a file to a question. Reference it from the answer with
+/// `attachment://<id>`. The MIME type is guessed from `file_name` when not given.
+#[tauri::command]
+pub async fn upload_attachment(
+    question_id: String,
+    file_name: String,
+    data: Vec<u8>,
+    mime_type: Option<String>,
+    app: AppHandle,
+) -> Result<Attachment, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = AttachmentsRepository::new(Arc::clone(db.inner()));
+    let mime_type = mime_type.unwrap_or_else(|| mime_from_file_name(&file_name).to_string());
+    run_blocking(move || repo.create(&question_id, &file_name, &mime_type, &data)).await
+}
+
+#[tauri::command]
+pub async fn get_attachments(
+    question_id: String,
+    app: AppHandle,
+) -> Result<Vec<Attachment>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = AttachmentsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.get_by_question(&question_id)).await
+}
+
+#[tauri::command]
+pub async fn delete_attachment(id: String, app: AppHandle) -> Result<bool, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = AttachmentsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.delete(&id)).await
+}
+
+/// Serve `attachment://<id>` requests from the webview. Depending on the
+/// platform the id arrives as the host (`attachment://<id>`) or as the path
+/// (`attachment://localhost/<id>`, `http://attachment.localhost/<id>`).
+pub fn attachment_protocol(
+    app: &AppHandle,
+    request: &tauri::http::Request<Vec<u8>>,
+) -> tauri::http::Response<Vec<u8>> {
+    let uri = request.uri();
+    let path = uri.path().trim_matches('/');
+    let id = if path.is_empty() {
+        uri.host().unwrap_or_default()
+    } else {
+        path
+    };
+
+    let db = app.state::<Arc<LazyDatabase>>();
+    let builder = tauri::http::Response::builder();
+    let response = match AttachmentsRepository::new(Arc::clone(db.inner())).get_data(id) {
+        Ok(Some((attachment, data))) => builder
+            .status(200)
+            .header("Content-Type", attachment.mime_type)
+            .body(data),
+        Ok(None) => builder.status(404).body(Vec::new()),
+        Err(e) => builder.status(500).body(e.message.into_bytes()),
+    };
+    response.unwrap_or_default()
+}
@@ -0,0 +1,68 @@
+use crate::database::models::BackupInfo;
+use crate::database::{run_blocking, LazyDatabase};
+use crate::error::{AppError, ErrorCode};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+
+const STORE_FILE: &str = "settings.json";
+const KEY_BACKUP_RETENTION: &str = "backup_retention";
+const DEFAULT_BACKUP_RETENTION: usize = 10;
+
+/// How many automatic backups to keep
+pub fn backup_retention(app: &AppHandle) -> usize {
+    app.store(STORE_FILE)
+        .ok()
+        .and_then(|store| store.get(KEY_BACKUP_RETENTION))
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize)
+        .unwrap_or(DEFAULT_BACKUP_RETENTION)
+}
+
+#[tauri::command]
+pub async fn get_backups(app: AppHandle) -> Result<Vec<BackupInfo>, AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    run_blocking(move || db.list_backups()).await
+}
+
+#[tauri::command]
+pub async fn create_backup(app: AppHandle) -> Result<BackupInfo, AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    let retention = backup_retention(&app);
+    run_blocking(move || db.create_backup("manual", retention)).await
+}
+
+#[tauri::command]
+pub async fn restore_backup(
+    file_name: String,
+    passphrase: Option<String>,
+    app: AppHandle,
+) -> Result<(), AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    run_blocking(move || {
+        // Keep the current state so the restore itself can be undone. Don't
+        // prune here: the backup being restored may be the oldest one.
+        db.create_backup("pre-restore", usize::MAX)?;
+        db.restore_backup(&file_name, passphrase.as_deref())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_backup_retention(app: AppHandle) -> Result<usize, AppError> {
+    Ok(backup_retention(&app))
+}
+
+#[tauri::command]
+pub async fn set_backup_retention(count: usize, app: AppHandle) -> Result<(), AppError> {
+    if count == 0 {
+        return Err(AppError::validation("Keep at least one backup").with_field("count"));
+    }
+    let store = app
+        .store(STORE_FILE)
+        .map_err(|e| AppError::new(ErrorCode::Io, format!("Failed to access store: {}", e)))?;
+    store.set(KEY_BACKUP_RETENTION, serde_json::json!(count));
+    store
+        .save()
+        .map_err(|e| AppError::new(ErrorCode::Io, format!("Failed to save store: {}", e)))
+}
@@ -0,0 +1,78 @@
+use crate::database::models::{BulkMode, BulkResult, Difficulty};
+use crate::database::repository::BulkRepository;
+use crate::database::{run_blocking, LazyDatabase};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn bulk_move_questions(
+    question_ids: Vec<String>,
+    topic_id: String,
+    mode: Option<BulkMode>,
+    app: AppHandle,
+) -> Result<BulkResult, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = BulkRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.move_questions(&question_ids, &topic_id, mode.unwrap_or_default()))
+        .await
+}
+
+#[tauri::command]
+pub async fn bulk_add_tags(
+    question_ids: Vec<String>,
+    tags: Vec<String>,
+    mode: Option<BulkMode>,
+    app: AppHandle,
+) -> Result<BulkResult, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = BulkRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.add_tags(&question_ids, &tags, mode.unwrap_or_default())).await
+}
+
+#[tauri::command]
+pub async fn bulk_remove_tags(
+    question_ids: Vec<String>,
+    tags: Vec<String>,
+    mode: Option<BulkMode>,
+    app: AppHandle,
+) -> Result<BulkResult, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = BulkRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.remove_tags(&question_ids, &tags, mode.unwrap_or_default())).await
+}
+
+#[tauri::command]
+pub async fn bulk_set_difficulty(
+    question_ids: Vec<String>,
+    difficulty: Difficulty,
+    mode: Option<BulkMode>,
+    app: AppHandle,
+) -> Result<BulkResult, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = BulkRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.set_difficulty(&question_ids, &difficulty, mode.unwrap_or_default()))
+        .await
+}
+
+#[tauri::command]
+pub async fn bulk_delete_questions(
+    question_ids: Vec<String>,
+    mode: Option<BulkMode>,
+    app: AppHandle,
+) -> Result<BulkResult, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = BulkRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.delete(&question_ids, mode.unwrap_or_default())).await
+}
+
+#[tauri::command]
+pub async fn bulk_reset_progress(
+    question_ids: Vec<String>,
+    mode: Option<BulkMode>,
+    app: AppHandle,
+) -> Result<BulkResult, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = BulkRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.reset_progress(&question_ids, mode.unwrap_or_default())).await
+}
@@ -1,6 +1,11 @@
-use crate::database::LazyDatabase;
-use crate::database::models::{Topic, Question, QuestionProgress, QuizSession}; // removed unused generate_id
-use crate::database::repository::{LazyQuestionsRepository, LazyTopicsRepository, ProgressRepository, QuizSessionRepository};
+use crate::database::{migrations, run_blocking, LazyDatabase};
+use crate::commands::backups::backup_retention;
+use crate::database::models::{Annotation, Attachment, DifficultyLevel, Topic, Question, QuestionProgress, QuizSession}; // removed unused generate_id
+use crate::database::repository::{difficulty_repo, AnnotationsRepository, AttachmentsRepository, DifficultyRepository, LazyQuestionsRepository, LazyTopicsRepository, ProgressRepository, QuizSessionRepository};
+use crate::error::AppError;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rusqlite::Connection;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 use std::fs;
@@ -22,6 +27,49 @@ pub struct ImportResult {
     pub questions_count: usize,
     pub progress_count: usize,
     pub quiz_sessions_count: usize,
+    pub attachments_count: usize,
+    pub annotations_count: usize,
+    /// Why the import was rejected; nothing is written when this isn't empty
+    pub errors: Vec<ImportError>,
+}
+
+impl ImportResult {
+    fn rejected(errors: Vec<ImportError>) -> Self {
+        Self {
+            success: false,
+            message: format!("Import rejected: {} problem(s) found, nothing was changed", errors.len()),
+            topics_count: 0,
+            questions_count: 0,
+            progress_count: 0,
+            quiz_sessions_count: 0,
+            attachments_count: 0,
+            annotations_count: 0,
+            errors,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportErrorKind {
+    /// The same id appears twice in the file
+    DuplicateId,
+    /// Refers to a topic or question in neither the file nor the database
+    MissingReference,
+    /// A value that can't be read, such as attachment content
+    InvalidData,
+}
+
+/// A record that kept an import from running
+#[derive(Serialize, Debug)]
+pub struct ImportError {
+    pub kind: ImportErrorKind,
+    /// Kind of record: "topic", "question", "progress", "quiz_session" or
+    /// "attachment"
+    pub record: &'static str,
+    /// The record's id; the question id for progress
+    pub id: String,
+    pub message: String,
 }
 
 #[derive(Serialize)]
@@ -38,6 +86,13 @@ struct DatabaseExport {
     database: DatabaseContent,
     progress: ProgressData,
     quiz_sessions: QuizSessionsData,
+    #[serde(default)]
+    attachments: Vec<AttachmentExport>,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+    /// Custom difficulty levels; built-in ones always exist
+    #[serde(default)]
+    difficulty_levels: Vec<DifficultyLevel>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -46,12 +101,44 @@ struct DatabaseContent {
     questions: Vec<Question>,
 }
 
+/// The parts of an export older than 2.5 that nested topics replaced: a
+/// topic's `subtopics` names and a question's `subtopic`
+#[derive(Deserialize, Default)]
+struct LegacyContent {
+    #[serde(default)]
+    topics: Vec<LegacyTopic>,
+    #[serde(default)]
+    questions: Vec<LegacyQuestion>,
+}
+
+#[derive(Deserialize)]
+struct LegacyTopic {
+    id: String,
+    #[serde(default)]
+    subtopics: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct LegacyQuestion {
+    id: String,
+    #[serde(default)]
+    subtopic: Option<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 struct ProgressData {
     version: String,
     data: Vec<QuestionProgress>,
 }
 
+#[derive(Serialize, Deserialize)]
+struct AttachmentExport {
+    #[serde(flatten)]
+    attachment: Attachment,
+    /// File content, base64 encoded
+    data: String,
+}
+
 #[derive(Serialize, Deserialize)]
 struct QuizSessionsData {
     version: String,
@@ -62,7 +149,7 @@ struct QuizSessionsData {
 pub async fn export_database(
     app: AppHandle,
     export_path: String,
-) -> Result<ExportResult, String> {
+) -> Result<ExportResult, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
 
     // Read Data using Repositories
@@ -70,39 +157,65 @@ pub async fn export_database(
     let questions_repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
     let progress_repo = ProgressRepository::new(Arc::clone(db.inner()));
     let quiz_repo = QuizSessionRepository::new(Arc::clone(db.inner()));
+    let attachments_repo = AttachmentsRepository::new(Arc::clone(db.inner()));
+    let annotations_repo = AnnotationsRepository::new(Arc::clone(db.inner()));
+    let levels_repo = DifficultyRepository::new(Arc::clone(db.inner()));
 
-    let topics = topics_repo.get_all()?;
-    let questions = questions_repo.get_all()?;
-    let progress = progress_repo.get_all()?;
-    let sessions = quiz_repo.get_all_sessions()?;
-
-    let export_data = DatabaseExport {
-        version: "2.1".to_string(),
-        exported_at: chrono::Utc::now().to_rfc3339(),
-        database: DatabaseContent {
-            topics,
-            questions,
-        },
-        progress: ProgressData {
-            version: "2.1".to_string(),
-            data: progress,
-        },
-        quiz_sessions: QuizSessionsData {
-            version: "2.1".to_string(),
-            sessions,
-        },
-    };
+    run_blocking(move || {
+        let topics = topics_repo.get_all(true)?;
+        let questions = questions_repo.get_all()?;
+        let progress = progress_repo.get_all()?;
+        let sessions = quiz_repo.get_all_sessions()?;
+        // Only attachments of exported questions, so the file imports cleanly
+        let attachments = attachments_repo
+            .get_all_with_data()?
+            .into_iter()
+            .filter(|(a, _)| questions.iter().any(|q| q.id == a.question_id))
+            .map(|(attachment, data)| AttachmentExport {
+                attachment,
+                data: BASE64.encode(data),
+            })
+            .collect();
+        let annotations = annotations_repo.get_all()?;
+        let difficulty_levels = levels_repo
+            .get_all()?
+            .into_iter()
+            .filter(|l| !l.built_in)
+            .collect();
 
-    let json = serde_json::to_string_pretty(&export_data)
-        .map_err(|e| format!("Serialization error: {}", e))?;
+        let export_data = DatabaseExport {
+            version: "2.5".to_string(),
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            database: DatabaseContent {
+                topics,
+                questions,
+            },
+            progress: ProgressData {
+                version: "2.1".to_string(),
+                data: progress,
+            },
+            quiz_sessions: QuizSessionsData {
+                version: "2.1".to_string(),
+                sessions,
+            },
+            attachments,
+            annotations,
+            difficulty_levels,
+        };
 
-    fs::write(&export_path, json).map_err(|e| format!("Failed to write file: {}", e))?;
+        let json = serde_json::to_string_pretty(&export_data)
+            .map_err(|e| AppError::from(e).context("Serialization error"))?;
 
-    Ok(ExportResult {
-        success: true,
-        message: "Database exported successfully".to_string(),
-        exported_path: Some(export_path),
+        fs::write(&export_path, json)
+            .map_err(|e| AppError::from(e).context("Failed to write file"))?;
+
+        Ok(ExportResult {
+            success: true,
+            message: "Database exported successfully".to_string(),
+            exported_path: Some(export_path),
+        })
     })
+    .await
 }
 
 #[tauri::command]
@@ -110,154 +223,514 @@ pub async fn import_database(
     app: AppHandle,
     import_content: String,
     merge: bool,
-) -> Result<ImportResult, String> {
-    let db = app.state::<Arc<LazyDatabase>>();
-    
-    let topics_repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
-    let questions_repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
-    let _progress_repo = ProgressRepository::new(Arc::clone(db.inner()));
-    let quiz_repo = QuizSessionRepository::new(Arc::clone(db.inner()));
+) -> Result<ImportResult, AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    let retention = backup_retention(&app);
+
+    run_blocking(move || {
+        let data = parse_export(&import_content)?;
+
+        let (attachments, mut errors) = decode_attachments(&data.attachments);
+
+        if !merge {
+            // Replacing: nothing in the database survives to be referenced,
+            // so the file must stand on its own. Check before the backup.
+            errors.extend(check_references(&data, &ExistingIds::default()));
+            if !errors.is_empty() {
+                return Ok(ImportResult::rejected(errors));
+            }
+            db.create_backup("pre-import", retention)?;
+        }
+
+        // Everything below runs in one transaction: any failure rolls the
+        // database back to where it was
+        let conn = db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let existing = if merge {
+            let existing = ExistingIds::load(&tx)?;
+            errors.extend(check_references(&data, &existing));
+            if !errors.is_empty() {
+                return Ok(ImportResult::rejected(errors));
+            }
+            existing
+        } else {
+            // progress -> questions -> topics; quiz sessions only hold ids in
+            // JSON. Attachments, links and revisions go with their questions.
+            tx.execute("DELETE FROM progress", [])?;
+            tx.execute("DELETE FROM quiz_sessions", [])?;
+            tx.execute("DELETE FROM questions", [])?;
+            tx.execute("DELETE FROM topics", [])?;
+            ExistingIds::default()
+        };
+
+        // Import Topics. Merging keeps the local copy of a record that
+        // already exists.
+        let mut topics_count = 0;
+        for topic in &data.database.topics {
+            if existing.topics.contains(&topic.id) {
+                continue;
+            }
+            tx.execute(
+                "INSERT INTO topics (id, name, description, slug, icon, color, parent_id, order_index, created_at, updated_at, archived) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                rusqlite::params![topic.id, topic.name, topic.description, topic.slug, topic.icon, topic.color, topic.parent_id, topic.order, topic.created_at, topic.updated_at, topic.archived]
+            ).map_err(|e| AppError::from(e).context(format!("Failed to import topic {}", topic.id)))?;
+            topics_count += 1;
+        }
+
+        // Import custom difficulty levels before the questions using them.
+        // A level whose rank is taken here is appended after the hardest
+        // one when its first question is imported.
+        for level in data.difficulty_levels.iter().filter(|l| !l.key.is_built_in()) {
+            tx.execute(
+                "INSERT OR IGNORE INTO difficulty_levels (key, name, rank, built_in) VALUES (?, ?, ?, 0)",
+                rusqlite::params![level.key, level.name, level.rank],
+            )?;
+        }
+
+        // Import Questions
+        let mut questions_count = 0;
+        for question in &data.database.questions {
+            if existing.questions.contains(&question.id) {
+                continue;
+            }
+            let answer_json = serde_json::to_string(&question.answer).unwrap_or("{}".to_string());
+            let tags_json = serde_json::to_string(&question.tags).unwrap_or("[]".to_string());
+            // Levels defined in the exporting database may be missing here
+            difficulty_repo::ensure_level(&tx, &question.difficulty)?;
+
+            tx.execute(
+                "INSERT INTO questions (id, topic_id, question_number, question, answer, tags, difficulty, order_index, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                rusqlite::params![question.id, question.topic_id, question.question_number, question.question, answer_json, tags_json, question.difficulty, question.order, question.created_at, question.updated_at]
+            ).map_err(|e| AppError::from(e).context(format!("Failed to import question {}", question.id)))?;
+            questions_count += 1;
+        }
+
+        // Import Progress. The file's progress replaces local progress.
+        let mut progress_count = 0;
+        for p in &data.progress.data {
+            let status_str = format!("{:?}", p.status);
+            tx.execute(
+                 "INSERT OR REPLACE INTO progress (question_id, topic_id, status, confidence_level, times_reviewed, times_correct, times_incorrect, last_reviewed_at, next_review_at, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                 rusqlite::params![p.question_id, p.topic_id, status_str, p.confidence_level, p.times_reviewed, p.times_correct, p.times_incorrect, p.last_reviewed_at, p.next_review_at, p.created_at, p.updated_at]
+            ).map_err(|e| AppError::from(e).context(format!("Failed to import progress of {}", p.question_id)))?;
+            progress_count += 1;
+        }
+
+        // Import Quiz Sessions
+        let mut quiz_sessions_count = 0;
+        for s in &data.quiz_sessions.sessions {
+            if existing.sessions.contains(&s.id) {
+                continue;
+            }
+            let topic_ids = serde_json::to_string(&s.topic_ids).unwrap();
+            let question_ids = serde_json::to_string(&s.question_ids).unwrap();
+            let results = serde_json::to_string(&s.results).unwrap();
+            let type_str = format!("{:?}", s.session_type);
+
+            tx.execute(
+                 "INSERT INTO quiz_sessions (id, session_type, topic_ids, question_ids, current_index, started_at, completed_at, results) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                 rusqlite::params![s.id, type_str, topic_ids, question_ids, s.current_index, s.started_at, s.completed_at, results]
+            ).map_err(|e| AppError::from(e).context(format!("Failed to import quiz session {}", s.id)))?;
+            quiz_sessions_count += 1;
+        }
+
+        // Import Attachments (absent from exports older than 2.2)
+        let mut attachments_count = 0;
+        for (attachment, content) in attachments {
+            // Merge: skip attachments that already exist, by id or content
+            let inserted = tx.execute(
+                 "INSERT OR IGNORE INTO attachments (id, question_id, file_name, mime_type, hash, size, data, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                 rusqlite::params![attachment.id, attachment.question_id, attachment.file_name, attachment.mime_type, attachment.hash, content.len() as i64, content, attachment.created_at]
+            ).map_err(|e| AppError::from(e).context(format!("Failed to import attachment {}", attachment.id)))?;
+            attachments_count += inserted;
+        }
+
+        // Import Annotations. Replacing the data leaves local annotations in
+        // place, since they aren't part of the deck; the file's copies win.
+        let mut annotations_count = 0;
+        let verb = if merge { "INSERT OR IGNORE" } else { "INSERT OR REPLACE" };
+        for a in &data.annotations {
+            annotations_count += tx.execute(
+                 &format!("{} INTO annotations (id, question_id, body, created_at, updated_at, sync_version, synced_at, deleted) VALUES (?, ?, ?, ?, ?, 1, NULL, 0)", verb),
+                 rusqlite::params![a.id, a.question_id, a.body, a.created_at, a.updated_at]
+            )?;
+        }
+
+        tx.commit()?;
+
+        Ok(ImportResult {
+            success: true,
+            message: "Import complete".to_string(),
+            topics_count,
+            questions_count,
+            progress_count,
+            quiz_sessions_count,
+            attachments_count,
+            annotations_count,
+            errors: Vec::new(),
+        })
+    })
+    .await
+}
 
-    // Parse V2
-    let data: DatabaseExport = serde_json::from_str(&import_content)
-        .map_err(|e| format!("Invalid JSON format: {}", e))?;
-
-    if !merge {
-        // Clear all (Manual SQL?)
-        // Or implement clear methods in repos.
-        // Quickest way since we have raw access in DB struct but repos encapsulate it.
-        // Let's rely on Repos or just SQL via DB conn if we exposed it. 
-        // We exposed get_connection().
-        let conn = db.get_connection();
-        let conn = conn.lock().unwrap();
-        
-        // Disable FK checks to clear? Or just delete in order?
-        // SQLite foreign keys are ON.
-        // Delete Quiz Results (inside sessions), Progress, Questions, Topics.
-        // Order: QuizSessions/Progress -> Questions -> Topics
-        
-        // Actually, schema:
-        // progress -> questions
-        // questions -> topics
-        // quiz_sessions -> no FK? actually just JSON arrays of IDs.
-        
-        conn.execute("DELETE FROM progress", []).map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM quiz_sessions", []).map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM questions", []).map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM topics", []).map_err(|e| e.to_string())?;
+/// Parse an export file, turning the subtopics of older files into child
+/// topics the way the topic hierarchy migration does
+fn parse_export(content: &str) -> Result<DatabaseExport, AppError> {
+    let invalid = |e: serde_json::Error| {
+        AppError::validation(format!("Invalid JSON format: {}", e)).with_field("importContent")
+    };
+    let value: serde_json::Value = serde_json::from_str(content).map_err(invalid)?;
+    let legacy = LegacyContent::deserialize(&value["database"]).unwrap_or_default();
+    let mut data: DatabaseExport = serde_json::from_value(value).map_err(invalid)?;
+    upgrade_subtopics(&mut data, &legacy);
+    Ok(data)
+}
+
+/// Give each subtopic named in `legacy` a child topic under its topic, with
+/// the id the migration would have given it, and move its questions and
+/// their progress there
+fn upgrade_subtopics(data: &mut DatabaseExport, legacy: &LegacyContent) {
+    let subtopic_of: HashMap<&str, &str> = legacy
+        .questions
+        .iter()
+        .filter_map(|q| Some((q.id.as_str(), q.subtopic.as_deref()?.trim())))
+        .filter(|(_, name)| !name.is_empty())
+        .collect();
+    let now = chrono::Utc::now().to_rfc3339();
+
+    let mut children = Vec::new();
+    let mut moved: HashMap<String, String> = HashMap::new();
+    for parent in &data.database.topics {
+        let questions: Vec<&Question> = data
+            .database
+            .questions
+            .iter()
+            .filter(|q| q.topic_id == parent.id)
+            .collect();
+
+        // The topic's list first, then names only its questions use
+        let mut names: Vec<&str> = legacy
+            .topics
+            .iter()
+            .filter(|t| t.id == parent.id)
+            .flat_map(|t| t.subtopics.iter().flatten())
+            .map(|name| name.trim())
+            .collect();
+        let mut used: Vec<&str> = questions
+            .iter()
+            .filter_map(|q| subtopic_of.get(q.id.as_str()).copied())
+            .collect();
+        used.sort();
+        used.dedup();
+        names.extend(used);
+
+        let mut seen: Vec<String> = Vec::new();
+        for name in names {
+            let key = name.to_lowercase();
+            if name.is_empty() || seen.contains(&key) {
+                continue;
+            }
+            let child_id = migrations::subtopic_id(&parent.id, &key);
+            for q in &questions {
+                if subtopic_of
+                    .get(q.id.as_str())
+                    .is_some_and(|s| s.to_lowercase() == key)
+                {
+                    moved.insert(q.id.clone(), child_id.clone());
+                }
+            }
+            children.push(Topic {
+                id: child_id,
+                name: name.to_string(),
+                description: String::new(),
+                slug: migrations::subtopic_slug(&parent.slug, &key),
+                icon: parent.icon.clone(),
+                color: parent.color.clone(),
+                parent_id: Some(parent.id.clone()),
+                order: seen.len() as i32,
+                created_at: now.clone(),
+                updated_at: now.clone(),
+                archived: false,
+            });
+            seen.push(key);
+        }
     }
 
-    // Import Topics
-    let mut topics_count = 0;
-    for topic in data.database.topics {
-        // Check if exists
-        if merge {
-             if let Ok(Some(_)) = topics_repo.get_by_id(&topic.id) { continue; }
+    let ids: HashSet<String> = data.database.topics.iter().map(|t| t.id.clone()).collect();
+    data.database
+        .topics
+        .extend(children.into_iter().filter(|t| !ids.contains(&t.id)));
+    for q in &mut data.database.questions {
+        if let Some(child_id) = moved.get(&q.id) {
+            q.topic_id = child_id.clone();
         }
-        // Create DTO or manual insert?
-        // Repo `create` takes `CreateTopicDto` which generates new ID/Time.
-        // We want to preserve ID/data.
-        // We need a `save` or `restore` method in repo, or direct SQL here.
-        // Implementing direct SQL here is repetitive but safe for "Restore".
-        let conn = db.get_connection();
-        let conn = conn.lock().unwrap();
-        let subtopics_json = serde_json::to_string(&topic.subtopics).unwrap_or("[]".to_string());
-        
-        conn.execute(
-            "INSERT INTO topics (id, name, description, slug, icon, color, subtopics, order_index, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            rusqlite::params![topic.id, topic.name, topic.description, topic.slug, topic.icon, topic.color, subtopics_json, topic.order, topic.created_at, topic.updated_at]
-        ).map_err(|e| e.to_string())?;
-        topics_count += 1;
     }
+    for p in &mut data.progress.data {
+        if let Some(child_id) = moved.get(&p.question_id) {
+            p.topic_id = child_id.clone();
+        }
+    }
+}
 
-    // Import Questions
-    let mut questions_count = 0;
-    for question in data.database.questions {
-        if merge {
-             if let Ok(Some(_)) = questions_repo.get_by_id(&question.id) { continue; }
+/// Ids already in the database, which merged records may refer to and which
+/// the merge leaves alone. Trashed rows count: their ids are taken.
+#[derive(Default)]
+struct ExistingIds {
+    topics: HashSet<String>,
+    questions: HashSet<String>,
+    sessions: HashSet<String>,
+}
+
+impl ExistingIds {
+    fn load(conn: &Connection) -> Result<Self, AppError> {
+        let ids = |sql: &str| -> Result<HashSet<String>, AppError> {
+            let mut stmt = conn.prepare(sql)?;
+            let rows = stmt
+                .query_map([], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>().map_err(AppError::from)
+        };
+        Ok(Self {
+            topics: ids("SELECT id FROM topics")?,
+            questions: ids("SELECT id FROM questions")?,
+            sessions: ids("SELECT id FROM quiz_sessions")?,
+        })
+    }
+}
+
+/// Decode attachment content up front, so a bad file is reported with the
+/// other problems instead of failing halfway through
+fn decode_attachments(exports: &[AttachmentExport]) -> (Vec<(Attachment, Vec<u8>)>, Vec<ImportError>) {
+    let mut attachments = Vec::new();
+    let mut errors = Vec::new();
+    for a in exports {
+        match BASE64.decode(&a.data) {
+            Ok(content) => attachments.push((a.attachment.clone(), content)),
+            Err(e) => errors.push(ImportError {
+                kind: ImportErrorKind::InvalidData,
+                record: "attachment",
+                id: a.attachment.id.clone(),
+                message: format!("Content is not valid base64: {}", e),
+            }),
+        }
+    }
+    (attachments, errors)
+}
+
+/// Find records whose ids repeat or that refer to a topic or question that
+/// is neither in the file nor in `existing`. Quiz sessions and annotations
+/// may outlive their questions, so they aren't checked.
+fn check_references(data: &DatabaseExport, existing: &ExistingIds) -> Vec<ImportError> {
+    let mut errors = Vec::new();
+    let mut missing = |record: &'static str, id: &str, message: String| {
+        errors.push(ImportError {
+            kind: ImportErrorKind::MissingReference,
+            record,
+            id: id.to_string(),
+            message,
+        })
+    };
+
+    let topic_ids: HashSet<&str> = existing
+        .topics
+        .iter()
+        .map(String::as_str)
+        .chain(data.database.topics.iter().map(|t| t.id.as_str()))
+        .collect();
+    let question_ids: HashSet<&str> = existing
+        .questions
+        .iter()
+        .map(String::as_str)
+        .chain(data.database.questions.iter().map(|q| q.id.as_str()))
+        .collect();
+
+    for topic in &data.database.topics {
+        if let Some(parent_id) = &topic.parent_id {
+            if !topic_ids.contains(parent_id.as_str()) {
+                missing("topic", &topic.id, format!("Parent topic {} not found", parent_id));
+            }
         }
-        
-        let conn = db.get_connection();
-        let conn = conn.lock().unwrap();
-        let answer_json = serde_json::to_string(&question.answer).unwrap_or("{}".to_string());
-        let tags_json = serde_json::to_string(&question.tags).unwrap_or("[]".to_string());
-        
-        conn.execute(
-            "INSERT INTO questions (id, topic_id, subtopic, question_number, question, answer, tags, difficulty, order_index, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-            rusqlite::params![question.id, question.topic_id, question.subtopic, question.question_number, question.question, answer_json, tags_json, question.difficulty, question.order, question.created_at, question.updated_at]
-        ).map_err(|e| e.to_string())?;
-        questions_count += 1;
     }
-    
-    // Import Progress
-    let mut progress_count = 0;
-    for p in data.progress.data {
-        // Merge logic: Update or Skip? 
-        // Typically merge overwrites progress if newer? Let's skip if exists for now or just overwrite (Update).
-        // Let's use INSERT OR REPLACE for progress always?
-        let conn = db.get_connection();
-        let conn = conn.lock().unwrap();
-        let status_str = format!("{:?}", p.status);
-        
-        conn.execute(
-             "INSERT OR REPLACE INTO progress (question_id, topic_id, status, confidence_level, times_reviewed, times_correct, times_incorrect, last_reviewed_at, next_review_at, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-             rusqlite::params![p.question_id, p.topic_id, status_str, p.confidence_level, p.times_reviewed, p.times_correct, p.times_incorrect, p.last_reviewed_at, p.next_review_at, p.created_at, p.updated_at]
-        ).map_err(|e| e.to_string())?;
-        progress_count += 1;
+    for question in &data.database.questions {
+        if !topic_ids.contains(question.topic_id.as_str()) {
+            missing("question", &question.id, format!("Topic {} not found", question.topic_id));
+        }
+    }
+    for p in &data.progress.data {
+        if !question_ids.contains(p.question_id.as_str()) {
+            missing("progress", &p.question_id, format!("Question {} not found", p.question_id));
+        }
+        if !topic_ids.contains(p.topic_id.as_str()) {
+            missing("progress", &p.question_id, format!("Topic {} not found", p.topic_id));
+        }
     }
-    
-    // Import Quiz Sessions
-    let mut quiz_sessions_count = 0;
-    for s in data.quiz_sessions.sessions {
-        // Merge: Skip if exists
-        if merge {
-            if let Ok(Some(_)) = quiz_repo.get_by_id(&s.id) { continue; }
+    for a in &data.attachments {
+        let question_id = &a.attachment.question_id;
+        if !question_ids.contains(question_id.as_str()) {
+            missing("attachment", &a.attachment.id, format!("Question {} not found", question_id));
         }
-        
-        let conn = db.get_connection();
-        let conn = conn.lock().unwrap();
-        let topic_ids = serde_json::to_string(&s.topic_ids).unwrap();
-        let question_ids = serde_json::to_string(&s.question_ids).unwrap();
-        let results = serde_json::to_string(&s.results).unwrap();
-        let type_str = format!("{:?}", s.session_type);
-
-        conn.execute(
-             "INSERT INTO quiz_sessions (id, session_type, topic_ids, question_ids, current_index, started_at, completed_at, results) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-             rusqlite::params![s.id, type_str, topic_ids, question_ids, s.current_index, s.started_at, s.completed_at, results]
-        ).map_err(|e| e.to_string())?;
-        quiz_sessions_count += 1;
     }
 
-    Ok(ImportResult {
-        success: true,
-        message: "Import complete".to_string(),
-        topics_count,
-        questions_count,
-        progress_count,
-        quiz_sessions_count,
-    })
+    let mut duplicates = |record: &'static str, ids: Vec<&str>| {
+        let mut seen = HashSet::new();
+        for id in ids {
+            if !seen.insert(id) {
+                errors.push(ImportError {
+                    kind: ImportErrorKind::DuplicateId,
+                    record,
+                    id: id.to_string(),
+                    message: format!("More than one {} with this id", record.replace('_', " ")),
+                });
+            }
+        }
+    };
+    duplicates("topic", data.database.topics.iter().map(|t| t.id.as_str()).collect());
+    duplicates("question", data.database.questions.iter().map(|q| q.id.as_str()).collect());
+    duplicates("quiz_session", data.quiz_sessions.sessions.iter().map(|s| s.id.as_str()).collect());
+
+    errors
 }
 
 #[tauri::command]
-pub async fn get_database_stats(app: AppHandle) -> Result<DatabaseStats, String> {
+pub async fn get_database_stats(app: AppHandle) -> Result<DatabaseStats, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let questions_repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
-    
-    // Efficient Count
-    let conn = db.get_connection();
-    let conn = conn.lock().unwrap();
-    let topics_count: i64 = conn.query_row("SELECT count(*) FROM topics", [], |r| r.get(0)).unwrap_or(0);
-    drop(conn); // Drop lock before using repo
-    
-    let questions_count = questions_repo.count().unwrap_or(0);
-    
-    // Size: Size of database file
-    let path = db.get_path();
-    let database_size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
-
-    Ok(DatabaseStats {
-        topics_count: topics_count as usize,
-        questions_count,
-        database_size,
+    let db = Arc::clone(db.inner());
+
+    run_blocking(move || {
+        // Efficient Count
+        let conn = db.read_connection()?;
+        let topics_count: i64 = conn.query_row("SELECT count(*) FROM topics", [], |r| r.get(0)).unwrap_or(0);
+        drop(conn); // Return connection before using repo
+
+        let questions_count = questions_repo.count().unwrap_or(0);
+
+        // Size: Size of database file
+        let path = db.get_path();
+        let database_size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+
+        Ok(DatabaseStats {
+            topics_count: topics_count as usize,
+            questions_count,
+            database_size,
+        })
     })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export(json: serde_json::Value) -> DatabaseExport {
+        let mut base = serde_json::json!({
+            "version": "2.4",
+            "exported_at": "2024-01-01T00:00:00Z",
+            "database": { "topics": [], "questions": [] },
+            "progress": { "version": "2.1", "data": [] },
+            "quiz_sessions": { "version": "2.1", "sessions": [] },
+        });
+        for (key, value) in json.as_object().unwrap() {
+            base["database"][key] = value.clone();
+        }
+        serde_json::from_value(base).unwrap()
+    }
+
+    fn topic(id: &str, parent_id: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "id": id, "name": id, "description": "", "slug": id, "icon": "", "color": "",
+            "parentId": parent_id, "order": 0, "createdAt": "", "updatedAt": "",
+        })
+    }
+
+    fn question(id: &str, topic_id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id, "topicId": topic_id, "questionNumber": 1, "question": "", "answer": { "markdown": "" },
+            "tags": [], "difficulty": "beginner", "order": 0, "createdAt": "", "updatedAt": "",
+        })
+    }
+
+    #[test]
+    fn test_check_references() {
+        let data = export(serde_json::json!({
+            "topics": [topic("t1", None), topic("t2", Some("gone")), topic("t1", None)],
+            "questions": [question("q1", "t1"), question("q2", "missing")],
+        }));
+        let errors = check_references(&data, &ExistingIds::default());
+        let found: Vec<_> = errors.iter().map(|e| (e.kind, e.record, e.id.as_str())).collect();
+        assert_eq!(
+            found,
+            vec![
+                (ImportErrorKind::MissingReference, "topic", "t2"),
+                (ImportErrorKind::MissingReference, "question", "q2"),
+                (ImportErrorKind::DuplicateId, "topic", "t1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_export_nests_legacy_subtopics() {
+        let mut t1 = topic("t1", None);
+        t1["subtopics"] = serde_json::json!(["Basics", "Unused"]);
+        let mut q1 = question("q1", "t1");
+        q1["subtopic"] = serde_json::json!("basics");
+        let mut q2 = question("q2", "t1");
+        q2["subtopic"] = serde_json::json!(" Traits ");
+        let q3 = question("q3", "t1");
+        let content = serde_json::json!({
+            "version": "2.4",
+            "exported_at": "2024-01-01T00:00:00Z",
+            "database": { "topics": [t1], "questions": [q1, q2, q3] },
+            "progress": { "version": "2.1", "data": [{
+                "questionId": "q2", "topicId": "t1", "status": "Studying", "confidenceLevel": 0,
+                "timesReviewed": 0, "timesCorrect": 0, "timesIncorrect": 0,
+                "createdAt": "", "updatedAt": "",
+            }] },
+            "quiz_sessions": { "version": "2.1", "sessions": [] },
+        });
+
+        let data = parse_export(&content.to_string()).unwrap();
+        let basics = migrations::subtopic_id("t1", "basics");
+        let unused = migrations::subtopic_id("t1", "unused");
+        let traits = migrations::subtopic_id("t1", "traits");
+        let children: Vec<_> = data
+            .database
+            .topics
+            .iter()
+            .filter(|t| t.parent_id.as_deref() == Some("t1"))
+            .map(|t| (t.id.as_str(), t.name.as_str(), t.slug.as_str(), t.order))
+            .collect();
+        assert_eq!(
+            children,
+            vec![
+                (basics.as_str(), "Basics", "t1-basics", 0),
+                (unused.as_str(), "Unused", "t1-unused", 1),
+                (traits.as_str(), "Traits", "t1-traits", 2),
+            ]
+        );
+        let topic_of: Vec<_> = data
+            .database
+            .questions
+            .iter()
+            .map(|q| q.topic_id.as_str())
+            .collect();
+        assert_eq!(topic_of, vec![basics.as_str(), traits.as_str(), "t1"]);
+        assert_eq!(data.progress.data[0].topic_id, traits);
+        assert!(check_references(&data, &ExistingIds::default()).is_empty());
+    }
+
+    #[test]
+    fn test_merge_references_existing_records() {
+        let data = export(serde_json::json!({
+            "topics": [topic("t2", Some("local"))],
+            "questions": [question("q1", "local")],
+        }));
+        let existing = ExistingIds {
+            topics: HashSet::from(["local".to_string()]),
+            ..Default::default()
+        };
+        assert!(check_references(&data, &existing).is_empty());
+        assert_eq!(check_references(&data, &ExistingIds::default()).len(), 2);
+    }
 }
@@ -0,0 +1,43 @@
+use crate::database::models::{
+    CreateDifficultyLevelDto, DifficultyLevel, UpdateDifficultyLevelDto,
+};
+use crate::database::repository::DifficultyRepository;
+use crate::database::{run_blocking, LazyDatabase};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn get_difficulty_levels(app: AppHandle) -> Result<Vec<DifficultyLevel>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = DifficultyRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.get_all()).await
+}
+
+#[tauri::command]
+pub async fn create_difficulty_level(
+    dto: CreateDifficultyLevelDto,
+    app: AppHandle,
+) -> Result<DifficultyLevel, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = DifficultyRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.create(dto)).await
+}
+
+#[tauri::command]
+pub async fn update_difficulty_level(
+    key: String,
+    dto: UpdateDifficultyLevelDto,
+    app: AppHandle,
+) -> Result<Option<DifficultyLevel>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = DifficultyRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.update(&key, dto)).await
+}
+
+#[tauri::command]
+pub async fn delete_difficulty_level(key: String, app: AppHandle) -> Result<bool, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = DifficultyRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.delete(&key)).await
+}
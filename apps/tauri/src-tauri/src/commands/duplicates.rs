@@ -0,0 +1,40 @@
+use crate::database::models::{DuplicateCluster, MergeResult};
+use crate::database::repository::DuplicatesRepository;
+use crate::database::{run_blocking, LazyDatabase};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+/// Clusters of likely duplicate questions, best match first. Searches every
+/// topic unless `topic_id` is given, and compares across topics unless
+/// `across_topics` is false.
+#[tauri::command]
+pub async fn find_duplicate_questions(
+    topic_id: Option<String>,
+    across_topics: Option<bool>,
+    threshold: Option<f64>,
+    app: AppHandle,
+) -> Result<Vec<DuplicateCluster>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = DuplicatesRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || {
+        repo.find(
+            topic_id.as_deref(),
+            across_topics.unwrap_or(true),
+            threshold,
+        )
+    })
+    .await
+}
+
+/// Keep one question and fold the others into it
+#[tauri::command]
+pub async fn merge_questions(
+    keep_id: String,
+    duplicate_ids: Vec<String>,
+    app: AppHandle,
+) -> Result<MergeResult, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = DuplicatesRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.merge(&keep_id, &duplicate_ids)).await
+}
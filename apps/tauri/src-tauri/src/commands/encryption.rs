@@ -0,0 +1,50 @@
+use crate::commands::backups::backup_retention;
+use crate::database::models::DatabaseStatus;
+use crate::database::{run_blocking, LazyDatabase};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn get_database_status(app: AppHandle) -> Result<DatabaseStatus, AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    run_blocking(move || db.status()).await
+}
+
+#[tauri::command]
+pub async fn unlock_database(passphrase: String, app: AppHandle) -> Result<(), AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    let retention = backup_retention(&app);
+    run_blocking(move || {
+        // The startup backup waits until the database is first opened
+        if db.unlock(&passphrase)? {
+            if let Err(e) = db.create_backup("startup", retention) {
+                eprintln!("Startup backup failed: {}", e);
+            }
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn enable_encryption(passphrase: String, app: AppHandle) -> Result<(), AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    run_blocking(move || db.enable_encryption(&passphrase)).await
+}
+
+#[tauri::command]
+pub async fn change_passphrase(
+    current_passphrase: String,
+    new_passphrase: String,
+    app: AppHandle,
+) -> Result<(), AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    run_blocking(move || db.change_passphrase(&current_passphrase, &new_passphrase)).await
+}
+
+#[tauri::command]
+pub async fn disable_encryption(passphrase: String, app: AppHandle) -> Result<(), AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    run_blocking(move || db.disable_encryption(&passphrase)).await
+}
@@ -1,6 +1,7 @@
-use crate::database::LazyDatabase;
-use crate::database::models::{CreateQuestionDto, CreateTopicDto};
-use crate::database::repository::{LazyQuestionsRepository, LazyTopicsRepository};
+use crate::database::{run_blocking, LazyDatabase};
+use crate::database::models::{CreateQuestionDto, CreateTopicDto, Difficulty};
+use crate::database::repository::{difficulty_repo, LazyQuestionsRepository, LazyTopicsRepository};
+use crate::error::AppError;
 use crate::utils::markdown_parser::{parse_markdown_file, ParsedTopic};
 use std::sync::Arc;
 use tauri::{AppHandle, Manager};
@@ -27,6 +28,9 @@ pub struct ImportResult {
     pub questions_imported: usize,
     pub message: String,
     pub errors: Vec<String>,
+    /// Questions imported with a change, such as an unknown difficulty
+    /// replaced by the default level
+    pub warnings: Vec<String>,
     pub topics_details: Vec<TopicImportDetail>,
 }
 
@@ -34,27 +38,33 @@ pub struct ImportResult {
 pub async fn import_from_markdown(
     content: String,
     app: AppHandle,
-) -> Result<ImportResult, String> {
-    let db = app.state::<Arc<LazyDatabase>>();
+) -> Result<ImportResult, AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    run_blocking(move || import_markdown(db, &content)).await
+}
 
+fn import_markdown(db: Arc<LazyDatabase>, content: &str) -> Result<ImportResult, AppError> {
     // Parse the markdown
-    let parsed_topics = parse_markdown_file(&content)
-        .map_err(|e| format!("Failed to parse markdown: {}", e))?;
+    let parsed_topics = parse_markdown_file(content)
+        .map_err(|e| {
+            AppError::validation(format!("Failed to parse markdown: {}", e)).with_field("content")
+        })?;
 
     let mut topics_imported = 0;
     let mut questions_imported = 0;
     let mut errors = Vec::new();
+    let mut warnings = Vec::new();
     let mut topics_details = Vec::new();
 
     for parsed_topic in parsed_topics {
-        match import_topic(Arc::clone(db.inner()), parsed_topic) {
+        match import_topic(Arc::clone(&db), parsed_topic, &mut warnings) {
             Ok((topic_count, question_count, detail)) => {
                 topics_imported += topic_count;
                 questions_imported += question_count;
                 topics_details.push(detail);
             }
             Err(e) => {
-                errors.push(e);
+                errors.push(e.message);
             }
         }
     }
@@ -77,6 +87,7 @@ pub async fn import_from_markdown(
             )
         },
         errors,
+        warnings,
         topics_details,
     })
 }
@@ -84,10 +95,38 @@ pub async fn import_from_markdown(
 fn import_topic(
     db: Arc<LazyDatabase>,
     parsed_topic: ParsedTopic,
-) -> Result<(usize, usize, TopicImportDetail), String> {
+    warnings: &mut Vec<String>,
+) -> Result<(usize, usize, TopicImportDetail), AppError> {
     let topics_repo = LazyTopicsRepository::new(Arc::clone(&db));
     let questions_repo = LazyQuestionsRepository::new(Arc::clone(&db));
 
+    // Settle difficulties before the topic exists: a question rejected part
+    // way through would leave the topic half imported. A hand-written file
+    // may misspell a level, so unknown ones fall back to the default.
+    let mut difficulties = Vec::with_capacity(parsed_topic.questions.len());
+    {
+        let conn = db.read_connection()?;
+        for parsed_question in &parsed_topic.questions {
+            let Some(name) = parsed_question.difficulty.as_deref() else {
+                difficulties.push(Difficulty::default());
+                continue;
+            };
+            let difficulty = Difficulty::parse(name);
+            if difficulty_repo::rank_of(&conn, &difficulty)?.is_some() {
+                difficulties.push(difficulty);
+            } else {
+                warnings.push(format!(
+                    "Question {} in topic '{}': unknown difficulty \"{}\", imported as {}",
+                    parsed_question.question_number,
+                    parsed_topic.name,
+                    name,
+                    Difficulty::default()
+                ));
+                difficulties.push(Difficulty::default());
+            }
+        }
+    }
+
     // Generate a slug from the topic name
     let slug = generate_slug(&parsed_topic.name);
 
@@ -98,13 +137,13 @@ fn import_topic(
         icon: "📚".to_string(),
         color: "#3B82F6".to_string(),
         slug,
-        subtopics: None,
+        parent_id: None,
         order: 0,
     };
 
     let topic = topics_repo
         .create(topic_dto)
-        .map_err(|e| format!("Failed to create topic '{}': {}", parsed_topic.name, e))?;
+        .map_err(|e| e.context(format!("Failed to create topic '{}'", parsed_topic.name)))?;
 
     let topic_id = topic.id;
 
@@ -112,17 +151,16 @@ fn import_topic(
     let mut question_count = 0;
     let mut question_details = Vec::new();
 
-    for parsed_question in parsed_topic.questions {
+    for (parsed_question, difficulty) in parsed_topic.questions.into_iter().zip(difficulties) {
         let question_dto = CreateQuestionDto {
             topic_id: topic_id.clone(),
-            subtopic: None,
             question_number: parsed_question.question_number,
             question: parsed_question.question.clone(),
             answer: crate::database::models::Answer {
                 markdown: parsed_question.answer.markdown.clone(),
             },
             tags: vec![parsed_topic.name.clone()],
-            difficulty: "intermediate".to_string(),
+            difficulty,
             order: parsed_question.question_number,
         };
 
@@ -136,10 +174,10 @@ fn import_topic(
                 });
             }
             Err(e) => {
-                return Err(format!(
-                    "Failed to create question {} in topic '{}': {}",
-                    parsed_question.question_number, parsed_topic.name, e
-                ));
+                return Err(e.context(format!(
+                    "Failed to create question {} in topic '{}'",
+                    parsed_question.question_number, parsed_topic.name
+                )));
             }
         }
     }
@@ -183,4 +221,33 @@ mod tests {
         assert_eq!(generate_slug("Spring Boot"), "spring-boot");
         assert_eq!(generate_slug("SQL & Databases"), "sql-_-databases");
     }
+
+    #[test]
+    fn test_import_topic_with_unknown_difficulty() {
+        let db = crate::database::repository::test_fixtures::database();
+        let content = "## Rust\n\n### 1. What is a trait?\n**Difficulty:** Advanced\n\n**Answer:**\nShared behaviour.\n\n### 2. What is a crate?\n**Difficulty:** Hardd\n\n**Answer:**\nA compilation unit.\n";
+        let parsed = parse_markdown_file(content).unwrap().remove(0);
+
+        let mut warnings = Vec::new();
+        let (topics, questions, detail) =
+            import_topic(Arc::clone(&db), parsed, &mut warnings).unwrap();
+        assert_eq!((topics, questions), (1, 2));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Question 2 in topic 'Rust'"));
+
+        let repo = LazyQuestionsRepository::new(Arc::clone(&db));
+        let difficulty = |i: usize| {
+            repo.get_by_id(&detail.questions[i].question_id)
+                .unwrap()
+                .unwrap()
+                .difficulty
+        };
+        assert_eq!(difficulty(0), Difficulty::Advanced);
+        assert_eq!(difficulty(1), Difficulty::default());
+        // The typo doesn't become a level
+        let conn = db.read_connection().unwrap();
+        assert!(difficulty_repo::rank_of(&conn, &Difficulty::parse("hardd"))
+            .unwrap()
+            .is_none());
+    }
 }
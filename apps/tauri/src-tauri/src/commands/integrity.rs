@@ -0,0 +1,27 @@
+use crate::commands::backups::backup_retention;
+use crate::database::models::{IntegrityReport, RepairResult};
+use crate::database::repository::IntegrityRepository;
+use crate::database::{run_blocking, LazyDatabase};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn check_database(app: AppHandle) -> Result<IntegrityReport, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = IntegrityRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.check()).await
+}
+
+#[tauri::command]
+pub async fn repair_database(app: AppHandle) -> Result<RepairResult, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = IntegrityRepository::new(Arc::clone(db.inner()));
+    let db = Arc::clone(db.inner());
+    let retention = backup_retention(&app);
+    run_blocking(move || {
+        db.create_backup("pre-repair", retention)?;
+        repo.repair()
+    })
+    .await
+}
@@ -0,0 +1,40 @@
+use crate::database::models::{LinkType, QuestionLinks};
+use crate::database::repository::LinksRepository;
+use crate::database::{run_blocking, LazyDatabase};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn add_question_link(
+    source_id: String,
+    target_id: String,
+    link_type: LinkType,
+    app: AppHandle,
+) -> Result<(), AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = LinksRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.add(&source_id, &target_id, link_type)).await
+}
+
+#[tauri::command]
+pub async fn remove_question_link(
+    source_id: String,
+    target_id: String,
+    link_type: LinkType,
+    app: AppHandle,
+) -> Result<bool, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = LinksRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.remove(&source_id, &target_id, link_type)).await
+}
+
+#[tauri::command]
+pub async fn get_question_links(
+    question_id: String,
+    app: AppHandle,
+) -> Result<QuestionLinks, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = LinksRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.get_for_question(&question_id)).await
+}
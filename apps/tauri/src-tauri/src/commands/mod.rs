@@ -1,13 +1,43 @@
+pub mod annotations;
+pub mod attachments;
+pub mod backups;
+pub mod bulk;
 pub mod data_management;
+pub mod difficulty;
+pub mod duplicates;
+pub mod encryption;
+pub mod import;
+pub mod integrity;
+pub mod links;
 pub mod progress;
 pub mod query;
 pub mod questions;
 pub mod quiz;
+pub mod revisions;
+pub mod saved_searches;
+pub mod tags;
 pub mod topics;
+pub mod trash;
+pub mod workspaces;
 
+pub use annotations::*;
+pub use attachments::*;
+pub use backups::*;
+pub use bulk::*;
 pub use data_management::*;
+pub use difficulty::*;
+pub use duplicates::*;
+pub use encryption::*;
+pub use import::*;
+pub use integrity::*;
+pub use links::*;
 pub use progress::*;
 pub use query::*;
 pub use questions::*;
 pub use quiz::*;
+pub use revisions::*;
+pub use saved_searches::*;
+pub use tags::*;
 pub use topics::*;
+pub use trash::*;
+pub use workspaces::*;
@@ -1,28 +1,39 @@
-use crate::database::LazyDatabase;
-use crate::database::models::{QuestionProgress, UpdateProgressDto, ProgressStatistics};
+use crate::database::{run_blocking, LazyDatabase};
+use crate::database::models::{Page, PageRequest, QuestionProgress, UpdateProgressDto, ProgressStatistics};
 use crate::database::repository::ProgressRepository;
+use crate::error::AppError;
 use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 
 #[tauri::command]
-pub async fn get_all_progress(app: AppHandle) -> Result<Vec<QuestionProgress>, String> {
+pub async fn get_all_progress(app: AppHandle) -> Result<Vec<QuestionProgress>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = ProgressRepository::new(Arc::clone(db.inner()));
-    repo.get_all()
+    run_blocking(move || repo.get_all()).await
 }
 
 #[tauri::command]
-pub async fn get_progress_by_question(question_id: String, app: AppHandle) -> Result<Option<QuestionProgress>, String> {
+pub async fn get_progress_page(
+    page: PageRequest,
+    app: AppHandle,
+) -> Result<Page<QuestionProgress>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = ProgressRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.get_page(&page)).await
+}
+
+#[tauri::command]
+pub async fn get_progress_by_question(question_id: String, app: AppHandle) -> Result<Option<QuestionProgress>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = ProgressRepository::new(Arc::clone(db.inner()));
-    repo.get_by_question_id(&question_id)
+    run_blocking(move || repo.get_by_question_id(&question_id)).await
 }
 
 #[tauri::command]
-pub async fn get_progress_by_topic(topic_id: String, app: AppHandle) -> Result<Vec<QuestionProgress>, String> {
+pub async fn get_progress_by_topic(topic_id: String, app: AppHandle) -> Result<Vec<QuestionProgress>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = ProgressRepository::new(Arc::clone(db.inner()));
-    repo.get_by_topic(&topic_id)
+    run_blocking(move || repo.get_by_topic(&topic_id)).await
 }
 
 #[tauri::command]
@@ -30,36 +41,45 @@ pub async fn update_question_progress(
     question_id: String,
     dto: UpdateProgressDto,
     app: AppHandle,
-) -> Result<QuestionProgress, String> {
+) -> Result<QuestionProgress, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = ProgressRepository::new(Arc::clone(db.inner()));
-    repo.update(&question_id, dto)
+    run_blocking(move || repo.update(&question_id, dto)).await
 }
 
 #[tauri::command]
-pub async fn reset_question_progress(question_id: String, app: AppHandle) -> Result<bool, String> {
+pub async fn reset_question_progress(question_id: String, app: AppHandle) -> Result<bool, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = ProgressRepository::new(Arc::clone(db.inner()));
-    repo.reset(&question_id)
+    run_blocking(move || repo.reset(&question_id)).await
 }
 
 #[tauri::command]
-pub async fn get_progress_statistics(app: AppHandle) -> Result<ProgressStatistics, String> {
+pub async fn get_progress_statistics(
+    topic_id: Option<String>,
+    include_archived: Option<bool>,
+    app: AppHandle,
+) -> Result<ProgressStatistics, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = ProgressRepository::new(Arc::clone(db.inner()));
-    repo.get_statistics()
+    let include_archived = include_archived.unwrap_or(false);
+    run_blocking(move || repo.get_statistics(topic_id.as_deref(), include_archived)).await
 }
 
 #[tauri::command]
-pub async fn get_questions_due_for_review(app: AppHandle) -> Result<Vec<QuestionProgress>, String> {
+pub async fn get_questions_due_for_review(
+    include_archived: Option<bool>,
+    app: AppHandle,
+) -> Result<Vec<QuestionProgress>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = ProgressRepository::new(Arc::clone(db.inner()));
-    repo.get_questions_due_for_review()
+    let include_archived = include_archived.unwrap_or(false);
+    run_blocking(move || repo.get_questions_due_for_review(include_archived)).await
 }
 
 #[tauri::command]
-pub async fn ensure_progress_for_all_questions(app: AppHandle) -> Result<usize, String> {
+pub async fn ensure_progress_for_all_questions(app: AppHandle) -> Result<usize, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = ProgressRepository::new(Arc::clone(db.inner()));
-    repo.ensure_progress_for_all_questions()
+    run_blocking(move || repo.ensure_progress_for_all_questions()).await
 }
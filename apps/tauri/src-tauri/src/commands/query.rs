@@ -1,5 +1,7 @@
-use crate::database::LazyDatabase;
-use crate::database::repository::{LazyQuestionsRepository, LazyTopicsRepository};
+use crate::database::{run_blocking, LazyDatabase};
+use crate::database::models::{Page, PageRequest, QuestionFilter, QuestionWithProgress};
+use crate::database::repository::{FilterRepository, LazyQuestionsRepository, LazyTopicsRepository};
+use crate::error::AppError;
 use jql_runner::runner;
 use std::sync::Arc;
 use tauri::{AppHandle, Manager};
@@ -9,20 +11,20 @@ use tauri::{AppHandle, Manager};
 /// - '.questions' - Get all questions
 /// - '.topics' - Get all topics
 /// - '.questions[0]' - Get first question
-/// NOTE: This command will be deprecated in favor of more powerful query commands
+/// NOTE: Loads every question into memory; prefer `filter_questions`
 #[tauri::command]
 pub async fn query_database(
     query: String,
     app: AppHandle,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
 
     // Build a temporary JSON structure for jql to query
     let topics_repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
     let questions_repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
 
-    let topics = topics_repo.get_all()?;
-    let all_questions = questions_repo.get_all()?;
+    let (topics, all_questions) =
+        run_blocking(move || Ok((topics_repo.get_all(true)?, questions_repo.get_all()?))).await?;
 
     // Create a temporary v1-like structure
     #[derive(serde::Serialize)]
@@ -40,63 +42,80 @@ pub async fn query_database(
 
     // Serialize to JSON Value
     let json_value = serde_json::to_value(&temp_data)
-        .map_err(|e| format!("Failed to serialize database: {}", e))?;
+        .map_err(|e| AppError::from(e).context("Failed to serialize database"))?;
 
     // Execute the jql query using raw function (handles parsing internally)
     let result = runner::raw(&query, &json_value)
-        .map_err(|e| format!("JQL execution error: {}", e))?;
+        .map_err(|e| AppError::validation(format!("JQL execution error: {}", e)).with_field("query"))?;
 
     // Convert result to string
     let result_str = serde_json::to_string(&result)
-        .map_err(|e| format!("Failed to serialize result: {}", e))?;
+        .map_err(|e| AppError::from(e).context("Failed to serialize result"))?;
 
     Ok(result_str)
 }
 
-/// Search questions by keyword
+/// Questions matching `filter` with their progress, one page at a time
+#[tauri::command]
+pub async fn filter_questions(
+    filter: QuestionFilter,
+    page: PageRequest,
+    app: AppHandle,
+) -> Result<Page<QuestionWithProgress>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = FilterRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.filter(&filter, &page)).await
+}
+
+/// Search questions with the query language in `utils::search_query`, e.g.
+/// `tag:rust -tag:legacy topic:"Spring Boot" lifetimes`. Text matches are
+/// ranked by relevance with highlighted snippets.
 #[tauri::command]
 pub async fn search_questions(
     keyword: String,
     app: AppHandle,
-) -> Result<Vec<crate::database::models::Question>, String> {
+) -> Result<Vec<crate::database::models::QuestionSearchResult>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
-    repo.search(&keyword)
+    run_blocking(move || repo.search(&keyword)).await
 }
 
-/// Search topics by keyword
+/// Search topics by name/description and by the content of their questions
 #[tauri::command]
 pub async fn search_topics(
     keyword: String,
     app: AppHandle,
-) -> Result<Vec<crate::database::models::Topic>, String> {
+) -> Result<Vec<crate::database::models::Topic>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
-    repo.search(&keyword)
+    run_blocking(move || repo.search(&keyword)).await
 }
 
 /// Get topic statistics with question counts
 #[tauri::command]
 pub async fn get_topic_stats(
     app: AppHandle,
-) -> Result<Vec<TopicStats>, String> {
+) -> Result<Vec<TopicStats>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let topics_repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
     let questions_repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
 
-    let topics = topics_repo.get_all()?;
-    let mut stats = Vec::new();
-
-    for topic in topics {
-        let question_count = questions_repo.count_by_topic(&topic.id).unwrap_or(0);
-        stats.push(TopicStats {
-            id: topic.id,
-            name: topic.name,
-            question_count,
-        });
-    }
-
-    Ok(stats)
+    run_blocking(move || {
+        let topics = topics_repo.get_all(true)?;
+        let mut stats = Vec::new();
+
+        for topic in topics {
+            let question_count = questions_repo.count_by_topic(&topic.id).unwrap_or(0);
+            stats.push(TopicStats {
+                id: topic.id,
+                name: topic.name,
+                question_count,
+            });
+        }
+
+        Ok(stats)
+    })
+    .await
 }
 
 #[derive(serde::Serialize)]
@@ -1,49 +1,80 @@
-use crate::database::LazyDatabase;
-use crate::database::models::{CreateQuestionDto, Question, UpdateQuestionDto};
+use crate::database::{run_blocking, LazyDatabase};
+use crate::database::models::{CreateQuestionDto, Page, PageRequest, Question, UpdateQuestionDto};
 use crate::database::repository::LazyQuestionsRepository;
+use crate::error::AppError;
 use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 
 #[tauri::command]
-pub async fn get_questions(app: AppHandle) -> Result<Vec<Question>, String> {
+pub async fn get_questions(app: AppHandle) -> Result<Vec<Question>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
-    repo.get_all()
+    run_blocking(move || repo.get_all()).await
 }
 
 #[tauri::command]
-pub async fn get_question_by_id(id: String, app: AppHandle) -> Result<Option<Question>, String> {
+pub async fn get_question_by_id(id: String, app: AppHandle) -> Result<Option<Question>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
-    repo.get_by_id(&id)
+    run_blocking(move || repo.get_by_id(&id)).await
 }
 
 #[tauri::command]
-pub async fn get_questions_by_topic(topic_id: String, app: AppHandle) -> Result<Vec<Question>, String> {
+pub async fn get_questions_by_topic(topic_id: String, app: AppHandle) -> Result<Vec<Question>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
-    repo.get_by_topic_id(&topic_id)
+    run_blocking(move || repo.get_by_topic_id(&topic_id)).await
 }
 
+/// Page through questions, of one topic if `topic_id` is given
 #[tauri::command]
-pub async fn create_question(dto: CreateQuestionDto, app: AppHandle) -> Result<String, String> {
+pub async fn get_questions_page(
+    topic_id: Option<String>,
+    page: PageRequest,
+    app: AppHandle,
+) -> Result<Page<Question>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
-    let question = repo.create(dto)?;
+    run_blocking(move || repo.get_page(topic_id.as_deref(), &page)).await
+}
+
+#[tauri::command]
+pub async fn create_question(dto: CreateQuestionDto, app: AppHandle) -> Result<String, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
+    let question = run_blocking(move || repo.create(dto)).await?;
     Ok(question.id)
 }
 
 #[tauri::command]
-pub async fn update_question(id: String, dto: UpdateQuestionDto, app: AppHandle) -> Result<bool, String> {
+pub async fn update_question(id: String, dto: UpdateQuestionDto, app: AppHandle) -> Result<bool, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
-    let result = repo.update(&id, dto)?;
+    let result = run_blocking(move || repo.update(&id, dto)).await?;
     Ok(result.is_some())
 }
 
 #[tauri::command]
-pub async fn delete_question(id: String, app: AppHandle) -> Result<bool, String> {
+pub async fn delete_question(id: String, app: AppHandle) -> Result<bool, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.delete(&id)).await
+}
+
+#[tauri::command]
+pub async fn reorder_questions(
+    topic_id: String,
+    ids: Vec<String>,
+    app: AppHandle,
+) -> Result<usize, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.reorder(&topic_id, &ids)).await
+}
+
+#[tauri::command]
+pub async fn renumber_topic(topic_id: String, app: AppHandle) -> Result<usize, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = LazyQuestionsRepository::new(Arc::clone(db.inner()));
-    repo.delete(&id)
+    run_blocking(move || repo.renumber_topic(&topic_id)).await
 }
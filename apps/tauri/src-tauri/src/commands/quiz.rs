@@ -1,6 +1,7 @@
-use crate::database::LazyDatabase;
-use crate::database::models::{QuizSession, CreateQuizSessionDto, QuizResult};
+use crate::database::{run_blocking, LazyDatabase};
+use crate::database::models::{QuizSession, CreateQuizSessionDto, Page, PageRequest, QuizResult};
 use crate::database::repository::QuizSessionRepository;
+use crate::error::AppError;
 use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 
@@ -8,27 +9,27 @@ use tauri::{AppHandle, Manager};
 pub async fn create_quiz_session(
     dto: CreateQuizSessionDto,
     app: AppHandle,
-) -> Result<QuizSession, String> {
+) -> Result<QuizSession, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = QuizSessionRepository::new(Arc::clone(db.inner()));
-    repo.create(dto)
+    run_blocking(move || repo.create(dto)).await
 }
 
 #[tauri::command]
 pub async fn get_quiz_session(
     session_id: String,
     app: AppHandle,
-) -> Result<Option<QuizSession>, String> {
+) -> Result<Option<QuizSession>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = QuizSessionRepository::new(Arc::clone(db.inner()));
-    repo.get_by_id(&session_id)
+    run_blocking(move || repo.get_by_id(&session_id)).await
 }
 
 #[tauri::command]
-pub async fn get_active_quiz_session(app: AppHandle) -> Result<Option<QuizSession>, String> {
+pub async fn get_active_quiz_session(app: AppHandle) -> Result<Option<QuizSession>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = QuizSessionRepository::new(Arc::clone(db.inner()));
-    repo.get_active()
+    run_blocking(move || repo.get_active()).await
 }
 
 #[tauri::command]
@@ -36,28 +37,38 @@ pub async fn submit_quiz_answer(
     session_id: String,
     result: QuizResult,
     app: AppHandle,
-) -> Result<QuizSession, String> {
+) -> Result<QuizSession, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = QuizSessionRepository::new(Arc::clone(db.inner()));
-    repo.submit_result(&session_id, result)
+    run_blocking(move || repo.submit_result(&session_id, result)).await
 }
 
 #[tauri::command]
 pub async fn complete_quiz_session(
     session_id: String,
     app: AppHandle,
-) -> Result<QuizSession, String> {
+) -> Result<QuizSession, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = QuizSessionRepository::new(Arc::clone(db.inner()));
-    repo.complete(&session_id)
+    run_blocking(move || repo.complete(&session_id)).await
 }
 
 #[tauri::command]
 pub async fn get_quiz_history(
     limit: Option<i32>,
     app: AppHandle,
-) -> Result<Vec<QuizSession>, String> {
+) -> Result<Vec<QuizSession>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = QuizSessionRepository::new(Arc::clone(db.inner()));
-    repo.get_history(limit)
+    run_blocking(move || repo.get_history(limit)).await
+}
+
+#[tauri::command]
+pub async fn get_quiz_history_page(
+    page: PageRequest,
+    app: AppHandle,
+) -> Result<Page<QuizSession>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = QuizSessionRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.get_history_page(&page)).await
 }
@@ -0,0 +1,39 @@
+use crate::database::models::{Question, QuestionRevision, RevisionDiff};
+use crate::database::repository::RevisionsRepository;
+use crate::database::{run_blocking, LazyDatabase};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn get_question_revisions(
+    question_id: String,
+    app: AppHandle,
+) -> Result<Vec<QuestionRevision>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = RevisionsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.list(&question_id)).await
+}
+
+#[tauri::command]
+pub async fn diff_question_revisions(
+    question_id: String,
+    from_revision: i64,
+    to_revision: Option<i64>,
+    app: AppHandle,
+) -> Result<RevisionDiff, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = RevisionsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.diff(&question_id, from_revision, to_revision)).await
+}
+
+#[tauri::command]
+pub async fn revert_question(
+    question_id: String,
+    revision_id: i64,
+    app: AppHandle,
+) -> Result<Option<Question>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = RevisionsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.revert(&question_id, revision_id)).await
+}
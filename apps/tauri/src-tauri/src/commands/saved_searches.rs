@@ -0,0 +1,83 @@
+use crate::database::models::{
+    CreateSavedSearchDto, Page, PageRequest, QuestionWithProgress, QuizSession, QuizSessionType,
+    SavedSearch, UpdateSavedSearchDto,
+};
+use crate::database::repository::{QuizSessionRepository, SavedSearchesRepository};
+use crate::database::{run_blocking, LazyDatabase};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn get_saved_searches(app: AppHandle) -> Result<Vec<SavedSearch>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = SavedSearchesRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.get_all()).await
+}
+
+#[tauri::command]
+pub async fn get_saved_search(id: String, app: AppHandle) -> Result<Option<SavedSearch>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = SavedSearchesRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.get_by_id(&id)).await
+}
+
+#[tauri::command]
+pub async fn create_saved_search(
+    dto: CreateSavedSearchDto,
+    app: AppHandle,
+) -> Result<SavedSearch, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = SavedSearchesRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.create(dto)).await
+}
+
+#[tauri::command]
+pub async fn update_saved_search(
+    id: String,
+    dto: UpdateSavedSearchDto,
+    app: AppHandle,
+) -> Result<Option<SavedSearch>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = SavedSearchesRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.update(&id, dto)).await
+}
+
+#[tauri::command]
+pub async fn delete_saved_search(id: String, app: AppHandle) -> Result<bool, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = SavedSearchesRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.delete(&id)).await
+}
+
+/// Questions currently in a saved search, with their progress
+#[tauri::command]
+pub async fn get_saved_search_members(
+    id: String,
+    page: PageRequest,
+    app: AppHandle,
+) -> Result<Page<QuestionWithProgress>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = SavedSearchesRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.members(&id, &page)).await
+}
+
+/// Start a quiz over the questions currently in a saved search
+#[tauri::command]
+pub async fn start_saved_search_quiz(
+    id: String,
+    session_type: QuizSessionType,
+    max_questions: Option<i32>,
+    app: AppHandle,
+) -> Result<QuizSession, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = SavedSearchesRepository::new(Arc::clone(db.inner()));
+    let quiz_repo = QuizSessionRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || {
+        let search = repo
+            .get_by_id(&id)?
+            .ok_or_else(|| AppError::not_found("saved_search", id))?;
+        quiz_repo.create_from_filter(&search.filter, session_type, max_questions)
+    })
+    .await
+}
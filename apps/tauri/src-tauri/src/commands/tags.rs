@@ -0,0 +1,45 @@
+use crate::database::{run_blocking, LazyDatabase};
+use crate::database::models::Tag;
+use crate::database::repository::TagsRepository;
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn get_tags(app: AppHandle) -> Result<Vec<Tag>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = TagsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.get_all()).await
+}
+
+#[tauri::command]
+pub async fn autocomplete_tags(
+    prefix: String,
+    limit: Option<i32>,
+    app: AppHandle,
+) -> Result<Vec<Tag>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = TagsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.autocomplete(&prefix, limit)).await
+}
+
+#[tauri::command]
+pub async fn rename_tag(old_name: String, new_name: String, app: AppHandle) -> Result<usize, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = TagsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.rename(&old_name, &new_name)).await
+}
+
+#[tauri::command]
+pub async fn merge_tags(source: String, target: String, app: AppHandle) -> Result<usize, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = TagsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.merge(&source, &target)).await
+}
+
+#[tauri::command]
+pub async fn delete_tag(name: String, app: AppHandle) -> Result<usize, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = TagsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.delete(&name)).await
+}
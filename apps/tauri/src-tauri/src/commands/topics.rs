@@ -1,42 +1,103 @@
-use crate::database::LazyDatabase;
-use crate::database::models::{CreateTopicDto, Topic, UpdateTopicDto};
+use crate::database::{run_blocking, LazyDatabase};
+use crate::database::models::{CreateTopicDto, Page, PageRequest, Topic, TopicNode, UpdateTopicDto};
 use crate::database::repository::LazyTopicsRepository;
+use crate::error::AppError;
 use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 
 #[tauri::command]
-pub async fn get_topics(app: AppHandle) -> Result<Vec<Topic>, String> {
+pub async fn get_topics(
+    include_archived: Option<bool>,
+    app: AppHandle,
+) -> Result<Vec<Topic>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
-    repo.get_all()
+    run_blocking(move || repo.get_all(include_archived.unwrap_or(false))).await
 }
 
 #[tauri::command]
-pub async fn get_topic_by_id(id: String, app: AppHandle) -> Result<Option<Topic>, String> {
+pub async fn get_topics_page(
+    include_archived: Option<bool>,
+    page: PageRequest,
+    app: AppHandle,
+) -> Result<Page<Topic>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
-    repo.get_by_id(&id)
+    run_blocking(move || repo.get_page(include_archived.unwrap_or(false), &page)).await
 }
 
 #[tauri::command]
-pub async fn create_topic(dto: CreateTopicDto, app: AppHandle) -> Result<String, String> {
+pub async fn get_topic_tree(
+    include_archived: Option<bool>,
+    app: AppHandle,
+) -> Result<Vec<TopicNode>, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
-    let topic = repo.create(dto)?;
+    run_blocking(move || repo.get_tree(include_archived.unwrap_or(false))).await
+}
+
+#[tauri::command]
+pub async fn get_topic_by_id(id: String, app: AppHandle) -> Result<Option<Topic>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.get_by_id(&id)).await
+}
+
+#[tauri::command]
+pub async fn create_topic(dto: CreateTopicDto, app: AppHandle) -> Result<String, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
+    let topic = run_blocking(move || repo.create(dto)).await?;
     Ok(topic.id)
 }
 
 #[tauri::command]
-pub async fn update_topic(id: String, dto: UpdateTopicDto, app: AppHandle) -> Result<bool, String> {
+pub async fn update_topic(id: String, dto: UpdateTopicDto, app: AppHandle) -> Result<bool, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
+    let result = run_blocking(move || repo.update(&id, dto)).await?;
+    Ok(result.is_some())
+}
+
+#[tauri::command]
+pub async fn move_topic(
+    id: String,
+    parent_id: Option<String>,
+    app: AppHandle,
+) -> Result<bool, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
-    let result = repo.update(&id, dto)?;
+    let result = run_blocking(move || repo.move_topic(&id, parent_id.as_deref())).await?;
     Ok(result.is_some())
 }
 
 #[tauri::command]
-pub async fn delete_topic(id: String, app: AppHandle) -> Result<bool, String> {
+pub async fn archive_topic(id: String, app: AppHandle) -> Result<bool, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
+    let result = run_blocking(move || repo.set_archived(&id, true)).await?;
+    Ok(result.is_some())
+}
+
+#[tauri::command]
+pub async fn unarchive_topic(id: String, app: AppHandle) -> Result<bool, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
+    let result = run_blocking(move || repo.set_archived(&id, false)).await?;
+    Ok(result.is_some())
+}
+
+/// Set the order of sibling topics; `ids` lists every topic under one parent
+#[tauri::command]
+pub async fn reorder_topics(ids: Vec<String>, app: AppHandle) -> Result<usize, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.reorder(&ids)).await
+}
+
+#[tauri::command]
+pub async fn delete_topic(id: String, app: AppHandle) -> Result<bool, AppError> {
     let db = app.state::<Arc<LazyDatabase>>();
     let repo = LazyTopicsRepository::new(Arc::clone(db.inner()));
-    repo.delete(&id)
+    run_blocking(move || repo.delete(&id)).await
 }
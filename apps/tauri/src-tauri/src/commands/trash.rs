@@ -0,0 +1,62 @@
+use crate::database::models::{TrashedQuestion, TrashedTopic};
+use crate::database::repository::TrashRepository;
+use crate::commands::backups::backup_retention;
+use crate::database::{run_blocking, LazyDatabase};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn get_trashed_topics(app: AppHandle) -> Result<Vec<TrashedTopic>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = TrashRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.list_topics()).await
+}
+
+#[tauri::command]
+pub async fn get_trashed_questions(app: AppHandle) -> Result<Vec<TrashedQuestion>, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = TrashRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.list_questions()).await
+}
+
+#[tauri::command]
+pub async fn restore_topic(id: String, app: AppHandle) -> Result<usize, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = TrashRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.restore_topic(&id)).await
+}
+
+#[tauri::command]
+pub async fn restore_question(id: String, app: AppHandle) -> Result<bool, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = TrashRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.restore_question(&id)).await
+}
+
+#[tauri::command]
+pub async fn purge_topic(id: String, app: AppHandle) -> Result<bool, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = TrashRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.purge_topic(&id)).await
+}
+
+#[tauri::command]
+pub async fn purge_question(id: String, app: AppHandle) -> Result<bool, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = TrashRepository::new(Arc::clone(db.inner()));
+    run_blocking(move || repo.purge_question(&id)).await
+}
+
+#[tauri::command]
+pub async fn empty_trash(app: AppHandle) -> Result<usize, AppError> {
+    let db = app.state::<Arc<LazyDatabase>>();
+    let repo = TrashRepository::new(Arc::clone(db.inner()));
+    let db = Arc::clone(db.inner());
+    let retention = backup_retention(&app);
+    run_blocking(move || {
+        db.create_backup("pre-empty-trash", retention)?;
+        repo.empty_trash()
+    })
+    .await
+}
@@ -0,0 +1,57 @@
+use crate::auth::forget_store;
+use crate::commands::backups::backup_retention;
+use crate::database::models::{DatabaseStatus, Workspace};
+use crate::database::{run_blocking, LazyDatabase};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn get_workspaces(app: AppHandle) -> Result<Vec<Workspace>, AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    run_blocking(move || Ok(db.list_workspaces())).await
+}
+
+#[tauri::command]
+pub async fn create_workspace(name: String, app: AppHandle) -> Result<Workspace, AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    run_blocking(move || db.create_workspace(&name)).await
+}
+
+#[tauri::command]
+pub async fn rename_workspace(
+    id: String,
+    name: String,
+    app: AppHandle,
+) -> Result<Workspace, AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    run_blocking(move || db.rename_workspace(&id, &name)).await
+}
+
+#[tauri::command]
+pub async fn delete_workspace(id: String, app: AppHandle) -> Result<(), AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    let dir = db.workspace_dir(&id);
+    run_blocking(move || db.delete_workspace(&id)).await?;
+    // Only once the delete went through: a refused one keeps its login
+    forget_store(&app, &dir);
+    Ok(())
+}
+
+/// Returns whether the workspace just opened still needs its passphrase
+#[tauri::command]
+pub async fn switch_workspace(id: String, app: AppHandle) -> Result<DatabaseStatus, AppError> {
+    let db = Arc::clone(app.state::<Arc<LazyDatabase>>().inner());
+    let retention = backup_retention(&app);
+    run_blocking(move || {
+        let status = db.switch_workspace(&id)?;
+        // Same as at startup: an encrypted workspace is backed up on unlock
+        if !status.locked {
+            if let Err(e) = db.create_backup("startup", retention) {
+                eprintln!("Startup backup failed: {}", e);
+            }
+        }
+        Ok(status)
+    })
+    .await
+}
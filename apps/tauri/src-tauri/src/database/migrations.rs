@@ -0,0 +1,807 @@
+use rusqlite::{params, Connection, Transaction};
+use sha2::{Digest, Sha256};
+
+use crate::error::{AppError, ErrorCode};
+
+/// A single schema migration. Migrations are applied in ascending `version`
+/// order and each one runs inside the same transaction as the version bump.
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub up: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+/// All known migrations, in order. Never edit or reorder a migration that has
+/// shipped; add a new one with the next version number instead.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline schema",
+        up: baseline_schema,
+    },
+    Migration {
+        version: 2,
+        description: "full-text search index over questions",
+        up: questions_fts,
+    },
+    Migration {
+        version: 3,
+        description: "normalized tag tables",
+        up: normalized_tags,
+    },
+    Migration {
+        version: 4,
+        description: "sync tombstones for purged records",
+        up: sync_tombstones,
+    },
+    Migration {
+        version: 5,
+        description: "question revision history",
+        up: question_revisions,
+    },
+    Migration {
+        version: 6,
+        description: "question attachments",
+        up: attachments,
+    },
+    Migration {
+        version: 7,
+        description: "typed links between questions",
+        up: question_links,
+    },
+    Migration {
+        version: 8,
+        description: "personal annotations on questions",
+        up: annotations,
+    },
+    Migration {
+        version: 9,
+        description: "ordered difficulty levels",
+        up: difficulty_levels,
+    },
+    Migration {
+        version: 10,
+        description: "nested topics",
+        up: topic_hierarchy,
+    },
+    Migration {
+        version: 11,
+        description: "archived topics",
+        up: archived_topics,
+    },
+    Migration {
+        version: 12,
+        description: "saved searches",
+        up: saved_searches,
+    },
+    Migration {
+        version: 13,
+        description: "trash batches",
+        up: trash_batches,
+    },
+];
+
+/// Latest schema version this build knows how to handle
+pub fn latest_version() -> i32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// Read the schema version recorded in the database header
+pub fn current_version(conn: &Connection) -> rusqlite::Result<i32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Bring the database up to `latest_version()`.
+///
+/// Pending migrations are applied in a single transaction, so a failure leaves
+/// the database at its previous version. A database written by a newer app
+/// version is refused rather than risking data loss.
+pub fn run(conn: &mut Connection) -> Result<i32, AppError> {
+    let current = current_version(conn)
+        .map_err(|e| AppError::from(e).context("Failed to read schema version"))?;
+    let latest = latest_version();
+
+    if current > latest {
+        return Err(AppError::new(
+            ErrorCode::UnsupportedVersion,
+            format!(
+                "Database schema version {} is newer than this app supports ({}). Please update the app.",
+                current, latest
+            ),
+        ));
+    }
+
+    if current == latest {
+        return Ok(current);
+    }
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| AppError::from(e).context("Failed to start migration transaction"))?;
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        println!(
+            "Applying migration {}: {}",
+            migration.version, migration.description
+        );
+        (migration.up)(&tx).map_err(|e| {
+            AppError::from(e).context(format!(
+                "Migration {} ({}) failed",
+                migration.version, migration.description
+            ))
+        })?;
+        // PRAGMA does not accept bound parameters
+        tx.execute_batch(&format!("PRAGMA user_version = {}", migration.version))
+            .map_err(|e| AppError::from(e).context("Failed to record schema version"))?;
+    }
+
+    tx.commit()
+        .map_err(|e| AppError::from(e).context("Failed to commit migrations"))?;
+
+    Ok(latest)
+}
+
+/// Version 1: the tables as they existed before versioning was introduced.
+/// Uses `IF NOT EXISTS` so databases created by older builds adopt it as-is.
+fn baseline_schema(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS topics (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT,
+            slug TEXT NOT NULL,
+            icon TEXT,
+            color TEXT,
+            subtopics TEXT, -- JSON array
+            order_index INTEGER DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            sync_version INTEGER DEFAULT 1,
+            synced_at INTEGER,
+            deleted INTEGER DEFAULT 0,
+            deleted_at INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS questions (
+            id TEXT PRIMARY KEY,
+            topic_id TEXT NOT NULL,
+            subtopic TEXT,
+            question_number INTEGER NOT NULL,
+            question TEXT NOT NULL,
+            answer TEXT NOT NULL, -- JSON { markdown: ... }
+            tags TEXT, -- JSON array
+            difficulty TEXT,
+            order_index INTEGER DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            sync_version INTEGER DEFAULT 1,
+            synced_at INTEGER,
+            deleted INTEGER DEFAULT 0,
+            deleted_at INTEGER,
+            FOREIGN KEY (topic_id) REFERENCES topics(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS progress (
+            question_id TEXT PRIMARY KEY,
+            id TEXT,
+            topic_id TEXT NOT NULL,
+            status TEXT NOT NULL,
+            confidence_level INTEGER DEFAULT 0,
+            times_reviewed INTEGER DEFAULT 0,
+            times_correct INTEGER DEFAULT 0,
+            times_incorrect INTEGER DEFAULT 0,
+            last_reviewed_at TEXT,
+            next_review_at TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            sync_version INTEGER DEFAULT 1,
+            synced_at INTEGER,
+            deleted INTEGER DEFAULT 0,
+            deleted_at INTEGER,
+            FOREIGN KEY (question_id) REFERENCES questions(id) ON DELETE CASCADE,
+            FOREIGN KEY (topic_id) REFERENCES topics(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS quiz_sessions (
+            id TEXT PRIMARY KEY,
+            session_type TEXT NOT NULL,
+            topic_ids TEXT, -- JSON array
+            question_ids TEXT, -- JSON array
+            current_index INTEGER DEFAULT 0,
+            started_at TEXT NOT NULL,
+            completed_at TEXT,
+            results TEXT, -- JSON array of QuizResult
+            sync_version INTEGER DEFAULT 1,
+            synced_at INTEGER,
+            deleted INTEGER DEFAULT 0,
+            deleted_at INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS sync_metadata (
+            key TEXT PRIMARY KEY,
+            value TEXT
+        );",
+    )
+}
+
+/// Version 2: FTS5 index over question text, answer markdown, tags and subtopic.
+/// Triggers keep it in sync with every write path (repositories, import, sync pull),
+/// and soft-deleted questions are dropped from the index.
+fn questions_fts(tx: &Transaction) -> rusqlite::Result<()> {
+    // Answers and tags are stored as JSON; index their text, tolerating rows
+    // written with malformed JSON by older sync payloads.
+    let fts_values = |row: &str| {
+        format!(
+            "{row}.id,
+            {row}.question,
+            CASE WHEN json_valid({row}.answer) THEN json_extract({row}.answer, '$.markdown') ELSE {row}.answer END,
+            CASE WHEN json_valid({row}.tags) THEN (SELECT group_concat(value, ' ') FROM json_each({row}.tags)) ELSE {row}.tags END,
+            {row}.subtopic",
+            row = row
+        )
+    };
+
+    tx.execute_batch(&format!(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS questions_fts USING fts5(
+            question_id UNINDEXED,
+            question,
+            answer,
+            tags,
+            subtopic,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        INSERT INTO questions_fts (question_id, question, answer, tags, subtopic)
+            SELECT {questions} FROM questions WHERE deleted = 0 OR deleted IS NULL;
+
+        CREATE TRIGGER IF NOT EXISTS questions_fts_insert AFTER INSERT ON questions
+        WHEN new.deleted = 0 OR new.deleted IS NULL
+        BEGIN
+            INSERT INTO questions_fts (question_id, question, answer, tags, subtopic)
+                VALUES ({new});
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS questions_fts_update
+        AFTER UPDATE OF question, answer, tags, subtopic, deleted ON questions
+        BEGIN
+            DELETE FROM questions_fts WHERE question_id = old.id;
+            INSERT INTO questions_fts (question_id, question, answer, tags, subtopic)
+                SELECT {new} WHERE new.deleted = 0 OR new.deleted IS NULL;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS questions_fts_delete AFTER DELETE ON questions
+        BEGIN
+            DELETE FROM questions_fts WHERE question_id = old.id;
+        END;",
+        questions = fts_values("questions"),
+        new = fts_values("new"),
+    ))
+}
+
+/// Version 3: `tags` and `question_tags` tables derived from `questions.tags`.
+/// The JSON column stays the synced source of truth; triggers keep the join
+/// table in step with it so tag queries never have to parse every question.
+fn normalized_tags(tx: &Transaction) -> rusqlite::Result<()> {
+    // `from` lists any table the tags are read from ahead of json_each
+    let link_tags = |row: &str, from: &str| {
+        format!(
+            "INSERT OR IGNORE INTO tags (name, created_at)
+                SELECT DISTINCT trim(j.value), strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+                FROM {from}json_each(CASE WHEN json_valid({row}.tags) THEN {row}.tags ELSE '[]' END) j
+                WHERE j.type = 'text' AND trim(j.value) != '';
+            INSERT OR IGNORE INTO question_tags (question_id, tag_id)
+                SELECT {row}.id, t.id
+                FROM {from}json_each(CASE WHEN json_valid({row}.tags) THEN {row}.tags ELSE '[]' END) j
+                JOIN tags t ON t.name = trim(j.value)
+                WHERE j.type = 'text';",
+            row = row,
+            from = from
+        )
+    };
+
+    tx.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS question_tags (
+            question_id TEXT NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (question_id, tag_id),
+            FOREIGN KEY (question_id) REFERENCES questions(id) ON DELETE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_question_tags_tag ON question_tags(tag_id);
+
+        {backfill}
+
+        CREATE TRIGGER IF NOT EXISTS question_tags_insert AFTER INSERT ON questions
+        BEGIN
+            {new}
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS question_tags_update
+        AFTER UPDATE OF tags, deleted ON questions
+        BEGIN
+            DELETE FROM question_tags WHERE question_id = new.id;
+            {new}
+        END;",
+        backfill = link_tags("q", "questions q, "),
+        new = link_tags("new", ""),
+    ))
+}
+
+/// Version 4: remembers hard-deleted rows until their deletion has been pushed,
+/// so purging the trash doesn't lose the delete on other devices.
+fn sync_tombstones(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sync_tombstones (
+            table_name TEXT NOT NULL,
+            row_id TEXT NOT NULL,
+            sync_version INTEGER NOT NULL,
+            deleted_at INTEGER NOT NULL,
+            PRIMARY KEY (table_name, row_id)
+        );",
+    )
+}
+
+/// Version 5: earlier versions of a question's content, written by
+/// `LazyQuestionsRepository::update`. Local only; revisions are not synced.
+fn question_revisions(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS question_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            question_id TEXT NOT NULL,
+            question TEXT NOT NULL,
+            answer TEXT NOT NULL,
+            tags TEXT,
+            difficulty TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            replaced_at TEXT NOT NULL,
+            FOREIGN KEY (question_id) REFERENCES questions(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_question_revisions_question
+            ON question_revisions(question_id, id);",
+    )
+}
+
+/// Version 6: images and files referenced from answers as `attachment://<id>`.
+/// Blobs live in the database so backups, encryption and export cover them.
+/// A question holds each distinct content only once.
+fn attachments(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS attachments (
+            id TEXT PRIMARY KEY,
+            question_id TEXT NOT NULL,
+            file_name TEXT NOT NULL,
+            mime_type TEXT NOT NULL,
+            hash TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            data BLOB NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (question_id) REFERENCES questions(id) ON DELETE CASCADE,
+            UNIQUE (question_id, hash)
+        );",
+    )
+}
+
+/// Version 7: directed links between questions. Links to trashed questions
+/// are kept for a restore and hidden by queries; a purge drops them.
+fn question_links(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS question_links (
+            source_id TEXT NOT NULL,
+            target_id TEXT NOT NULL,
+            link_type TEXT NOT NULL CHECK (link_type IN ('related', 'prerequisite', 'followUp')),
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (source_id, target_id, link_type),
+            FOREIGN KEY (source_id) REFERENCES questions(id) ON DELETE CASCADE,
+            FOREIGN KEY (target_id) REFERENCES questions(id) ON DELETE CASCADE,
+            CHECK (source_id != target_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_question_links_target ON question_links(target_id);",
+    )
+}
+
+/// Version 8: personal notes kept apart from the question's answer. There is
+/// deliberately no foreign key: annotations outlive the question being replaced
+/// by an import or a sync pull, and reattach when it comes back with its id.
+fn annotations(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS annotations (
+            id TEXT PRIMARY KEY,
+            question_id TEXT NOT NULL,
+            body TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            sync_version INTEGER DEFAULT 1,
+            synced_at INTEGER,
+            deleted INTEGER DEFAULT 0,
+            deleted_at INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_annotations_question ON annotations(question_id);",
+    )
+}
+
+/// Version 9: difficulty levels with an ordering. Seeds the built-in levels,
+/// normalizes stored difficulties the way `Difficulty::parse` does, and keeps
+/// any other value as a custom level ranked after the built-ins.
+fn difficulty_levels(tx: &Transaction) -> rusqlite::Result<()> {
+    let normalize = |table: &str| {
+        format!(
+            "UPDATE {table} SET difficulty = CASE replace(lower(trim(COALESCE(difficulty, ''))), ' ', '-')
+                 WHEN '' THEN 'intermediate'
+                 WHEN 'easy' THEN 'beginner'
+                 WHEN 'medium' THEN 'intermediate'
+                 WHEN 'hard' THEN 'advanced'
+                 ELSE replace(lower(trim(difficulty)), ' ', '-')
+             END;"
+        )
+    };
+
+    tx.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS difficulty_levels (
+            key TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            rank INTEGER NOT NULL UNIQUE,
+            built_in INTEGER NOT NULL DEFAULT 0
+        );
+        INSERT OR IGNORE INTO difficulty_levels (key, name, rank, built_in) VALUES
+            ('beginner', 'Beginner', 100, 1),
+            ('intermediate', 'Intermediate', 200, 1),
+            ('advanced', 'Advanced', 300, 1);
+
+        {questions}
+        {revisions}
+
+        INSERT INTO difficulty_levels (key, name, rank, built_in)
+            SELECT d, d, 300 + 100 * ROW_NUMBER() OVER (ORDER BY d), 0
+            FROM (SELECT difficulty AS d FROM questions
+                  UNION SELECT difficulty FROM question_revisions)
+            WHERE d NOT IN (SELECT key FROM difficulty_levels);",
+        questions = normalize("questions"),
+        revisions = normalize("question_revisions"),
+    ))
+}
+
+/// Version 10: topics nest through `parent_id`. The names in each topic's
+/// `subtopics` list and the `subtopic` strings on its questions become child
+/// topics, and the questions move into them. The old columns are left empty.
+fn topic_hierarchy(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE topics ADD COLUMN parent_id TEXT;
+        CREATE INDEX IF NOT EXISTS idx_topics_parent ON topics(parent_id);",
+    )?;
+
+    let parents = {
+        let mut stmt = tx.prepare("SELECT id, slug, subtopics FROM topics ORDER BY id")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+    let now = chrono::Utc::now().to_rfc3339();
+
+    for (parent_id, parent_slug, subtopics) in parents {
+        let mut names: Vec<String> = subtopics
+            .and_then(|json| serde_json::from_str::<Vec<String>>(&json).ok())
+            .unwrap_or_default();
+        {
+            let mut stmt = tx.prepare(
+                "SELECT DISTINCT trim(subtopic) FROM questions
+                 WHERE topic_id = ? AND trim(COALESCE(subtopic, '')) != ''
+                 ORDER BY 1",
+            )?;
+            let rows = stmt.query_map([&parent_id], |row| row.get::<_, String>(0))?;
+            for name in rows {
+                names.push(name?);
+            }
+        }
+
+        let mut seen: Vec<String> = Vec::new();
+        for name in names.iter().map(|n| n.trim()) {
+            let key = name.to_lowercase();
+            if name.is_empty() || seen.contains(&key) {
+                continue;
+            }
+            let child_id = subtopic_id(&parent_id, &key);
+
+            tx.execute(
+                "INSERT OR IGNORE INTO topics (id, name, description, slug, icon, color, parent_id, order_index,
+                                               created_at, updated_at, sync_version, synced_at, deleted, deleted_at)
+                 SELECT ?1, ?2, '', ?3, icon, color, id, ?4, ?5, ?5, 1, NULL, deleted, deleted_at
+                 FROM topics WHERE id = ?6",
+                params![
+                    child_id,
+                    name,
+                    subtopic_slug(&parent_slug, &key),
+                    seen.len() as i32,
+                    now,
+                    parent_id
+                ],
+            )?;
+            tx.execute(
+                "UPDATE questions SET topic_id = ?1, subtopic = NULL, synced_at = NULL,
+                        sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE topic_id = ?2 AND lower(trim(subtopic)) = ?3",
+                params![child_id, parent_id, key],
+            )?;
+            tx.execute(
+                "UPDATE progress SET topic_id = ?1, synced_at = NULL,
+                        sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE question_id IN (SELECT id FROM questions WHERE topic_id = ?1)
+                   AND topic_id != ?1",
+                params![child_id],
+            )?;
+            seen.push(key);
+        }
+
+        tx.execute(
+            "UPDATE topics SET subtopics = NULL,
+                    synced_at = CASE WHEN ?2 THEN NULL ELSE synced_at END,
+                    sync_version = COALESCE(sync_version, 0) + ?2
+             WHERE id = ?1",
+            params![parent_id, !seen.is_empty()],
+        )?;
+    }
+
+    // Whitespace-only subtopics don't become topics
+    tx.execute_batch("UPDATE questions SET subtopic = NULL WHERE subtopic IS NOT NULL;")
+}
+
+/// Id of the child topic that subtopic `key` (trimmed and lowercased) of
+/// `parent_id` becomes. The same on every device, so migrating each one
+/// separately, or importing an old export, doesn't leave duplicate children.
+pub fn subtopic_id(parent_id: &str, key: &str) -> String {
+    let digest = format!("{:x}", Sha256::digest(format!("{}/{}", parent_id, key)));
+    format!(
+        "{}-{}-{}-{}-{}",
+        &digest[0..8],
+        &digest[8..12],
+        &digest[12..16],
+        &digest[16..20],
+        &digest[20..32]
+    )
+}
+
+/// Slug of the child topic that subtopic `key` of a topic becomes
+pub fn subtopic_slug(parent_slug: &str, key: &str) -> String {
+    let slug_part: String = key
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("{}-{}", parent_slug, slug_part)
+}
+
+/// Version 11: topics can be archived
+fn archived_topics(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE topics ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;")
+}
+
+/// Version 12: named question filters, stored as JSON
+fn saved_searches(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS saved_searches (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            filter TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            sync_version INTEGER DEFAULT 1,
+            synced_at INTEGER,
+            deleted INTEGER DEFAULT 0,
+            deleted_at INTEGER
+        );",
+    )
+}
+
+/// Version 13: a delete stamps every row it trashes with one batch id, so a
+/// restore brings back exactly those rows. `deleted_at` only has one-second
+/// resolution. Rows already in the trash are batched by their `deleted_at`,
+/// which is how restores grouped them before.
+fn trash_batches(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE topics ADD COLUMN deleted_batch TEXT;
+        ALTER TABLE questions ADD COLUMN deleted_batch TEXT;
+        ALTER TABLE progress ADD COLUMN deleted_batch TEXT;
+        UPDATE topics SET deleted_batch = deleted_at WHERE deleted = 1 AND deleted_at IS NOT NULL;
+        UPDATE questions SET deleted_batch = deleted_at WHERE deleted = 1 AND deleted_at IS NOT NULL;
+        UPDATE progress SET deleted_batch = deleted_at WHERE deleted = 1 AND deleted_at IS NOT NULL;",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_migrates_fresh_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let version = run(&mut conn).unwrap();
+
+        assert_eq!(version, latest_version());
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+
+        let tables: i64 = conn
+            .query_row(
+                "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name IN ('topics', 'questions', 'progress', 'quiz_sessions', 'sync_metadata')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(tables, 5);
+    }
+
+    #[test]
+    fn test_run_is_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn).unwrap();
+        assert_eq!(run(&mut conn).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn test_fts_index_follows_question_writes() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn).unwrap();
+
+        conn.execute_batch(
+            "INSERT INTO topics (id, name, slug, created_at, updated_at) VALUES ('t1', 'Rust', 'rust', '', '');
+             INSERT INTO questions (id, topic_id, question_number, question, answer, tags, created_at, updated_at)
+                VALUES ('q1', 't1', 1, 'Explain ownership', '{\"markdown\":\"Each value has an owner\"}', '[\"memory\"]', '', '');",
+        )
+        .unwrap();
+
+        let matches = |query: &str| -> i64 {
+            conn.query_row(
+                "SELECT count(*) FROM questions_fts WHERE questions_fts MATCH ?",
+                [query],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(matches("owner"), 1);
+        assert_eq!(matches("memory"), 1);
+        assert_eq!(matches("markdown"), 0);
+
+        conn.execute("UPDATE questions SET deleted = 1 WHERE id = 'q1'", [])
+            .unwrap();
+        assert_eq!(matches("owner"), 0);
+    }
+
+    #[test]
+    fn test_tag_links_follow_question_tags() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn).unwrap();
+
+        conn.execute_batch(
+            "INSERT INTO topics (id, name, slug, created_at, updated_at) VALUES ('t1', 'Rust', 'rust', '', '');
+             INSERT INTO questions (id, topic_id, question_number, question, answer, tags, created_at, updated_at)
+                VALUES ('q1', 't1', 1, 'a', '{}', '[\"Rust\", \"memory\"]', '', ''),
+                       ('q2', 't1', 2, 'b', '{}', '[\"rust\"]', '', '');",
+        )
+        .unwrap();
+
+        let links = |tag: &str| -> i64 {
+            conn.query_row(
+                "SELECT count(*) FROM question_tags qt JOIN tags t ON t.id = qt.tag_id WHERE t.name = ?",
+                [tag],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(links("rust"), 2);
+        assert_eq!(links("memory"), 1);
+
+        conn.execute("UPDATE questions SET tags = '[]' WHERE id = 'q1'", [])
+            .unwrap();
+        assert_eq!(links("Rust"), 1);
+        assert_eq!(links("memory"), 0);
+    }
+
+    #[test]
+    fn test_difficulty_levels_migrate_existing_values() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        for migration in MIGRATIONS.iter().filter(|m| m.version < 9) {
+            let tx = conn.transaction().unwrap();
+            (migration.up)(&tx).unwrap();
+            tx.commit().unwrap();
+        }
+        conn.execute_batch(
+            "PRAGMA user_version = 8;
+             INSERT INTO topics (id, name, slug, created_at, updated_at) VALUES ('t1', 'Rust', 'rust', '', '');
+             INSERT INTO questions (id, topic_id, question_number, question, answer, difficulty, created_at, updated_at)
+                VALUES ('q1', 't1', 1, 'a', '{}', ' Hard', '', ''),
+                       ('q2', 't1', 2, 'b', '{}', NULL, '', ''),
+                       ('q3', 't1', 3, 'c', '{}', 'Staff Engineer', '', '');",
+        )
+        .unwrap();
+        run(&mut conn).unwrap();
+
+        let difficulty = |id: &str| -> String {
+            conn.query_row("SELECT difficulty FROM questions WHERE id = ?", [id], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        assert_eq!(difficulty("q1"), "advanced");
+        assert_eq!(difficulty("q2"), "intermediate");
+        assert_eq!(difficulty("q3"), "staff-engineer");
+
+        let rank: i32 = conn
+            .query_row(
+                "SELECT rank FROM difficulty_levels WHERE key = 'staff-engineer'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(rank > 300);
+    }
+
+    #[test]
+    fn test_subtopics_become_child_topics() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        for migration in MIGRATIONS.iter().filter(|m| m.version < 10) {
+            let tx = conn.transaction().unwrap();
+            (migration.up)(&tx).unwrap();
+            tx.commit().unwrap();
+        }
+        conn.execute_batch(
+            "PRAGMA user_version = 9;
+             INSERT INTO topics (id, name, slug, subtopics, created_at, updated_at)
+                VALUES ('t1', 'Rust', 'rust', '[\"Ownership\", \"Traits\"]', '', '');
+             INSERT INTO questions (id, topic_id, subtopic, question_number, question, answer, created_at, updated_at)
+                VALUES ('q1', 't1', 'ownership ', 1, 'a', '{}', '', ''),
+                       ('q2', 't1', 'Async', 2, 'b', '{}', '', ''),
+                       ('q3', 't1', NULL, 3, 'c', '{}', '', '');
+             INSERT INTO progress (question_id, topic_id, status, created_at, updated_at)
+                VALUES ('q1', 't1', 'Studying', '', '');",
+        )
+        .unwrap();
+        run(&mut conn).unwrap();
+
+        let children: Vec<String> = conn
+            .prepare("SELECT name FROM topics WHERE parent_id = 't1' ORDER BY order_index")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(children, ["Ownership", "Traits", "Async"]);
+
+        let topic_of = |table: &str, id: &str| -> String {
+            conn.query_row(
+                &format!(
+                    "SELECT t.name FROM {} x JOIN topics t ON t.id = x.topic_id
+                     WHERE x.{} = ?",
+                    table,
+                    if table == "questions" { "id" } else { "question_id" }
+                ),
+                [id],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(topic_of("questions", "q1"), "Ownership");
+        assert_eq!(topic_of("progress", "q1"), "Ownership");
+        assert_eq!(topic_of("questions", "q2"), "Async");
+        assert_eq!(topic_of("questions", "q3"), "Rust");
+    }
+
+    #[test]
+    fn test_run_refuses_newer_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!("PRAGMA user_version = {}", latest_version() + 1))
+            .unwrap();
+
+        assert!(run(&mut conn).is_err());
+    }
+}
@@ -1,5 +1,22 @@
+pub mod migrations;
 pub mod models;
+pub mod pool;
 pub mod repository;
 pub mod sqlite_db;
+pub mod workspaces;
 
 pub use sqlite_db::SqliteDatabase as LazyDatabase; // Alias for backward compatibility during refactor
+
+use crate::error::AppError;
+
+/// Run blocking database work on the blocking thread pool, keeping SQLite
+/// calls (and the writer lock) off the async runtime's worker threads.
+pub async fn run_blocking<T, F>(f: F) -> Result<T, AppError>
+where
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::internal(format!("Database task failed: {}", e)))?
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+/// A personal note on a question, kept apart from its canonical answer
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Annotation {
+    pub id: String,
+    #[serde(rename = "questionId")]
+    pub question_id: String,
+    /// Markdown
+    pub body: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// URI scheme answers use to reference attachments: `attachment://<id>`
+pub const ATTACHMENT_SCHEME: &str = "attachment";
+
+/// An image or file attached to a question. The content itself is only
+/// loaded when served through the `attachment://` protocol or exported.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Attachment {
+    pub id: String,
+    #[serde(rename = "questionId")]
+    pub question_id: String,
+    #[serde(rename = "fileName")]
+    pub file_name: String,
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+    /// SHA-256 of the content, hex encoded
+    pub hash: String,
+    /// Content size in bytes
+    pub size: u64,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl Attachment {
+    /// Stable reference to paste into answer markdown
+    pub fn uri(&self) -> String {
+        format!("{}://{}", ATTACHMENT_SCHEME, self.id)
+    }
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// A snapshot of the database in the backups folder
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupInfo {
+    #[serde(rename = "fileName")]
+    pub file_name: String,
+    /// What triggered the backup, e.g. "startup" or "pre-import"
+    pub reason: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    /// File size in bytes
+    pub size: u64,
+    /// Whether the snapshot is a SQLCipher file
+    pub encrypted: bool,
+}
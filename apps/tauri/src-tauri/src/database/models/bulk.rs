@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// What a bulk operation does when some of its questions can't be changed
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum BulkMode {
+    /// Change nothing unless every question can be changed
+    #[default]
+    AllOrNothing,
+    /// Change the questions that can be, and report the rest
+    BestEffort,
+}
+
+/// Outcome of a bulk operation for one question
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BulkItemResult {
+    pub id: String,
+    pub success: bool,
+    /// Why the question couldn't be changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<AppError>,
+}
+
+/// Per-question outcomes of a bulk operation, in request order
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BulkResult {
+    pub results: Vec<BulkItemResult>,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Set when all-or-nothing mode hit a failure and changed nothing. The
+    /// results still say which questions would have succeeded.
+    #[serde(rename = "rolledBack", default)]
+    pub rolled_back: bool,
+}
@@ -0,0 +1,133 @@
+use rusqlite::types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How hard a question is. The built-in levels always exist; anything else
+/// must be defined as a custom level first, which `LazyQuestionsRepository`
+/// checks on create and update. Serialized as its key, e.g. `"advanced"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Difficulty {
+    Beginner,
+    #[default]
+    Intermediate,
+    Advanced,
+    /// Key of a user-defined level
+    Custom(String),
+}
+
+impl Difficulty {
+    pub const BUILT_IN: [Difficulty; 3] = [
+        Difficulty::Beginner,
+        Difficulty::Intermediate,
+        Difficulty::Advanced,
+    ];
+
+    /// Normalize user input to a level key: trimmed, lowercase, spaces as
+    /// dashes. Empty input means the default level, and easy/medium/hard are
+    /// read as the built-in levels.
+    pub fn parse(s: &str) -> Self {
+        let key = s.trim().to_lowercase().replace(' ', "-");
+        match key.as_str() {
+            "" => Difficulty::default(),
+            "beginner" | "easy" => Difficulty::Beginner,
+            "intermediate" | "medium" => Difficulty::Intermediate,
+            "advanced" | "hard" => Difficulty::Advanced,
+            _ => Difficulty::Custom(key),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        match self {
+            Difficulty::Beginner => "beginner",
+            Difficulty::Intermediate => "intermediate",
+            Difficulty::Advanced => "advanced",
+            Difficulty::Custom(key) => key,
+        }
+    }
+
+    pub fn is_built_in(&self) -> bool {
+        !matches!(self, Difficulty::Custom(_))
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+impl From<String> for Difficulty {
+    fn from(s: String) -> Self {
+        Difficulty::parse(&s)
+    }
+}
+
+impl From<Difficulty> for String {
+    fn from(d: Difficulty) -> Self {
+        d.key().to_string()
+    }
+}
+
+impl ToSql for Difficulty {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.key()))
+    }
+}
+
+impl FromSql for Difficulty {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Null => Ok(Difficulty::default()),
+            _ => value.as_str().map(Difficulty::parse),
+        }
+    }
+}
+
+/// A difficulty level and where it sits in the ordering
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DifficultyLevel {
+    pub key: Difficulty,
+    pub name: String,
+    /// Position in the ordering; higher is harder
+    pub rank: i32,
+    #[serde(rename = "builtIn")]
+    pub built_in: bool,
+    /// Active questions at this level
+    #[serde(rename = "questionCount", default)]
+    pub question_count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateDifficultyLevelDto {
+    pub name: String,
+    pub rank: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateDifficultyLevelDto {
+    pub name: Option<String>,
+    pub rank: Option<i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_normalizes_keys() {
+        assert_eq!(Difficulty::parse(" Advanced "), Difficulty::Advanced);
+        assert_eq!(Difficulty::parse("Hard"), Difficulty::Advanced);
+        assert_eq!(Difficulty::parse(""), Difficulty::Intermediate);
+        assert_eq!(
+            Difficulty::parse("Staff Engineer"),
+            Difficulty::Custom("staff-engineer".to_string())
+        );
+        assert_eq!(
+            serde_json::to_string(&Difficulty::Beginner).unwrap(),
+            "\"beginner\""
+        );
+        let custom: Difficulty = serde_json::from_str("\"Expert\"").unwrap();
+        assert_eq!(custom.key(), "expert");
+    }
+}
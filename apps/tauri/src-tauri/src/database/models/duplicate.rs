@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+use super::Question;
+
+/// Two questions whose normalized text is similar
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicatePair {
+    #[serde(rename = "firstId")]
+    pub first_id: String,
+    #[serde(rename = "secondId")]
+    pub second_id: String,
+    /// Jaccard similarity of text shingles, from 0 to 1
+    pub score: f64,
+}
+
+/// Questions linked by similar pairs; likely the same question more than once
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateCluster {
+    /// Oldest first, usually the one to keep
+    pub questions: Vec<Question>,
+    /// Best first
+    pub pairs: Vec<DuplicatePair>,
+    /// Highest pair score
+    pub score: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MergeResult {
+    /// The kept question, with the merged tags
+    pub question: Question,
+    /// Questions moved to the trash
+    #[serde(rename = "mergedIds")]
+    pub merged_ids: Vec<String>,
+    /// Quiz sessions that now point at the kept question
+    #[serde(rename = "quizSessionsUpdated")]
+    pub quiz_sessions_updated: usize,
+}
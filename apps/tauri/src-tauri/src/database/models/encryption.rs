@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+/// Whether the database file is encrypted and still waiting for its passphrase
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DatabaseStatus {
+    pub encrypted: bool,
+    pub locked: bool,
+}
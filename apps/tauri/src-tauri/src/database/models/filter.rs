@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Difficulty, ProgressStatus, Question, QuestionProgress};
+
+/// Range of timestamps: RFC 3339 or plain `YYYY-MM-DD` dates (midnight
+/// UTC). `from` is inclusive and `to` exclusive; either may be left open.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct DateRange {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
+
+/// Criteria for `filter_questions`. Every criterion given must match; an
+/// empty filter matches every active question outside archived topics.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct QuestionFilter {
+    /// Questions in these topics or any topic nested under them
+    #[serde(rename = "topicIds", default, skip_serializing_if = "Vec::is_empty")]
+    pub topic_ids: Vec<String>,
+    /// Questions with all of these tags, ignoring case
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Questions at any of these levels
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub difficulties: Vec<Difficulty>,
+    #[serde(
+        rename = "minDifficulty",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min_difficulty: Option<Difficulty>,
+    #[serde(
+        rename = "maxDifficulty",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_difficulty: Option<Difficulty>,
+    /// Questions in any of these states; no progress counts as not studied
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statuses: Vec<ProgressStatus>,
+    #[serde(
+        rename = "minConfidence",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min_confidence: Option<i32>,
+    #[serde(
+        rename = "maxConfidence",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_confidence: Option<i32>,
+    /// Questions answered wrong at least this many times
+    #[serde(
+        rename = "minTimesIncorrect",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min_times_incorrect: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateRange>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<DateRange>,
+    /// Never-reviewed questions never match a last-reviewed range
+    #[serde(
+        rename = "lastReviewed",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_reviewed: Option<DateRange>,
+    /// Full-text match on question, answer, and tags; every word must appear
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(rename = "includeArchived", default)]
+    pub include_archived: bool,
+}
+
+/// A question matched by a filter, with its progress if it has any
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuestionWithProgress {
+    #[serde(flatten)]
+    pub question: Question,
+    pub progress: Option<QuestionProgress>,
+}
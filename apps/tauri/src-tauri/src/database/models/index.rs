@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::Difficulty;
+
 /// The main index structure that's always kept in memory
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DatabaseIndex {
@@ -47,6 +49,9 @@ pub struct DifficultyDistribution {
     pub beginner: usize,
     pub intermediate: usize,
     pub advanced: usize,
+    /// Counts for user-defined levels, by key
+    #[serde(default)]
+    pub custom: HashMap<String, usize>,
 }
 
 /// Information about a tag across the database
@@ -114,11 +119,13 @@ impl DatabaseIndex {
         let mut tags_set: Vec<String> = Vec::new();
 
         for question in questions {
-            match question.difficulty.as_str() {
-                "beginner" => difficulty_dist.beginner += 1,
-                "intermediate" => difficulty_dist.intermediate += 1,
-                "advanced" => difficulty_dist.advanced += 1,
-                _ => {}
+            match &question.difficulty {
+                Difficulty::Beginner => difficulty_dist.beginner += 1,
+                Difficulty::Intermediate => difficulty_dist.intermediate += 1,
+                Difficulty::Advanced => difficulty_dist.advanced += 1,
+                Difficulty::Custom(key) => {
+                    *difficulty_dist.custom.entry(key.clone()).or_default() += 1
+                }
             }
 
             for tag in &question.tags {
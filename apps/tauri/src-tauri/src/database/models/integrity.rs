@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum IssueKind {
+    /// Active progress whose question is deleted or missing
+    OrphanedProgress,
+    /// Progress `topic_id` differs from its question's topic
+    ProgressTopicMismatch,
+    /// More than one active question with the same number in a topic
+    DuplicateQuestionNumber,
+    /// Unfinished quiz session listing questions that were purged
+    QuizMissingQuestions,
+    /// Active topic whose parent is missing, trashed, or nested under it
+    InvalidTopicParent,
+}
+
+/// One class of inconsistency found by `check_database`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IntegrityIssue {
+    pub kind: IssueKind,
+    pub count: usize,
+    /// Up to 10 affected ids (question ids, or session or topic ids for
+    /// those kinds)
+    #[serde(rename = "sampleIds")]
+    pub sample_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IntegrityReport {
+    /// Problems reported by SQLite's own `PRAGMA quick_check`; empty when fine
+    #[serde(rename = "sqliteErrors")]
+    pub sqlite_errors: Vec<String>,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.sqlite_errors.is_empty() && self.issues.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RepairResult {
+    /// What was found and fixed
+    pub repaired: Vec<IntegrityIssue>,
+    /// A fresh check after the repair
+    pub report: IntegrityReport,
+}
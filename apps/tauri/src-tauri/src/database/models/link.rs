@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+/// What the target of a link is to its source
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkType {
+    /// "See also"; shown the same way from both ends
+    Related,
+    /// The target should be learned before the source
+    Prerequisite,
+    /// The target builds on the source
+    FollowUp,
+}
+
+impl LinkType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkType::Related => "related",
+            LinkType::Prerequisite => "prerequisite",
+            LinkType::FollowUp => "followUp",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "related" => Some(LinkType::Related),
+            "prerequisite" => Some(LinkType::Prerequisite),
+            "followUp" => Some(LinkType::FollowUp),
+            _ => None,
+        }
+    }
+}
+
+/// The question at the other end of a link
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LinkedQuestion {
+    #[serde(rename = "linkType")]
+    pub link_type: LinkType,
+    #[serde(rename = "questionId")]
+    pub question_id: String,
+    #[serde(rename = "topicId")]
+    pub topic_id: String,
+    #[serde(rename = "questionNumber")]
+    pub question_number: i32,
+    pub question: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+/// Links from a question, and links pointing at it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuestionLinks {
+    pub outgoing: Vec<LinkedQuestion>,
+    pub backlinks: Vec<LinkedQuestion>,
+}
@@ -1,12 +1,46 @@
+pub mod annotation;
+pub mod attachment;
+pub mod backup;
+pub mod bulk;
+pub mod difficulty;
+pub mod duplicate;
+pub mod encryption;
+pub mod filter;
 pub mod index;
+pub mod integrity;
+pub mod link;
+pub mod page;
 pub mod progress;
 pub mod question;
+pub mod revision;
+pub mod saved_search;
+pub mod tag;
 pub mod topic;
+pub mod trash;
+pub mod workspace;
 
+pub use annotation::Annotation;
+pub use attachment::{Attachment, ATTACHMENT_SCHEME};
+pub use backup::BackupInfo;
+pub use bulk::{BulkItemResult, BulkMode, BulkResult};
+pub use difficulty::{
+    CreateDifficultyLevelDto, Difficulty, DifficultyLevel, UpdateDifficultyLevelDto,
+};
+pub use duplicate::{DuplicateCluster, DuplicatePair, MergeResult};
+pub use encryption::DatabaseStatus;
+pub use filter::{DateRange, QuestionFilter, QuestionWithProgress};
 pub use index::{DatabaseIndex, TopicQuestions, TopicsContainer};
+pub use integrity::{IntegrityIssue, IntegrityReport, IssueKind, RepairResult};
+pub use link::{LinkType, LinkedQuestion, QuestionLinks};
+pub use page::{Page, PageRequest, SortKey};
 pub use progress::{
     CreateQuizSessionDto, ProgressContainer, ProgressStatistics, ProgressStatus, QuestionProgress,
     QuizResult, QuizSession, QuizSessionType, QuizSessionsIndex, UpdateProgressDto,
 };
-pub use question::{Answer, CreateQuestionDto, Question, UpdateQuestionDto};
-pub use topic::{generate_id, CreateTopicDto, Topic, UpdateTopicDto};
+pub use question::{Answer, CreateQuestionDto, Question, QuestionSearchResult, UpdateQuestionDto};
+pub use revision::{DiffLine, DiffOp, QuestionRevision, RevisionDiff};
+pub use saved_search::{CreateSavedSearchDto, SavedSearch, UpdateSavedSearchDto};
+pub use tag::Tag;
+pub use topic::{generate_id, CreateTopicDto, Topic, TopicNode, UpdateTopicDto};
+pub use trash::{TrashedQuestion, TrashedTopic};
+pub use workspace::Workspace;
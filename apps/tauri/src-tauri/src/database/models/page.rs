@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// Field a listing is sorted by. Rows with equal values are ordered by id,
+/// so paging through them never skips or repeats a row.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SortKey {
+    Order,
+    Created,
+    Updated,
+    QuestionNumber,
+    LastReviewed,
+}
+
+/// Which page of a listing to return
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PageRequest {
+    /// `nextCursor` from the previous page; `None` for the first page
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Rows per page, 50 by default and at most 500
+    #[serde(default)]
+    pub limit: Option<u32>,
+    /// Defaults to the listing's natural order
+    #[serde(default)]
+    pub sort: Option<SortKey>,
+    #[serde(default)]
+    pub descending: Option<bool>,
+}
+
+/// One page of a listing
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass back as `cursor` for the next page; `None` on the last page
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+    /// Rows in the whole listing
+    pub total: usize,
+}
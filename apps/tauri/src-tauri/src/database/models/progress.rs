@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::Utc;
 
+use super::Difficulty;
+
 /// Generate a new UUID
 #[allow(dead_code)]
 pub fn generate_id() -> String {
@@ -159,9 +161,18 @@ pub struct CreateQuizSessionDto {
     #[serde(rename = "topicIds", skip_serializing_if = "Option::is_none")]
     pub topic_ids: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub difficulty: Option<String>,
+    pub difficulty: Option<Difficulty>,
+    /// Only questions at this level or harder
+    #[serde(rename = "minDifficulty", default, skip_serializing_if = "Option::is_none")]
+    pub min_difficulty: Option<Difficulty>,
+    /// Only questions at this level or easier
+    #[serde(rename = "maxDifficulty", default, skip_serializing_if = "Option::is_none")]
+    pub max_difficulty: Option<Difficulty>,
     #[serde(rename = "maxQuestions", skip_serializing_if = "Option::is_none")]
     pub max_questions: Option<i32>,
+    /// Also draw from archived topics, which are skipped otherwise
+    #[serde(rename = "includeArchived", default)]
+    pub include_archived: bool,
 }
 
 /// Progress statistics
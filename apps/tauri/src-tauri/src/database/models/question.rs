@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::Difficulty;
+
 /// Generate a new UUID for a question
 #[allow(dead_code)]
 pub fn generate_id() -> String {
@@ -17,14 +19,12 @@ pub struct Question {
     pub id: String,
     #[serde(rename = "topicId")]
     pub topic_id: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub subtopic: Option<String>,
     #[serde(rename = "questionNumber")]
     pub question_number: i32,
     pub question: String,
     pub answer: Answer,
     pub tags: Vec<String>,
-    pub difficulty: String, // "beginner", "intermediate", "advanced"
+    pub difficulty: Difficulty,
     pub order: i32,
     #[serde(rename = "createdAt")]
     pub created_at: String,
@@ -36,26 +36,35 @@ pub struct Question {
 pub struct CreateQuestionDto {
     #[serde(rename = "topicId")]
     pub topic_id: String,
-    pub subtopic: Option<String>,
     #[serde(rename = "questionNumber")]
     pub question_number: i32,
     pub question: String,
     pub answer: Answer,
     pub tags: Vec<String>,
-    pub difficulty: String,
+    pub difficulty: Difficulty,
     pub order: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct UpdateQuestionDto {
     #[serde(rename = "topicId")]
     pub topic_id: Option<String>,
-    pub subtopic: Option<String>,
     #[serde(rename = "questionNumber")]
     pub question_number: Option<i32>,
     pub question: Option<String>,
     pub answer: Option<Answer>,
     pub tags: Option<Vec<String>>,
-    pub difficulty: Option<String>,
+    pub difficulty: Option<Difficulty>,
     pub order: Option<i32>,
 }
+
+/// A full-text search hit: the question plus its BM25 rank and a highlighted snippet
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuestionSearchResult {
+    #[serde(flatten)]
+    pub question: Question,
+    /// BM25 score; lower is a better match
+    pub rank: f64,
+    /// Best-matching fragment with matches wrapped in `<mark>` tags
+    pub snippet: String,
+}
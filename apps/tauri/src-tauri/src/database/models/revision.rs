@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Answer, Difficulty};
+
+/// A previous version of a question's content, recorded when it was edited
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuestionRevision {
+    pub id: i64,
+    #[serde(rename = "questionId")]
+    pub question_id: String,
+    pub question: String,
+    pub answer: Answer,
+    pub tags: Vec<String>,
+    pub difficulty: Difficulty,
+    /// When this version was written
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+    /// When this version was replaced by an edit or revert
+    #[serde(rename = "replacedAt")]
+    pub replaced_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// One line of a line-based text diff
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// Differences between two versions of a question. `to` is `None` when
+/// comparing against the question as it is now.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RevisionDiff {
+    pub from: i64,
+    pub to: Option<i64>,
+    pub question: Vec<DiffLine>,
+    pub answer: Vec<DiffLine>,
+    #[serde(rename = "tagsAdded")]
+    pub tags_added: Vec<String>,
+    #[serde(rename = "tagsRemoved")]
+    pub tags_removed: Vec<String>,
+    /// `[old, new]` when the difficulty changed
+    pub difficulty: Option<(Difficulty, Difficulty)>,
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+use super::QuestionFilter;
+
+/// A named filter whose members are worked out whenever it is read, so it
+/// follows questions and progress as they change
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: String,
+    pub filter: QuestionFilter,
+    /// Questions matching right now; `None` if the filter no longer applies,
+    /// e.g. it names a deleted difficulty level
+    #[serde(rename = "questionCount", default)]
+    pub question_count: Option<usize>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateSavedSearchDto {
+    pub name: String,
+    pub filter: QuestionFilter,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateSavedSearchDto {
+    pub name: Option<String>,
+    pub filter: Option<QuestionFilter>,
+}
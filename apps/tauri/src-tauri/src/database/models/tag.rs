@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// A tag with its usage across active (non-deleted) questions
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    #[serde(rename = "questionCount")]
+    pub question_count: usize,
+    #[serde(rename = "topicIds")]
+    pub topic_ids: Vec<String>,
+}
@@ -15,12 +15,18 @@ pub struct Topic {
     pub slug: String,
     pub icon: String,
     pub color: String,
-    pub subtopics: Option<Vec<String>>,
+    /// Topic this one is nested under; `None` for a top-level topic
+    #[serde(rename = "parentId", default)]
+    pub parent_id: Option<String>,
     pub order: i32,
     #[serde(rename = "createdAt")]
     pub created_at: String,
     #[serde(rename = "updatedAt")]
     pub updated_at: String,
+    /// Kept but left out of topic lists, quizzes, and statistics unless they
+    /// ask for archived topics. Covers everything nested under the topic.
+    #[serde(default)]
+    pub archived: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -30,7 +36,8 @@ pub struct CreateTopicDto {
     pub slug: String,
     pub icon: String,
     pub color: String,
-    pub subtopics: Option<Vec<String>>,
+    #[serde(rename = "parentId", default)]
+    pub parent_id: Option<String>,
     pub order: i32,
 }
 
@@ -41,6 +48,19 @@ pub struct UpdateTopicDto {
     pub slug: Option<String>,
     pub icon: Option<String>,
     pub color: Option<String>,
-    pub subtopics: Option<Vec<String>>,
     pub order: Option<i32>,
 }
+
+/// A topic with its descendants, as returned by `get_topic_tree`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicNode {
+    #[serde(flatten)]
+    pub topic: Topic,
+    /// Active questions filed directly under this topic
+    #[serde(rename = "questionCount")]
+    pub question_count: usize,
+    /// Active questions in this topic and every topic below it
+    #[serde(rename = "totalQuestionCount")]
+    pub total_question_count: usize,
+    pub children: Vec<TopicNode>,
+}
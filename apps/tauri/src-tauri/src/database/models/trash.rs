@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Question, Topic};
+
+/// A soft-deleted topic as shown in the trash bin
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrashedTopic {
+    #[serde(flatten)]
+    pub topic: Topic,
+    /// Unix seconds, as stored in `deleted_at`
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: i64,
+    /// Questions that were deleted together with the topic
+    #[serde(rename = "questionCount")]
+    pub question_count: usize,
+}
+
+/// A soft-deleted question as shown in the trash bin
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrashedQuestion {
+    #[serde(flatten)]
+    pub question: Question,
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: i64,
+    /// The question's topic is in the trash too; restore the topic first
+    #[serde(rename = "topicDeleted")]
+    pub topic_deleted: bool,
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// A named set of data with its own database file, sync checkpoint and login
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    /// Whether this is the workspace the app is currently using
+    #[serde(default)]
+    pub active: bool,
+}
@@ -0,0 +1,122 @@
+use rusqlite::{Connection, OpenFlags};
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use crate::error::{AppError, ErrorCode};
+
+/// How long a connection waits on a locked database before giving up
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Open a connection, applying the SQLCipher key first when there is one.
+/// A wrong key only shows up on first read, so check it straight away.
+pub fn open_connection(
+    path: &Path,
+    flags: OpenFlags,
+    key: Option<&str>,
+) -> Result<Connection, AppError> {
+    let conn = Connection::open_with_flags(path, flags)
+        .map_err(|e| AppError::from(e).context("Failed to open database"))?;
+
+    if let Some(key) = key {
+        conn.pragma_update(None, "key", key)
+            .map_err(|e| AppError::from(e).context("Failed to set database key"))?;
+    }
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |row| {
+        row.get::<_, i64>(0)
+    })
+    .map_err(|e| match e.sqlite_error_code() {
+        Some(rusqlite::ErrorCode::NotADatabase) => {
+            AppError::new(ErrorCode::IncorrectPassphrase, "Incorrect passphrase")
+        }
+        _ => AppError::from(e).context("Failed to open database"),
+    })?;
+
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .map_err(|e| AppError::from(e).context("Failed to set busy timeout"))?;
+    Ok(conn)
+}
+
+/// Fixed-size pool of read-only connections.
+///
+/// With the database in WAL mode these readers never block on (or block) the
+/// single writer connection, so UI reads stay responsive during long writes
+/// such as sync or import.
+pub struct ReadPool {
+    size: usize,
+    idle: Mutex<Vec<Connection>>,
+    available: Condvar,
+}
+
+impl ReadPool {
+    pub fn open(path: &Path, size: usize, key: Option<&str>) -> Result<Arc<Self>, AppError> {
+        let mut connections = Vec::with_capacity(size);
+        for _ in 0..size {
+            let conn = open_connection(
+                path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY
+                    | OpenFlags::SQLITE_OPEN_URI
+                    | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+                key,
+            )
+            .map_err(|e| e.context("Failed to open read connection"))?;
+            connections.push(conn);
+        }
+
+        Ok(Arc::new(Self {
+            size,
+            idle: Mutex::new(connections),
+            available: Condvar::new(),
+        }))
+    }
+
+    /// Borrow a connection, waiting for one to be returned if all are in use
+    pub fn get(self: &Arc<Self>) -> PooledConnection {
+        let mut idle = self.idle.lock().unwrap();
+        loop {
+            if let Some(conn) = idle.pop() {
+                return PooledConnection {
+                    pool: Arc::clone(self),
+                    conn: Some(conn),
+                };
+            }
+            idle = self.available.wait(idle).unwrap();
+        }
+    }
+
+    /// Wait for every borrowed connection to come back, then close them all.
+    /// The pool is unusable afterwards: `get` would wait forever.
+    pub fn close(&self) {
+        let mut idle = self.idle.lock().unwrap();
+        while idle.len() < self.size {
+            idle = self.available.wait(idle).unwrap();
+        }
+        idle.clear();
+    }
+}
+
+/// A read connection borrowed from the pool; returned to it on drop
+pub struct PooledConnection {
+    pool: Arc<ReadPool>,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+            .as_ref()
+            .expect("connection already returned to pool")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.idle.lock().unwrap().push(conn);
+            self.pool.available.notify_all();
+        }
+    }
+}
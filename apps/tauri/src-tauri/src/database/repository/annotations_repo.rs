@@ -0,0 +1,248 @@
+use rusqlite::{params, OptionalExtension};
+use std::sync::Arc;
+
+use crate::database::{
+    models::{generate_id, Annotation},
+    LazyDatabase,
+};
+use crate::error::AppError;
+
+/// Personal annotations. Deletes are soft until pushed, like quiz sessions,
+/// and annotations are purged along with their question from the trash.
+pub struct AnnotationsRepository {
+    db: Arc<LazyDatabase>,
+}
+
+impl AnnotationsRepository {
+    pub fn new(db: Arc<LazyDatabase>) -> Self {
+        Self { db }
+    }
+
+    fn map_annotation(row: &rusqlite::Row) -> rusqlite::Result<Annotation> {
+        Ok(Annotation {
+            id: row.get(0)?,
+            question_id: row.get(1)?,
+            body: row.get(2)?,
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+        })
+    }
+
+    /// Annotations on a question, oldest first
+    pub fn get_by_question(&self, question_id: &str) -> Result<Vec<Annotation>, AppError> {
+        let conn = self.db.read_connection()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, question_id, body, created_at, updated_at FROM annotations
+                 WHERE question_id = ? AND (deleted = 0 OR deleted IS NULL)
+                 ORDER BY created_at, id",
+            )?;
+        let rows = stmt
+            .query_map(params![question_id], Self::map_annotation)?;
+
+        let mut annotations = Vec::new();
+        for a in rows {
+            annotations.push(a?);
+        }
+        Ok(annotations)
+    }
+
+    /// Every active annotation, for export
+    pub fn get_all(&self) -> Result<Vec<Annotation>, AppError> {
+        let conn = self.db.read_connection()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, question_id, body, created_at, updated_at FROM annotations
+                 WHERE deleted = 0 OR deleted IS NULL
+                 ORDER BY question_id, created_at, id",
+            )?;
+        let rows = stmt
+            .query_map([], Self::map_annotation)?;
+
+        let mut annotations = Vec::new();
+        for a in rows {
+            annotations.push(a?);
+        }
+        Ok(annotations)
+    }
+
+    pub fn get_by_id(&self, id: &str) -> Result<Option<Annotation>, AppError> {
+        let conn = self.db.read_connection()?;
+        conn.query_row(
+            "SELECT id, question_id, body, created_at, updated_at FROM annotations
+             WHERE id = ? AND (deleted = 0 OR deleted IS NULL)",
+            params![id],
+            Self::map_annotation,
+        )
+        .optional()
+        .map_err(AppError::from)
+    }
+
+    pub fn create(&self, question_id: &str, body: &str) -> Result<Annotation, AppError> {
+        if body.trim().is_empty() {
+            return Err(AppError::validation("Annotation cannot be empty").with_field("body"));
+        }
+
+        let conn = self.db.get_connection()?;
+        let conn = conn.lock().unwrap();
+
+        let question_exists: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM questions WHERE id = ? AND (deleted = 0 OR deleted IS NULL))",
+                params![question_id],
+                |row| row.get(0),
+            )?;
+        if !question_exists {
+            return Err(AppError::not_found("question", question_id));
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let annotation = Annotation {
+            id: generate_id(),
+            question_id: question_id.to_string(),
+            body: body.to_string(),
+            created_at: now.clone(),
+            updated_at: now,
+        };
+        conn.execute(
+            "INSERT INTO annotations (id, question_id, body, created_at, updated_at, sync_version, synced_at, deleted)
+             VALUES (?, ?, ?, ?, ?, 1, NULL, 0)",
+            params![
+                annotation.id,
+                annotation.question_id,
+                annotation.body,
+                annotation.created_at,
+                annotation.updated_at,
+            ],
+        )?;
+
+        Ok(annotation)
+    }
+
+    pub fn update(&self, id: &str, body: &str) -> Result<Option<Annotation>, AppError> {
+        if body.trim().is_empty() {
+            return Err(AppError::validation("Annotation cannot be empty").with_field("body"));
+        }
+
+        {
+            let conn = self.db.get_connection()?;
+            let conn = conn.lock().unwrap();
+            let updated = conn
+                .execute(
+                    "UPDATE annotations SET body = ?, updated_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                     WHERE id = ? AND (deleted = 0 OR deleted IS NULL)",
+                    params![body, chrono::Utc::now().to_rfc3339(), id],
+                )?;
+            if updated == 0 {
+                return Ok(None);
+            }
+        }
+
+        self.get_by_id(id)
+    }
+
+    /// Soft-delete until the deletion is pushed, then sync removes the row
+    pub fn delete(&self, id: &str) -> Result<bool, AppError> {
+        let conn = self.db.get_connection()?;
+        let conn = conn.lock().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let count = conn
+            .execute(
+                "UPDATE annotations SET deleted = 1, deleted_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE id = ? AND (deleted = 0 OR deleted IS NULL)",
+                params![now, id],
+            )?;
+        Ok(count > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::repository::test_fixtures::{database, deleted, question, topic};
+    use crate::database::repository::{LazyQuestionsRepository, TrashRepository};
+    use crate::error::ErrorCode;
+
+    fn bodies(annotations: Vec<Annotation>) -> Vec<String> {
+        annotations.into_iter().map(|a| a.body).collect()
+    }
+
+    #[test]
+    fn test_create_update_delete() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q = question(&db, &t, 1, "What is a trait?");
+
+        let repo = AnnotationsRepository::new(Arc::clone(&db));
+        let first = repo.create(&q, "Like an interface").unwrap();
+        repo.create(&q, "See the Rust book").unwrap();
+        assert_eq!(
+            bodies(repo.get_by_question(&q).unwrap()),
+            vec!["Like an interface", "See the Rust book"]
+        );
+
+        let updated = repo.update(&first.id, "Like a typeclass").unwrap().unwrap();
+        assert_eq!(updated.body, "Like a typeclass");
+        assert_eq!(updated.created_at, first.created_at);
+
+        assert!(repo.delete(&first.id).unwrap());
+        assert!(!repo.delete(&first.id).unwrap());
+        assert!(repo.get_by_id(&first.id).unwrap().is_none());
+        assert!(repo.update(&first.id, "Too late").unwrap().is_none());
+        assert_eq!(
+            bodies(repo.get_by_question(&q).unwrap()),
+            vec!["See the Rust book"]
+        );
+    }
+
+    #[test]
+    fn test_rejects_empty_body_and_unknown_question() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q = question(&db, &t, 1, "What is a trait?");
+
+        let repo = AnnotationsRepository::new(Arc::clone(&db));
+        let err = repo.create(&q, "  \n").unwrap_err();
+        assert_eq!(err.code, ErrorCode::Validation);
+        assert_eq!(err.field.as_deref(), Some("body"));
+        let err = repo.create("missing", "Note").unwrap_err();
+        assert_eq!(err.code, ErrorCode::NotFound);
+
+        let annotation = repo.create(&q, "Note").unwrap();
+        let err = repo.update(&annotation.id, "").unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("body"));
+        assert_eq!(
+            repo.get_by_id(&annotation.id).unwrap().unwrap().body,
+            "Note"
+        );
+    }
+
+    #[test]
+    fn test_trashed_question_keeps_annotations_until_purged() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q = question(&db, &t, 1, "What is a trait?");
+
+        let repo = AnnotationsRepository::new(Arc::clone(&db));
+        let annotation = repo.create(&q, "Like an interface").unwrap();
+        LazyQuestionsRepository::new(Arc::clone(&db))
+            .delete(&q)
+            .unwrap();
+
+        // Trashing leaves the annotation in place but allows no new ones
+        assert_eq!(repo.get_by_question(&q).unwrap().len(), 1);
+        let err = repo.create(&q, "Another").unwrap_err();
+        assert_eq!(err.code, ErrorCode::NotFound);
+
+        let trash = TrashRepository::new(Arc::clone(&db));
+        assert!(trash.restore_question(&q).unwrap());
+        assert_eq!(repo.get_by_question(&q).unwrap().len(), 1);
+
+        LazyQuestionsRepository::new(Arc::clone(&db))
+            .delete(&q)
+            .unwrap();
+        assert!(trash.purge_question(&q).unwrap());
+        assert_eq!(deleted(&db, "annotations", "id", &annotation.id), None);
+        assert!(repo.get_all().unwrap().is_empty());
+    }
+}
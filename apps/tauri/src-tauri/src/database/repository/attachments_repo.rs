@@ -0,0 +1,167 @@
+use rusqlite::{params, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::database::{
+    models::{generate_id, Attachment},
+    LazyDatabase,
+};
+use crate::error::AppError;
+
+/// Largest file that can be attached, in bytes
+pub const MAX_ATTACHMENT_SIZE: usize = 20 * 1024 * 1024;
+
+const COLUMNS: &str = "id, question_id, file_name, mime_type, hash, size, created_at";
+
+/// Attachment metadata and content. Attachments go with their question when
+/// it is purged; soft-deleting the question leaves them in place for restore.
+pub struct AttachmentsRepository {
+    db: Arc<LazyDatabase>,
+}
+
+impl AttachmentsRepository {
+    pub fn new(db: Arc<LazyDatabase>) -> Self {
+        Self { db }
+    }
+
+    fn map_attachment(row: &rusqlite::Row) -> rusqlite::Result<Attachment> {
+        Ok(Attachment {
+            id: row.get(0)?,
+            question_id: row.get(1)?,
+            file_name: row.get(2)?,
+            mime_type: row.get(3)?,
+            hash: row.get(4)?,
+            size: row.get::<_, i64>(5)? as u64,
+            created_at: row.get(6)?,
+        })
+    }
+
+    /// Attach `data` to a question. Uploading content the question already
+    /// has returns the existing attachment instead of storing it twice.
+    pub fn create(
+        &self,
+        question_id: &str,
+        file_name: &str,
+        mime_type: &str,
+        data: &[u8],
+    ) -> Result<Attachment, AppError> {
+        if data.len() > MAX_ATTACHMENT_SIZE {
+            return Err(AppError::validation(format!(
+                "Attachment is too large ({} bytes, limit {})",
+                data.len(),
+                MAX_ATTACHMENT_SIZE
+            ))
+            .with_field("data"));
+        }
+        let hash = format!("{:x}", Sha256::digest(data));
+
+        let conn = self.db.get_connection()?;
+        let conn = conn.lock().unwrap();
+
+        let question_exists: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM questions WHERE id = ? AND (deleted = 0 OR deleted IS NULL))",
+                params![question_id],
+                |row| row.get(0),
+            )?;
+        if !question_exists {
+            return Err(AppError::not_found("question", question_id));
+        }
+
+        let existing = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM attachments WHERE question_id = ? AND hash = ?",
+                    COLUMNS
+                ),
+                params![question_id, hash],
+                Self::map_attachment,
+            )
+            .optional()?;
+        if let Some(existing) = existing {
+            return Ok(existing);
+        }
+
+        let attachment = Attachment {
+            id: generate_id(),
+            question_id: question_id.to_string(),
+            file_name: file_name.to_string(),
+            mime_type: mime_type.to_string(),
+            hash,
+            size: data.len() as u64,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        conn.execute(
+            "INSERT INTO attachments (id, question_id, file_name, mime_type, hash, size, data, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                attachment.id,
+                attachment.question_id,
+                attachment.file_name,
+                attachment.mime_type,
+                attachment.hash,
+                attachment.size as i64,
+                data,
+                attachment.created_at,
+            ],
+        )?;
+
+        Ok(attachment)
+    }
+
+    /// Attachments of a question, oldest first
+    pub fn get_by_question(&self, question_id: &str) -> Result<Vec<Attachment>, AppError> {
+        let conn = self.db.read_connection()?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM attachments WHERE question_id = ? ORDER BY created_at, id",
+                COLUMNS
+            ))?;
+        let rows = stmt
+            .query_map(params![question_id], Self::map_attachment)?;
+
+        let mut attachments = Vec::new();
+        for a in rows {
+            attachments.push(a?);
+        }
+        Ok(attachments)
+    }
+
+    /// Every attachment with its content, for export
+    pub fn get_all_with_data(&self) -> Result<Vec<(Attachment, Vec<u8>)>, AppError> {
+        let conn = self.db.read_connection()?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {}, data FROM attachments ORDER BY question_id, created_at, id",
+                COLUMNS
+            ))?;
+        let rows = stmt
+            .query_map([], |row| Ok((Self::map_attachment(row)?, row.get(7)?)))?;
+
+        let mut attachments = Vec::new();
+        for a in rows {
+            attachments.push(a?);
+        }
+        Ok(attachments)
+    }
+
+    /// Metadata and content of one attachment
+    pub fn get_data(&self, id: &str) -> Result<Option<(Attachment, Vec<u8>)>, AppError> {
+        let conn = self.db.read_connection()?;
+        conn.query_row(
+            &format!("SELECT {}, data FROM attachments WHERE id = ?", COLUMNS),
+            params![id],
+            |row| Ok((Self::map_attachment(row)?, row.get(7)?)),
+        )
+        .optional()
+        .map_err(AppError::from)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<bool, AppError> {
+        let conn = self.db.get_connection()?;
+        let conn = conn.lock().unwrap();
+        let deleted = conn
+            .execute("DELETE FROM attachments WHERE id = ?", params![id])?;
+        Ok(deleted > 0)
+    }
+}
@@ -0,0 +1,349 @@
+use rusqlite::{params, OptionalExtension, Transaction};
+use std::sync::Arc;
+
+use crate::database::{
+    models::{generate_id, BulkItemResult, BulkMode, BulkResult, Difficulty, Question},
+    repository::{difficulty_repo, revisions_repo::record_revision, LazyQuestionsRepository},
+    LazyDatabase,
+};
+use crate::error::{AppError, ErrorCode};
+
+/// Operations over many questions at once. Each call runs in one
+/// transaction. A question that can't be changed (missing, trashed, no
+/// progress) is reported in its result; in `BulkMode::AllOrNothing` it rolls
+/// back the whole batch, in `BulkMode::BestEffort` it is just skipped. A
+/// database error always rolls back the whole batch. Every changed row gets `synced_at` cleared
+/// and its `sync_version` bumped, as the single-question operations do.
+pub struct BulkRepository {
+    db: Arc<LazyDatabase>,
+}
+
+impl BulkRepository {
+    pub fn new(db: Arc<LazyDatabase>) -> Self {
+        Self { db }
+    }
+
+    /// Apply `op` to each question id in one transaction. `op` returns
+    /// `Ok(Err(e))` when a question can't be changed and `Err` to abort the
+    /// batch.
+    fn run<F>(&self, ids: &[String], mode: BulkMode, mut op: F) -> Result<BulkResult, AppError>
+    where
+        F: FnMut(&Transaction, &str) -> Result<Result<(), AppError>, AppError>,
+    {
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let mut result = BulkResult::default();
+        for (i, id) in ids.iter().enumerate() {
+            let outcome = if ids[..i].contains(id) {
+                Err(AppError::validation("Listed more than once")
+                    .with_entity("question", id.clone()))
+            } else {
+                op(&tx, id)?
+            };
+            match outcome {
+                Ok(()) => result.succeeded += 1,
+                Err(_) => result.failed += 1,
+            }
+            result.results.push(BulkItemResult {
+                id: id.clone(),
+                success: outcome.is_ok(),
+                error: outcome.err(),
+            });
+        }
+
+        if mode == BulkMode::AllOrNothing && result.failed > 0 {
+            tx.rollback()?;
+            result.rolled_back = true;
+        } else {
+            tx.commit()?;
+        }
+        Ok(result)
+    }
+
+    /// Move questions into `topic_id`, numbered after its last question in
+    /// the order given. Questions already there keep their number.
+    pub fn move_questions(
+        &self,
+        ids: &[String],
+        topic_id: &str,
+        mode: BulkMode,
+    ) -> Result<BulkResult, AppError> {
+        let mut next_number: Option<i32> = None;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        self.run(ids, mode, |tx, id| {
+            if next_number.is_none() {
+                let topic_active: bool = tx
+                    .query_row(
+                        "SELECT EXISTS(SELECT 1 FROM topics WHERE id = ? AND (deleted = 0 OR deleted IS NULL))",
+                        params![topic_id],
+                        |row| row.get(0),
+                    )?;
+                if !topic_active {
+                    return Err(AppError::not_found("topic", topic_id));
+                }
+                let max: Option<i32> = tx
+                    .query_row(
+                        "SELECT MAX(question_number) FROM questions WHERE topic_id = ?",
+                        params![topic_id],
+                        |row| row.get(0),
+                    )?;
+                next_number = Some(max.unwrap_or(0) + 1);
+            }
+
+            let question = match active_question(tx, id)? {
+                Some(q) => q,
+                None => return Ok(Err(AppError::not_found("question", id))),
+            };
+            if question.topic_id == topic_id {
+                return Ok(Ok(()));
+            }
+
+            let number = next_number.unwrap();
+            next_number = Some(number + 1);
+            tx.execute(
+                "UPDATE questions SET topic_id = ?, question_number = ?, updated_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE id = ?",
+                params![topic_id, number, now, id],
+            )?;
+            tx.execute(
+                "UPDATE progress SET topic_id = ?, updated_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE question_id = ?",
+                params![topic_id, now, id],
+            )?;
+            Ok(Ok(()))
+        })
+    }
+
+    /// Add tags to questions, skipping ones a question already has
+    pub fn add_tags(
+        &self,
+        ids: &[String],
+        tags: &[String],
+        mode: BulkMode,
+    ) -> Result<BulkResult, AppError> {
+        let tags: Vec<&str> = tags
+            .iter()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .collect();
+        if tags.is_empty() {
+            return Err(AppError::validation("No tags given").with_field("tags"));
+        }
+        self.edit_tags(ids, mode, |current| {
+            let mut updated = current.to_vec();
+            for tag in &tags {
+                if !updated.iter().any(|t| t.trim().eq_ignore_ascii_case(tag)) {
+                    updated.push(tag.to_string());
+                }
+            }
+            updated
+        })
+    }
+
+    /// Remove tags from questions, matching case-insensitively
+    pub fn remove_tags(
+        &self,
+        ids: &[String],
+        tags: &[String],
+        mode: BulkMode,
+    ) -> Result<BulkResult, AppError> {
+        self.edit_tags(ids, mode, |current| {
+            current
+                .iter()
+                .filter(|t| !tags.iter().any(|r| r.trim().eq_ignore_ascii_case(t.trim())))
+                .cloned()
+                .collect()
+        })
+    }
+
+    fn edit_tags<F>(&self, ids: &[String], mode: BulkMode, edit: F) -> Result<BulkResult, AppError>
+    where
+        F: Fn(&[String]) -> Vec<String>,
+    {
+        let now = chrono::Utc::now().to_rfc3339();
+        self.run(ids, mode, |tx, id| {
+            let question = match active_question(tx, id)? {
+                Some(q) => q,
+                None => return Ok(Err(AppError::not_found("question", id))),
+            };
+            let tags = edit(&question.tags);
+            if tags == question.tags {
+                return Ok(Ok(()));
+            }
+
+            record_revision(tx, &question, &now)?;
+            // Triggers relink question_tags from the new JSON
+            tx.execute(
+                "UPDATE questions SET tags = ?, updated_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE id = ?",
+                params![
+                    serde_json::to_string(&tags).unwrap_or("[]".to_string()),
+                    now,
+                    id
+                ],
+            )?;
+            Ok(Ok(()))
+        })
+    }
+
+    pub fn set_difficulty(
+        &self,
+        ids: &[String],
+        difficulty: &Difficulty,
+        mode: BulkMode,
+    ) -> Result<BulkResult, AppError> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut validated = false;
+        self.run(ids, mode, |tx, id| {
+            if !validated {
+                difficulty_repo::validate(tx, difficulty)?;
+                validated = true;
+            }
+            let question = match active_question(tx, id)? {
+                Some(q) => q,
+                None => return Ok(Err(AppError::not_found("question", id))),
+            };
+            if question.difficulty == *difficulty {
+                return Ok(Ok(()));
+            }
+
+            record_revision(tx, &question, &now)?;
+            tx.execute(
+                "UPDATE questions SET difficulty = ?, updated_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE id = ?",
+                params![difficulty, now, id],
+            )?;
+            Ok(Ok(()))
+        })
+    }
+
+    /// Move questions and their progress to the trash. They share one
+    /// `deleted_batch`, like a single delete.
+    pub fn delete(&self, ids: &[String], mode: BulkMode) -> Result<BulkResult, AppError> {
+        let now = chrono::Utc::now().timestamp();
+        let batch = generate_id();
+        self.run(ids, mode, |tx, id| {
+            if active_question(tx, id)?.is_none() {
+                return Ok(Err(AppError::not_found("question", id)));
+            }
+            tx.execute(
+                "UPDATE progress SET deleted = 1, deleted_at = ?, deleted_batch = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE question_id = ? AND (deleted = 0 OR deleted IS NULL)",
+                params![now, batch, id],
+            )?;
+            tx.execute(
+                "UPDATE questions SET deleted = 1, deleted_at = ?, deleted_batch = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE id = ?",
+                params![now, batch, id],
+            )?;
+            Ok(Ok(()))
+        })
+    }
+
+    /// Put questions back to not studied
+    pub fn reset_progress(&self, ids: &[String], mode: BulkMode) -> Result<BulkResult, AppError> {
+        let now = chrono::Utc::now().to_rfc3339();
+        self.run(ids, mode, |tx, id| {
+            if active_question(tx, id)?.is_none() {
+                return Ok(Err(AppError::not_found("question", id)));
+            }
+            let updated = tx
+                .execute(
+                    "UPDATE progress SET status = 'NotStudied', confidence_level = 0, times_reviewed = 0, times_correct = 0, times_incorrect = 0,
+                            last_reviewed_at = NULL, next_review_at = NULL, updated_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                     WHERE question_id = ? AND (deleted = 0 OR deleted IS NULL)",
+                    params![now, id],
+                )?;
+            if updated == 0 {
+                return Ok(Err(AppError::new(
+                    ErrorCode::NotFound,
+                    format!("Progress not found for {}", id),
+                )
+                .with_entity("progress", id)));
+            }
+            Ok(Ok(()))
+        })
+    }
+}
+
+pub(crate) fn active_question(tx: &Transaction, id: &str) -> Result<Option<Question>, AppError> {
+    tx.query_row(
+        "SELECT * FROM questions WHERE id = ? AND (deleted = 0 OR deleted IS NULL)",
+        params![id],
+        LazyQuestionsRepository::map_question,
+    )
+    .optional()
+    .map_err(AppError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::repository::test_fixtures::{database, deleted, question, topic};
+
+    fn ids(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_all_or_nothing_rolls_back_on_failed_item() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q1 = question(&db, &t, 1, "What is a trait?");
+        let q2 = question(&db, &t, 2, "What is a crate?");
+
+        let result = BulkRepository::new(Arc::clone(&db))
+            .add_tags(
+                &ids(&[&q1, "missing", &q2]),
+                &ids(&["generics"]),
+                BulkMode::AllOrNothing,
+            )
+            .unwrap();
+        assert!(result.rolled_back);
+        assert_eq!((result.succeeded, result.failed), (2, 1));
+        assert!(!result.results[1].success);
+
+        let questions = LazyQuestionsRepository::new(Arc::clone(&db));
+        for id in [&q1, &q2] {
+            assert!(questions.get_by_id(id).unwrap().unwrap().tags.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_best_effort_reports_failed_items_in_input_order() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q1 = question(&db, &t, 1, "What is a trait?");
+        let q2 = question(&db, &t, 2, "What is a crate?");
+
+        let input = ids(&[&q2, "missing", &q1, &q2]);
+        let result = BulkRepository::new(Arc::clone(&db))
+            .delete(&input, BulkMode::BestEffort)
+            .unwrap();
+        assert!(!result.rolled_back);
+        assert_eq!((result.succeeded, result.failed), (2, 2));
+        assert_eq!(
+            result
+                .results
+                .iter()
+                .map(|r| r.id.clone())
+                .collect::<Vec<_>>(),
+            input
+        );
+        assert_eq!(
+            result.results.iter().map(|r| r.success).collect::<Vec<_>>(),
+            vec![true, false, true, false]
+        );
+        let missing = result.results[1].error.as_ref().unwrap();
+        assert_eq!(missing.code, ErrorCode::NotFound);
+        assert_eq!(missing.entity_id.as_deref(), Some("missing"));
+        let repeated = result.results[3].error.as_ref().unwrap();
+        assert_eq!(repeated.code, ErrorCode::Validation);
+        assert_eq!(repeated.entity_id.as_deref(), Some(q2.as_str()));
+        assert_eq!(deleted(&db, "questions", "id", &q1), Some(true));
+        assert_eq!(deleted(&db, "questions", "id", &q2), Some(true));
+    }
+}
@@ -0,0 +1,258 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::Arc;
+
+use crate::database::{
+    models::{CreateDifficultyLevelDto, Difficulty, DifficultyLevel, UpdateDifficultyLevelDto},
+    LazyDatabase,
+};
+use crate::error::AppError;
+
+/// Rank gap between levels appended by `ensure_level`
+const RANK_STEP: i32 = 100;
+
+/// Built-in and custom difficulty levels. Built-in levels can't be changed;
+/// custom ones can be renamed, moved, and deleted while no question uses them.
+pub struct DifficultyRepository {
+    db: Arc<LazyDatabase>,
+}
+
+impl DifficultyRepository {
+    pub fn new(db: Arc<LazyDatabase>) -> Self {
+        Self { db }
+    }
+
+    /// All levels, easiest first
+    pub fn get_all(&self) -> Result<Vec<DifficultyLevel>, AppError> {
+        let conn = self.db.read_connection()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT d.key, d.name, d.rank, d.built_in,
+                        (SELECT count(*) FROM questions q
+                         WHERE q.difficulty = d.key AND (q.deleted = 0 OR q.deleted IS NULL))
+                 FROM difficulty_levels d
+                 ORDER BY d.rank",
+            )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(DifficultyLevel {
+                    key: row.get(0)?,
+                    name: row.get(1)?,
+                    rank: row.get(2)?,
+                    built_in: row.get(3)?,
+                    question_count: row.get::<_, i64>(4)? as usize,
+                })
+            })?;
+
+        let mut levels = Vec::new();
+        for l in rows {
+            levels.push(l?);
+        }
+        Ok(levels)
+    }
+
+    /// Add a custom level. Its key is the normalized name.
+    pub fn create(&self, dto: CreateDifficultyLevelDto) -> Result<DifficultyLevel, AppError> {
+        let name = dto.name.trim();
+        let key = Difficulty::parse(name);
+        if name.is_empty() {
+            return Err(AppError::validation("Level name cannot be empty").with_field("name"));
+        }
+        if key.is_built_in() {
+            return Err(AppError::conflict(format!("\"{}\" is a built-in level", key)).with_field("name"));
+        }
+
+        let conn = self.db.get_connection()?;
+        let conn = conn.lock().unwrap();
+        if rank_of(&conn, &key)?.is_some() {
+            return Err(AppError::conflict(format!("Level \"{}\" already exists", key)).with_field("name"));
+        }
+        check_rank_free(&conn, dto.rank, None)?;
+
+        conn.execute(
+            "INSERT INTO difficulty_levels (key, name, rank, built_in) VALUES (?, ?, ?, 0)",
+            params![key, name, dto.rank],
+        )?;
+
+        Ok(DifficultyLevel {
+            key,
+            name: name.to_string(),
+            rank: dto.rank,
+            built_in: false,
+            question_count: 0,
+        })
+    }
+
+    pub fn update(
+        &self,
+        key: &str,
+        dto: UpdateDifficultyLevelDto,
+    ) -> Result<Option<DifficultyLevel>, AppError> {
+        let key = Difficulty::parse(key);
+        if key.is_built_in() {
+            return Err(AppError::conflict("Built-in levels cannot be changed"));
+        }
+
+        {
+            let conn = self.db.get_connection()?;
+            let conn = conn.lock().unwrap();
+            if rank_of(&conn, &key)?.is_none() {
+                return Ok(None);
+            }
+            if let Some(name) = &dto.name {
+                if name.trim().is_empty() {
+                    return Err(AppError::validation("Level name cannot be empty").with_field("name"));
+                }
+                conn.execute(
+                    "UPDATE difficulty_levels SET name = ? WHERE key = ?",
+                    params![name.trim(), key],
+                )?;
+            }
+            if let Some(rank) = dto.rank {
+                check_rank_free(&conn, rank, Some(&key))?;
+                conn.execute(
+                    "UPDATE difficulty_levels SET rank = ? WHERE key = ?",
+                    params![rank, key],
+                )?;
+            }
+        }
+
+        Ok(self.get_all()?.into_iter().find(|l| l.key == key))
+    }
+
+    /// Delete a custom level no question uses, trashed ones included. Levels
+    /// kept in revision history count as used too, so reverting to an old
+    /// revision never names a missing level.
+    pub fn delete(&self, key: &str) -> Result<bool, AppError> {
+        let key = Difficulty::parse(key);
+        if key.is_built_in() {
+            return Err(AppError::conflict("Built-in levels cannot be deleted"));
+        }
+
+        let conn = self.db.get_connection()?;
+        let conn = conn.lock().unwrap();
+        let (questions, revised): (i64, i64) = conn
+            .query_row(
+                "SELECT (SELECT count(*) FROM questions WHERE difficulty = ?1),
+                        (SELECT count(DISTINCT question_id) FROM question_revisions WHERE difficulty = ?1)",
+                params![key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+        if questions > 0 {
+            return Err(AppError::conflict(format!(
+                "Level \"{}\" is used by {} questions; move them to another level first",
+                key, questions
+            )));
+        }
+        if revised > 0 {
+            return Err(AppError::conflict(format!(
+                "Level \"{}\" is in the revision history of {} questions",
+                key, revised
+            )));
+        }
+
+        let deleted = conn
+            .execute("DELETE FROM difficulty_levels WHERE key = ?", params![key])?;
+        Ok(deleted > 0)
+    }
+}
+
+/// Rank of a level, `None` if it isn't defined
+pub(crate) fn rank_of(conn: &Connection, difficulty: &Difficulty) -> Result<Option<i32>, AppError> {
+    conn.query_row(
+        "SELECT rank FROM difficulty_levels WHERE key = ?",
+        params![difficulty],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(AppError::from)
+}
+
+/// Reject difficulties that aren't a defined level
+pub(crate) fn validate(conn: &Connection, difficulty: &Difficulty) -> Result<(), AppError> {
+    match rank_of(conn, difficulty)? {
+        Some(_) => Ok(()),
+        None => Err(
+            AppError::validation(format!("Unknown difficulty \"{}\"", difficulty))
+                .with_field("difficulty"),
+        ),
+    }
+}
+
+/// Define `difficulty` as a custom level after the hardest one if it isn't
+/// known yet. For data that arrives already written, such as an import or a
+/// sync pull, where rejecting the question would lose it.
+pub(crate) fn ensure_level(conn: &Connection, difficulty: &Difficulty) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT OR IGNORE INTO difficulty_levels (key, name, rank, built_in)
+         SELECT ?1, ?1, COALESCE(MAX(rank), 0) + ?2, 0 FROM difficulty_levels",
+        params![difficulty, RANK_STEP],
+    )?;
+    Ok(())
+}
+
+fn check_rank_free(
+    conn: &Connection,
+    rank: i32,
+    except: Option<&Difficulty>,
+) -> Result<(), AppError> {
+    let taken: Option<String> = conn
+        .query_row(
+            "SELECT key FROM difficulty_levels WHERE rank = ?",
+            params![rank],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match taken {
+        Some(key) if Some(key.as_str()) != except.map(|d| d.key()) => {
+            Err(AppError::conflict(format!("Rank {} is already used by \"{}\"", rank, key))
+                .with_field("rank"))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::UpdateQuestionDto;
+    use crate::database::repository::test_fixtures::{database, question, topic};
+    use crate::database::repository::LazyQuestionsRepository;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn test_delete_refuses_level_kept_in_revisions() {
+        let db = database();
+        let levels = DifficultyRepository::new(Arc::clone(&db));
+        let expert = levels
+            .create(CreateDifficultyLevelDto {
+                name: "Expert".to_string(),
+                rank: 400,
+            })
+            .unwrap()
+            .key;
+        let t = topic(&db, "Rust", None);
+        let q = question(&db, &t, 1, "What is variance?");
+
+        let questions = LazyQuestionsRepository::new(Arc::clone(&db));
+        let set = |difficulty: &Difficulty| {
+            questions
+                .update(
+                    &q,
+                    UpdateQuestionDto {
+                        difficulty: Some(difficulty.clone()),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        };
+        set(&expert);
+        let err = levels.delete(expert.key()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+
+        // Only a revision still names it
+        set(&Difficulty::Advanced);
+        let err = levels.delete(expert.key()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+        assert!(err.message.contains("revision history"), "{}", err.message);
+    }
+}
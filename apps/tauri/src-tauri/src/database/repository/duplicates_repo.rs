@@ -0,0 +1,426 @@
+use rusqlite::{params, OptionalExtension, Transaction};
+use std::sync::Arc;
+
+use crate::database::{
+    models::{
+        generate_id, DuplicateCluster, DuplicatePair, MergeResult, QuestionProgress, QuizResult,
+    },
+    repository::{
+        bulk_repo::active_question, lazy_topics_repo::subtree_ids, progress_repo::PROGRESS_COLUMNS,
+        revisions_repo::record_revision, LazyQuestionsRepository, ProgressRepository,
+    },
+    LazyDatabase,
+};
+use crate::error::AppError;
+use crate::utils::similarity::{clusters, jaccard, shingles};
+
+/// Similarity at or above which two questions count as duplicates
+pub const DEFAULT_THRESHOLD: f64 = 0.6;
+
+/// Finds questions entered more than once, e.g. by repeated markdown
+/// imports, and merges them into one.
+pub struct DuplicatesRepository {
+    db: Arc<LazyDatabase>,
+}
+
+impl DuplicatesRepository {
+    pub fn new(db: Arc<LazyDatabase>) -> Self {
+        Self { db }
+    }
+
+    /// Clusters of active questions whose normalized text scores at least
+    /// `threshold`, best first. `topic_id` limits the search to a topic and
+    /// the topics below it; without `across_topics` only questions in the
+    /// same topic are compared.
+    pub fn find(
+        &self,
+        topic_id: Option<&str>,
+        across_topics: bool,
+        threshold: Option<f64>,
+    ) -> Result<Vec<DuplicateCluster>, AppError> {
+        let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD);
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            return Err(
+                AppError::validation("Threshold must be greater than 0 and at most 1")
+                    .with_field("threshold"),
+            );
+        }
+
+        let questions = {
+            let conn = self.db.read_connection()?;
+            let mut sql =
+                "SELECT * FROM questions WHERE (deleted = 0 OR deleted IS NULL)".to_string();
+            let mut topics = Vec::new();
+            if let Some(topic_id) = topic_id {
+                topics = subtree_ids(&conn, topic_id)?;
+                sql.push_str(&format!(
+                    " AND topic_id IN ({})",
+                    vec!["?"; topics.len()].join(",")
+                ));
+            }
+            sql.push_str(" ORDER BY created_at, id");
+
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt
+                .query_map(
+                    rusqlite::params_from_iter(&topics),
+                    LazyQuestionsRepository::map_question,
+                )?;
+            let mut questions = Vec::new();
+            for q in rows {
+                questions.push(q?);
+            }
+            questions
+        };
+
+        let sets: Vec<Vec<u64>> = questions.iter().map(|q| shingles(&q.question)).collect();
+        let mut edges = Vec::new();
+        let mut scores = Vec::new();
+        for i in 0..questions.len() {
+            for j in i + 1..questions.len() {
+                if !across_topics && questions[i].topic_id != questions[j].topic_id {
+                    continue;
+                }
+                // Jaccard can't exceed the ratio of the set sizes
+                let (a, b) = (sets[i].len(), sets[j].len());
+                if (a.min(b) as f64) < threshold * a.max(b) as f64 {
+                    continue;
+                }
+                let score = jaccard(&sets[i], &sets[j]);
+                if score >= threshold {
+                    edges.push((i, j));
+                    scores.push(score);
+                }
+            }
+        }
+
+        let mut result: Vec<DuplicateCluster> = clusters(questions.len(), &edges)
+            .into_iter()
+            .map(|members| {
+                let mut pairs: Vec<DuplicatePair> = edges
+                    .iter()
+                    .zip(&scores)
+                    .filter(|((i, _), _)| members.contains(i))
+                    .map(|(&(i, j), &score)| DuplicatePair {
+                        first_id: questions[i].id.clone(),
+                        second_id: questions[j].id.clone(),
+                        score,
+                    })
+                    .collect();
+                pairs.sort_by(|a, b| b.score.total_cmp(&a.score));
+                DuplicateCluster {
+                    score: pairs.first().map(|p| p.score).unwrap_or(0.0),
+                    questions: members.iter().map(|&i| questions[i].clone()).collect(),
+                    pairs,
+                }
+            })
+            .collect();
+        result.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(result)
+    }
+
+    /// Merge `duplicate_ids` into `keep_id` in one transaction. The kept
+    /// question gains their tags, their progress is combined into its own,
+    /// and annotations, links, and quiz sessions are pointed at it. The
+    /// duplicates then go to the trash.
+    pub fn merge(&self, keep_id: &str, duplicate_ids: &[String]) -> Result<MergeResult, AppError> {
+        if duplicate_ids.is_empty() {
+            return Err(AppError::validation("No duplicates given").with_field("duplicateIds"));
+        }
+        for (i, id) in duplicate_ids.iter().enumerate() {
+            if id == keep_id {
+                return Err(
+                    AppError::validation("Cannot merge a question into itself")
+                        .with_field("duplicateIds"),
+                );
+            }
+            if duplicate_ids[..i].contains(id) {
+                return Err(AppError::validation(format!(
+                    "Question {} is listed more than once",
+                    id
+                ))
+                .with_field("duplicateIds"));
+            }
+        }
+
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let keep = active_question(&tx, keep_id)?
+            .ok_or_else(|| AppError::not_found("question", keep_id))?;
+        let mut duplicates = Vec::new();
+        for id in duplicate_ids {
+            duplicates.push(
+                active_question(&tx, id)?.ok_or_else(|| AppError::not_found("question", id))?,
+            );
+        }
+
+        // Tags
+        let mut tags = keep.tags.clone();
+        for tag in duplicates.iter().flat_map(|d| &d.tags) {
+            if !tags
+                .iter()
+                .any(|t| t.trim().eq_ignore_ascii_case(tag.trim()))
+            {
+                tags.push(tag.clone());
+            }
+        }
+        if tags != keep.tags {
+            record_revision(&tx, &keep, &now)?;
+            tx.execute(
+                "UPDATE questions SET tags = ?, updated_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE id = ?",
+                params![
+                    serde_json::to_string(&tags).unwrap_or("[]".to_string()),
+                    now,
+                    keep_id
+                ],
+            )?;
+        }
+
+        // Progress
+        let keep_progress = active_progress(&tx, keep_id)?;
+        let mut others = Vec::new();
+        for id in duplicate_ids {
+            others.extend(active_progress(&tx, id)?);
+        }
+        if let Some(progress) = merge_progress(keep_progress, others) {
+            tx.execute(
+                "INSERT INTO progress (question_id, id, topic_id, status, confidence_level, times_reviewed, times_correct, times_incorrect,
+                                       last_reviewed_at, next_review_at, created_at, updated_at, sync_version, synced_at, deleted)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, 1, NULL, 0)
+                 ON CONFLICT(question_id) DO UPDATE SET topic_id = ?3, status = ?4, confidence_level = ?5, times_reviewed = ?6,
+                        times_correct = ?7, times_incorrect = ?8, last_reviewed_at = ?9, next_review_at = ?10, created_at = ?11,
+                        updated_at = ?12, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1, deleted = 0, deleted_at = NULL",
+                params![
+                    keep_id,
+                    generate_id(),
+                    keep.topic_id,
+                    format!("{:?}", progress.status),
+                    progress.confidence_level,
+                    progress.times_reviewed,
+                    progress.times_correct,
+                    progress.times_incorrect,
+                    progress.last_reviewed_at,
+                    progress.next_review_at,
+                    progress.created_at,
+                    now,
+                ],
+            )?;
+        }
+
+        for id in duplicate_ids {
+            tx.execute(
+                "UPDATE annotations SET question_id = ?, updated_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE question_id = ? AND (deleted = 0 OR deleted IS NULL)",
+                params![keep_id, now, id],
+            )?;
+            // Links the kept question already has stay with the duplicate
+            // and go when it is purged
+            tx.execute(
+                "UPDATE OR IGNORE question_links SET source_id = ?1 WHERE source_id = ?2 AND target_id != ?1",
+                params![keep_id, id],
+            )?;
+            tx.execute(
+                "UPDATE OR IGNORE question_links SET target_id = ?1 WHERE target_id = ?2 AND source_id != ?1",
+                params![keep_id, id],
+            )?;
+        }
+
+        let quiz_sessions_updated = repoint_quiz_sessions(&tx, keep_id, duplicate_ids)?;
+
+        // Trash the duplicates, as a bulk delete does
+        let deleted_at = chrono::Utc::now().timestamp();
+        let batch = generate_id();
+        for id in duplicate_ids {
+            tx.execute(
+                "UPDATE progress SET deleted = 1, deleted_at = ?, deleted_batch = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE question_id = ? AND (deleted = 0 OR deleted IS NULL)",
+                params![deleted_at, batch, id],
+            )?;
+            tx.execute(
+                "UPDATE questions SET deleted = 1, deleted_at = ?, deleted_batch = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE id = ?",
+                params![deleted_at, batch, id],
+            )?;
+        }
+
+        let question = active_question(&tx, keep_id)?
+            .ok_or_else(|| AppError::not_found("question", keep_id))?;
+        tx.commit()?;
+
+        Ok(MergeResult {
+            question,
+            merged_ids: duplicate_ids.to_vec(),
+            quiz_sessions_updated,
+        })
+    }
+}
+
+fn active_progress(
+    tx: &Transaction,
+    question_id: &str,
+) -> Result<Option<QuestionProgress>, AppError> {
+    tx.query_row(
+        &format!(
+            "SELECT {} FROM progress WHERE question_id = ? AND (deleted = 0 OR deleted IS NULL)",
+            PROGRESS_COLUMNS
+        ),
+        params![question_id],
+        |row| ProgressRepository::map_progress_at(row, 0),
+    )
+    .optional()
+    .map_err(AppError::from)
+}
+
+/// Combine progress records of the same question: review counts add up,
+/// and status, confidence, and schedule come from the latest review. The
+/// kept question's own record wins when nothing was reviewed.
+fn merge_progress(
+    keep: Option<QuestionProgress>,
+    others: Vec<QuestionProgress>,
+) -> Option<QuestionProgress> {
+    let mut all: Vec<QuestionProgress> = keep.into_iter().chain(others).collect();
+    if all.is_empty() {
+        return None;
+    }
+
+    let times_reviewed = all.iter().map(|p| p.times_reviewed).sum();
+    let times_correct = all.iter().map(|p| p.times_correct).sum();
+    let times_incorrect = all.iter().map(|p| p.times_incorrect).sum();
+    let created_at = all.iter().map(|p| p.created_at.clone()).min();
+
+    // max_by_key keeps the last of equals, so search in reverse to prefer
+    // the kept record
+    let latest = all
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|(_, p)| p.last_reviewed_at.clone())
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    let mut merged = all.swap_remove(latest);
+    merged.times_reviewed = times_reviewed;
+    merged.times_correct = times_correct;
+    merged.times_incorrect = times_incorrect;
+    merged.created_at = created_at.unwrap_or(merged.created_at);
+    Some(merged)
+}
+
+/// Replace duplicates with the kept question in quiz sessions, keeping only
+/// the first entry and result per question. Returns the sessions changed.
+fn repoint_quiz_sessions(
+    tx: &Transaction,
+    keep_id: &str,
+    duplicate_ids: &[String],
+) -> Result<usize, AppError> {
+    let sessions: Vec<(String, String, String)> = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT id, COALESCE(question_ids, '[]'), COALESCE(results, '[]') FROM quiz_sessions
+                 WHERE deleted = 0 OR deleted IS NULL",
+            )?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        let mut sessions = Vec::new();
+        for s in rows {
+            sessions.push(s?);
+        }
+        sessions
+    };
+
+    let mut updated = 0;
+    for (id, question_ids_json, results_json) in sessions {
+        let question_ids: Vec<String> =
+            serde_json::from_str(&question_ids_json).unwrap_or_default();
+        let results: Vec<QuizResult> = serde_json::from_str(&results_json).unwrap_or_default();
+        let mentions = |qid: &String| duplicate_ids.contains(qid);
+        if !question_ids.iter().any(mentions) && !results.iter().any(|r| mentions(&r.question_id)) {
+            continue;
+        }
+
+        let mut new_ids: Vec<String> = Vec::new();
+        for qid in question_ids {
+            let qid = if mentions(&qid) {
+                keep_id.to_string()
+            } else {
+                qid
+            };
+            if !new_ids.contains(&qid) {
+                new_ids.push(qid);
+            }
+        }
+        let mut new_results: Vec<QuizResult> = Vec::new();
+        for mut result in results {
+            if mentions(&result.question_id) {
+                result.question_id = keep_id.to_string();
+            }
+            if !new_results
+                .iter()
+                .any(|r| r.question_id == result.question_id)
+            {
+                new_results.push(result);
+            }
+        }
+
+        tx.execute(
+            "UPDATE quiz_sessions SET question_ids = ?, results = ?, current_index = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+             WHERE id = ?",
+            params![
+                serde_json::to_string(&new_ids).unwrap_or("[]".to_string()),
+                serde_json::to_string(&new_results).unwrap_or("[]".to_string()),
+                new_results.len() as i32,
+                id
+            ],
+        )?;
+        updated += 1;
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::ProgressStatus;
+
+    fn progress(reviewed_at: Option<&str>, status: ProgressStatus, times: i32) -> QuestionProgress {
+        QuestionProgress {
+            question_id: "q".to_string(),
+            topic_id: "t".to_string(),
+            status,
+            confidence_level: times,
+            times_reviewed: times,
+            times_correct: times,
+            times_incorrect: 0,
+            last_reviewed_at: reviewed_at.map(str::to_string),
+            next_review_at: None,
+            created_at: format!("2024-01-0{}", times),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_merge_progress_takes_latest_review() {
+        let merged = merge_progress(
+            Some(progress(Some("2024-02-01"), ProgressStatus::Studying, 2)),
+            vec![
+                progress(Some("2024-03-01"), ProgressStatus::Mastered, 3),
+                progress(None, ProgressStatus::NotStudied, 1),
+            ],
+        )
+        .unwrap();
+        assert_eq!(merged.status, ProgressStatus::Mastered);
+        assert_eq!(merged.times_reviewed, 6);
+        assert_eq!(merged.created_at, "2024-01-01");
+
+        let unreviewed = merge_progress(
+            Some(progress(None, ProgressStatus::Studying, 1)),
+            vec![progress(None, ProgressStatus::NotStudied, 2)],
+        )
+        .unwrap();
+        assert_eq!(unreviewed.status, ProgressStatus::Studying);
+        assert!(merge_progress(None, Vec::new()).is_none());
+    }
+}
@@ -0,0 +1,447 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use std::sync::Arc;
+
+use crate::database::{
+    models::{
+        DateRange, Difficulty, Page, PageRequest, QuestionFilter, QuestionWithProgress, SortKey,
+    },
+    repository::{
+        difficulty_repo,
+        lazy_questions_repo::fts_match_query,
+        lazy_topics_repo::{subtree_ids, ARCHIVED_SQL},
+        pagination::{fetch_page, Listing},
+        LazyQuestionsRepository, ProgressRepository,
+    },
+    LazyDatabase,
+};
+use crate::error::AppError;
+use crate::utils::search_query::{SearchQuery, SearchQueryError, SearchTerm};
+
+/// Questions with their progress, if any; clauses below refer to `q` and `p`
+pub(crate) const QUESTIONS_WITH_PROGRESS_SQL: &str = "FROM questions q
+         LEFT JOIN progress p ON p.question_id = q.id AND (p.deleted = 0 OR p.deleted IS NULL)";
+
+// tags.name is COLLATE NOCASE
+const HAS_TAG_SQL: &str = "EXISTS (SELECT 1 FROM question_tags qt JOIN tags t ON t.id = qt.tag_id
+                     WHERE qt.question_id = q.id AND t.name = ?)";
+const TEXT_MATCH_SQL: &str =
+    "q.id IN (SELECT question_id FROM questions_fts WHERE questions_fts MATCH ?)";
+/// Questions without progress haven't been studied
+const STATUS_SQL: &str = "COALESCE(p.status, 'NotStudied')";
+
+/// Progress columns selected after `q.*`; the alias marks where they start
+const JOINED_PROGRESS_COLUMNS: &str = "p.question_id AS progress_question_id, p.topic_id, p.status, p.confidence_level, p.times_reviewed, p.times_correct, p.times_incorrect, p.last_reviewed_at, p.next_review_at, p.created_at, p.updated_at";
+
+/// Questions matching a `QuestionFilter`, joined with their progress. The
+/// filter is compiled to one parameterized query; no user input is ever
+/// spliced into the SQL.
+pub struct FilterRepository {
+    db: Arc<LazyDatabase>,
+}
+
+impl FilterRepository {
+    pub fn new(db: Arc<LazyDatabase>) -> Self {
+        Self { db }
+    }
+
+    pub fn filter(
+        &self,
+        filter: &QuestionFilter,
+        request: &PageRequest,
+    ) -> Result<Page<QuestionWithProgress>, AppError> {
+        let sort = request.sort.unwrap_or(SortKey::Order);
+        let sort_expr = match sort {
+            SortKey::Order => "q.order_index",
+            SortKey::Created => "q.created_at",
+            SortKey::Updated => "q.updated_at",
+            SortKey::QuestionNumber => "q.question_number",
+            // Never-reviewed questions sort first
+            SortKey::LastReviewed => "COALESCE(p.last_reviewed_at, '')",
+        };
+
+        let conn = self.db.read_connection()?;
+        let (from, params) = compile(&conn, filter)?;
+        fetch_page(
+            &conn,
+            Listing {
+                columns: &format!("q.*, {}", JOINED_PROGRESS_COLUMNS),
+                from: &from,
+                params,
+                id: "q.id",
+            },
+            sort,
+            sort_expr,
+            request,
+            false,
+            map_question_with_progress,
+        )
+    }
+}
+
+/// Number of questions matching `filter`
+pub(crate) fn count(conn: &Connection, filter: &QuestionFilter) -> Result<usize, AppError> {
+    let (from, params) = compile(conn, filter)?;
+    conn.query_row(
+        &format!("SELECT count(*) {}", from),
+        rusqlite::params_from_iter(&params),
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|n| n as usize)
+    .map_err(AppError::from)
+}
+
+fn map_question_with_progress(row: &rusqlite::Row) -> rusqlite::Result<QuestionWithProgress> {
+    let start = row.as_ref().column_index("progress_question_id")?;
+    let progress = match row.get::<_, Option<String>>(start)? {
+        Some(_) => Some(ProgressRepository::map_progress_at(row, start)?),
+        None => None,
+    };
+    Ok(QuestionWithProgress {
+        question: LazyQuestionsRepository::map_question(row)?,
+        progress,
+    })
+}
+
+/// Build `FROM ... WHERE ...` and its parameters for `filter`. Topics are
+/// expanded to their subtrees and difficulty levels resolved here, so
+/// unknown levels are rejected rather than silently matching nothing.
+pub(crate) fn compile(
+    conn: &Connection,
+    filter: &QuestionFilter,
+) -> Result<(String, Vec<Value>), AppError> {
+    let mut clauses = vec!["(q.deleted = 0 OR q.deleted IS NULL)".to_string()];
+    let mut params: Vec<Value> = Vec::new();
+
+    if !filter.topic_ids.is_empty() {
+        let mut topics: Vec<String> = Vec::new();
+        for id in &filter.topic_ids {
+            for tid in subtree_ids(conn, id)? {
+                if !topics.contains(&tid) {
+                    topics.push(tid);
+                }
+            }
+        }
+        clauses.push(format!("q.topic_id IN ({})", placeholders(topics.len())));
+        params.extend(topics.into_iter().map(Value::Text));
+    }
+
+    if !filter.include_archived {
+        clauses.push(format!("q.topic_id NOT IN ({})", ARCHIVED_SQL));
+    }
+
+    for tag in &filter.tags {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            continue;
+        }
+        clauses.push(HAS_TAG_SQL.to_string());
+        params.push(Value::Text(tag.to_string()));
+    }
+
+    if !filter.difficulties.is_empty() {
+        for diff in &filter.difficulties {
+            difficulty_repo::validate(conn, diff)?;
+            params.push(Value::Text(diff.key().to_string()));
+        }
+        clauses.push(format!(
+            "q.difficulty IN ({})",
+            placeholders(filter.difficulties.len())
+        ));
+    }
+
+    // Ranges compare level ranks, as quiz selection does
+    for (bound, op) in [
+        (&filter.min_difficulty, ">="),
+        (&filter.max_difficulty, "<="),
+    ] {
+        if let Some(diff) = bound {
+            let rank = difficulty_repo::rank_of(conn, diff)?
+                .ok_or_else(|| {
+                    AppError::validation(format!("Unknown difficulty \"{}\"", diff))
+                        .with_field(if op == ">=" { "minDifficulty" } else { "maxDifficulty" })
+                })?;
+            clauses.push(format!(
+                "(SELECT rank FROM difficulty_levels WHERE key = q.difficulty) {} ?",
+                op
+            ));
+            params.push(Value::Integer(rank as i64));
+        }
+    }
+
+    if !filter.statuses.is_empty() {
+        clauses.push(format!(
+            "{} IN ({})",
+            STATUS_SQL,
+            placeholders(filter.statuses.len())
+        ));
+        params.extend(
+            filter
+                .statuses
+                .iter()
+                .map(|s| Value::Text(format!("{:?}", s))),
+        );
+    }
+
+    if let (Some(min), Some(max)) = (filter.min_confidence, filter.max_confidence) {
+        if min > max {
+            return Err(AppError::validation("minConfidence is greater than maxConfidence")
+                .with_field("minConfidence"));
+        }
+    }
+    for (bound, op) in [(filter.min_confidence, ">="), (filter.max_confidence, "<=")] {
+        if let Some(confidence) = bound {
+            clauses.push(format!("COALESCE(p.confidence_level, 0) {} ?", op));
+            params.push(Value::Integer(confidence as i64));
+        }
+    }
+
+    if let Some(times) = filter.min_times_incorrect {
+        clauses.push("COALESCE(p.times_incorrect, 0) >= ?".to_string());
+        params.push(Value::Integer(times as i64));
+    }
+
+    for (range, column, name) in [
+        (&filter.created, "q.created_at", "created"),
+        (&filter.updated, "q.updated_at", "updated"),
+        (&filter.last_reviewed, "p.last_reviewed_at", "lastReviewed"),
+    ] {
+        if let Some(range) = range {
+            date_range_clauses(range, column, name, &mut clauses, &mut params)?;
+        }
+    }
+
+    if let Some(query) = filter.text.as_deref().and_then(fts_match_query) {
+        clauses.push(TEXT_MATCH_SQL.to_string());
+        params.push(Value::Text(query));
+    }
+
+    let from = format!(
+        "{} WHERE {}",
+        QUESTIONS_WITH_PROGRESS_SQL,
+        clauses.join(" AND ")
+    );
+    Ok((from, params))
+}
+
+/// Compile a parsed search box query into a condition over
+/// `QUESTIONS_WITH_PROGRESS_SQL` and its parameters. Difficulties and topic
+/// names are looked up here; an unknown one is reported at its position.
+pub(crate) fn compile_search(
+    conn: &Connection,
+    query: &SearchQuery,
+) -> Result<(String, Vec<Value>), AppError> {
+    let mut params = Vec::new();
+    let now = Utc::now().to_rfc3339();
+    let condition = compile_search_part(conn, query, &now, &mut params)?;
+    Ok((condition, params))
+}
+
+fn compile_search_part(
+    conn: &Connection,
+    query: &SearchQuery,
+    now: &str,
+    params: &mut Vec<Value>,
+) -> Result<String, AppError> {
+    let (parts, op) = match query {
+        SearchQuery::And(parts) => (parts, " AND "),
+        SearchQuery::Or(parts) => (parts, " OR "),
+        SearchQuery::Not(inner) => {
+            return Ok(format!(
+                "NOT ({})",
+                compile_search_part(conn, inner, now, params)?
+            ))
+        }
+        SearchQuery::Term(term, position) => {
+            return compile_search_term(conn, term, *position, now, params)
+        }
+    };
+    let mut sql = Vec::new();
+    for part in parts {
+        sql.push(compile_search_part(conn, part, now, params)?);
+    }
+    Ok(format!("({})", sql.join(op)))
+}
+
+fn compile_search_term(
+    conn: &Connection,
+    term: &SearchTerm,
+    position: usize,
+    now: &str,
+    params: &mut Vec<Value>,
+) -> Result<String, AppError> {
+    let sql = match term {
+        SearchTerm::Word(_) | SearchTerm::Phrase(_) => {
+            params.push(Value::Text(fts_term(term).unwrap_or_default()));
+            TEXT_MATCH_SQL.to_string()
+        }
+        SearchTerm::Tag(tag) => {
+            params.push(Value::Text(tag.clone()));
+            HAS_TAG_SQL.to_string()
+        }
+        SearchTerm::Difficulty(value) => {
+            let difficulty = Difficulty::parse(value);
+            if difficulty_repo::rank_of(conn, &difficulty)?.is_none() {
+                return Err(SearchQueryError::new(
+                    format!("Unknown difficulty \"{}\"", value),
+                    position,
+                )
+                .into());
+            }
+            params.push(Value::Text(difficulty.key().to_string()));
+            "q.difficulty = ?".to_string()
+        }
+        SearchTerm::Status(status) => {
+            params.push(Value::Text(format!("{:?}", status)));
+            format!("{} = ?", STATUS_SQL)
+        }
+        SearchTerm::Topic(name) => {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id FROM topics
+                     WHERE name = ? COLLATE NOCASE AND (deleted = 0 OR deleted IS NULL)",
+                )?;
+            let roots = stmt
+                .query_map([name], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            if roots.is_empty() {
+                return Err(SearchQueryError::new(
+                    format!("No topic named \"{}\"", name),
+                    position,
+                )
+                .into());
+            }
+            let mut topics: Vec<String> = Vec::new();
+            for root in roots {
+                for id in subtree_ids(conn, &root)? {
+                    if !topics.contains(&id) {
+                        topics.push(id);
+                    }
+                }
+            }
+            let sql = format!("q.topic_id IN ({})", placeholders(topics.len()));
+            params.extend(topics.into_iter().map(Value::Text));
+            sql
+        }
+        SearchTerm::Due => {
+            params.push(Value::Text(now.to_string()));
+            "(p.next_review_at IS NOT NULL AND p.next_review_at <= ?)".to_string()
+        }
+        SearchTerm::New => format!("{} = 'NotStudied'", STATUS_SQL),
+    };
+    Ok(sql)
+}
+
+/// FTS5 expression for a word (prefix) or phrase. Quotes are stripped, so
+/// FTS operators inside are matched literally.
+pub(crate) fn fts_term(term: &SearchTerm) -> Option<String> {
+    match term {
+        SearchTerm::Word(word) => Some(format!("\"{}\"*", word.replace('"', ""))),
+        SearchTerm::Phrase(phrase) => Some(format!("\"{}\"", phrase.replace('"', ""))),
+        _ => None,
+    }
+}
+
+fn date_range_clauses(
+    range: &DateRange,
+    column: &str,
+    name: &str,
+    clauses: &mut Vec<String>,
+    params: &mut Vec<Value>,
+) -> Result<(), AppError> {
+    let from = range.from.as_deref().map(normalize_timestamp).transpose();
+    let to = range.to.as_deref().map(normalize_timestamp).transpose();
+    let (from, to) = match (from, to) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(e), _) | (_, Err(e)) => {
+            return Err(e.context(format!("Invalid {} range", name)).with_field(name))
+        }
+    };
+    if let (Some(from), Some(to)) = (&from, &to) {
+        if from >= to {
+            return Err(AppError::validation(format!(
+                "Invalid {} range: from must be before to",
+                name
+            ))
+            .with_field(name));
+        }
+    }
+    if let Some(from) = from {
+        clauses.push(format!("{} >= ?", column));
+        params.push(Value::Text(from));
+    }
+    if let Some(to) = to {
+        clauses.push(format!("{} < ?", column));
+        params.push(Value::Text(to));
+    }
+    Ok(())
+}
+
+/// Timestamps are stored as UTC RFC 3339 strings, so bounds are converted
+/// to the same form to compare as text
+fn normalize_timestamp(input: &str) -> Result<String, AppError> {
+    let input = input.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+        return Ok(time.with_timezone(&Utc).to_rfc3339());
+    }
+    NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc().to_rfc3339())
+        .map_err(|_| {
+            AppError::validation(format!("\"{}\" is not a date or RFC 3339 timestamp", input))
+        })
+}
+
+fn placeholders(n: usize) -> String {
+    vec!["?"; n].join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn test_normalize_timestamp() {
+        assert_eq!(
+            normalize_timestamp("2024-03-01").unwrap(),
+            "2024-03-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            normalize_timestamp("2024-03-01T09:30:00+02:00").unwrap(),
+            "2024-03-01T07:30:00+00:00"
+        );
+        assert!(normalize_timestamp("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_date_range_clauses() {
+        let mut clauses = Vec::new();
+        let mut params = Vec::new();
+        let range = DateRange {
+            from: Some("2024-01-01".to_string()),
+            to: None,
+        };
+        date_range_clauses(&range, "q.created_at", "created", &mut clauses, &mut params).unwrap();
+        assert_eq!(clauses, vec!["q.created_at >= ?"]);
+        assert_eq!(
+            params,
+            vec![Value::Text("2024-01-01T00:00:00+00:00".to_string())]
+        );
+
+        let backwards = DateRange {
+            from: Some("2024-02-01".to_string()),
+            to: Some("2024-01-01".to_string()),
+        };
+        let err = date_range_clauses(
+            &backwards,
+            "q.created_at",
+            "created",
+            &mut clauses,
+            &mut params,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Validation);
+        assert_eq!(err.field.as_deref(), Some("created"));
+    }
+}
@@ -0,0 +1,419 @@
+use rusqlite::{params, Connection};
+use std::sync::Arc;
+
+use crate::database::{
+    models::{IntegrityIssue, IntegrityReport, IssueKind, RepairResult},
+    LazyDatabase,
+};
+use crate::error::AppError;
+
+/// How many affected ids to include per issue
+const SAMPLE_SIZE: usize = 10;
+
+/// Active progress rows whose question is soft-deleted or gone
+const ORPHANED_PROGRESS_SQL: &str = "SELECT p.question_id FROM progress p
+     LEFT JOIN questions q ON q.id = p.question_id
+     WHERE (p.deleted = 0 OR p.deleted IS NULL) AND (q.id IS NULL OR q.deleted = 1)
+     ORDER BY p.question_id";
+
+/// Progress filed under a different topic than its question. Deleted rows
+/// count too: restoring a topic finds its progress by `topic_id`.
+const PROGRESS_TOPIC_SQL: &str = "SELECT p.question_id FROM progress p
+     JOIN questions q ON q.id = p.question_id
+     WHERE p.topic_id != q.topic_id
+     ORDER BY p.question_id";
+
+/// Every active question sharing its topic and number with an older one
+const DUPLICATE_NUMBER_SQL: &str = "SELECT id FROM (
+         SELECT id, topic_id, question_number, ROW_NUMBER() OVER (
+             PARTITION BY topic_id, question_number ORDER BY created_at, id
+         ) AS n
+         FROM questions WHERE deleted = 0 OR deleted IS NULL
+     ) WHERE n > 1
+     ORDER BY topic_id, question_number";
+
+/// Unfinished quiz sessions listing a question that no longer exists.
+/// Trashed questions don't count, since a restore brings them back, and
+/// completed sessions are kept as they were taken.
+const QUIZ_MISSING_SQL: &str = "SELECT DISTINCT s.id FROM quiz_sessions s,
+         json_each(CASE WHEN json_valid(s.question_ids) THEN s.question_ids ELSE '[]' END) j
+     LEFT JOIN questions q ON q.id = j.value
+     WHERE (s.deleted = 0 OR s.deleted IS NULL) AND s.completed_at IS NULL AND q.id IS NULL
+     ORDER BY s.id";
+
+/// Active topics whose parent isn't an active topic, or that are their own
+/// ancestor. Sync can produce either, since it applies moves one at a time.
+const INVALID_PARENT_SQL: &str = "WITH RECURSIVE ancestors(id, ancestor) AS (
+         SELECT id, parent_id FROM topics WHERE parent_id IS NOT NULL
+         UNION
+         SELECT a.id, t.parent_id FROM ancestors a
+         JOIN topics t ON t.id = a.ancestor
+         WHERE t.parent_id IS NOT NULL
+     )
+     SELECT c.id FROM topics c
+     LEFT JOIN topics p ON p.id = c.parent_id AND (p.deleted = 0 OR p.deleted IS NULL)
+     WHERE (c.deleted = 0 OR c.deleted IS NULL) AND c.parent_id IS NOT NULL
+       AND (p.id IS NULL OR EXISTS (SELECT 1 FROM ancestors a WHERE a.id = c.id AND a.ancestor = c.id))
+     ORDER BY c.id";
+
+/// Finds and repairs inconsistencies the schema can't rule out on its own
+pub struct IntegrityRepository {
+    db: Arc<LazyDatabase>,
+}
+
+impl IntegrityRepository {
+    pub fn new(db: Arc<LazyDatabase>) -> Self {
+        Self { db }
+    }
+
+    pub fn check(&self) -> Result<IntegrityReport, AppError> {
+        let conn = self.db.read_connection()?;
+        // Run every query against one snapshot
+        let _snapshot = conn.unchecked_transaction()?;
+
+        let mut stmt = conn
+            .prepare("PRAGMA quick_check")?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))?;
+        let mut sqlite_errors = Vec::new();
+        for r in rows {
+            let line = r?;
+            if line != "ok" {
+                sqlite_errors.push(line);
+            }
+        }
+
+        let mut issues = Vec::new();
+        for (kind, sql) in [
+            (IssueKind::OrphanedProgress, ORPHANED_PROGRESS_SQL),
+            (IssueKind::ProgressTopicMismatch, PROGRESS_TOPIC_SQL),
+            (IssueKind::DuplicateQuestionNumber, DUPLICATE_NUMBER_SQL),
+            (IssueKind::QuizMissingQuestions, QUIZ_MISSING_SQL),
+            (IssueKind::InvalidTopicParent, INVALID_PARENT_SQL),
+        ] {
+            let ids = query_ids(&conn, sql)?;
+            if !ids.is_empty() {
+                issues.push(issue(kind, &ids));
+            }
+        }
+
+        Ok(IntegrityReport {
+            sqlite_errors,
+            issues,
+        })
+    }
+
+    /// Fix every issue class in one transaction, then check again.
+    /// Repairs bump `sync_version` so other devices pick them up.
+    pub fn repair(&self) -> Result<RepairResult, AppError> {
+        let mut repaired = Vec::new();
+        {
+            let conn = self.db.get_connection()?;
+            let mut conn = conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            let now = chrono::Utc::now().to_rfc3339();
+            let now_secs = chrono::Utc::now().timestamp();
+
+            // Delete orphaned progress alongside its question, so restoring
+            // the question from the trash brings the progress back too
+            let ids = query_ids(&tx, ORPHANED_PROGRESS_SQL)?;
+            for id in &ids {
+                tx.execute(
+                    "UPDATE progress SET deleted = 1,
+                        deleted_at = COALESCE((SELECT deleted_at FROM questions WHERE id = ?1), ?2),
+                        deleted_batch = (SELECT deleted_batch FROM questions WHERE id = ?1),
+                        synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                     WHERE question_id = ?1",
+                    params![id, now_secs],
+                )?;
+            }
+            if !ids.is_empty() {
+                repaired.push(issue(IssueKind::OrphanedProgress, &ids));
+            }
+
+            let ids = query_ids(&tx, PROGRESS_TOPIC_SQL)?;
+            for id in &ids {
+                tx.execute(
+                    "UPDATE progress SET topic_id = (SELECT topic_id FROM questions WHERE id = ?1),
+                        updated_at = ?2, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                     WHERE question_id = ?1",
+                    params![id, now],
+                )?;
+            }
+            if !ids.is_empty() {
+                repaired.push(issue(IssueKind::ProgressTopicMismatch, &ids));
+            }
+
+            // The oldest question keeps its number; later ones move to the end
+            let ids = query_ids(&tx, DUPLICATE_NUMBER_SQL)?;
+            for id in &ids {
+                tx.execute(
+                    "UPDATE questions SET
+                        question_number = (SELECT MAX(question_number) + 1 FROM questions
+                                           WHERE topic_id = (SELECT topic_id FROM questions WHERE id = ?1)),
+                        updated_at = ?2, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                     WHERE id = ?1",
+                    params![id, now],
+                )?;
+            }
+            if !ids.is_empty() {
+                repaired.push(issue(IssueKind::DuplicateQuestionNumber, &ids));
+            }
+
+            let ids = query_ids(&tx, QUIZ_MISSING_SQL)?;
+            for id in &ids {
+                prune_quiz_session(&tx, id)?;
+            }
+            if !ids.is_empty() {
+                repaired.push(issue(IssueKind::QuizMissingQuestions, &ids));
+            }
+
+            // Such topics move to the top level
+            let ids = query_ids(&tx, INVALID_PARENT_SQL)?;
+            for id in &ids {
+                tx.execute(
+                    "UPDATE topics SET parent_id = NULL, updated_at = ?2, synced_at = NULL,
+                        sync_version = COALESCE(sync_version, 0) + 1
+                     WHERE id = ?1",
+                    params![id, now],
+                )?;
+            }
+            if !ids.is_empty() {
+                repaired.push(issue(IssueKind::InvalidTopicParent, &ids));
+            }
+
+            tx.commit()?;
+        }
+
+        Ok(RepairResult {
+            repaired,
+            report: self.check()?,
+        })
+    }
+}
+
+fn query_ids(conn: &Connection, sql: &str) -> Result<Vec<String>, AppError> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))?;
+    let mut ids = Vec::new();
+    for r in rows {
+        ids.push(r?);
+    }
+    Ok(ids)
+}
+
+fn issue(kind: IssueKind, ids: &[String]) -> IntegrityIssue {
+    IntegrityIssue {
+        kind,
+        count: ids.len(),
+        sample_ids: ids.iter().take(SAMPLE_SIZE).cloned().collect(),
+    }
+}
+
+/// Drop purged questions from a quiz session, moving `current_index` back
+/// by however many of them came before it
+fn prune_quiz_session(conn: &Connection, session_id: &str) -> Result<(), AppError> {
+    let (question_ids, current_index): (String, i32) = conn
+        .query_row(
+            "SELECT question_ids, current_index FROM quiz_sessions WHERE id = ?",
+            params![session_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+    let question_ids: Vec<String> = serde_json::from_str(&question_ids).unwrap_or_default();
+
+    let mut kept = Vec::new();
+    let mut new_index = current_index;
+    for (i, qid) in question_ids.into_iter().enumerate() {
+        let exists: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM questions WHERE id = ?)",
+                params![qid],
+                |row| row.get(0),
+            )?;
+        if exists {
+            kept.push(qid);
+        } else if (i as i32) < current_index {
+            new_index -= 1;
+        }
+    }
+
+    conn.execute(
+        "UPDATE quiz_sessions SET question_ids = ?, current_index = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1 WHERE id = ?",
+        params![
+            serde_json::to_string(&kept).unwrap_or("[]".to_string()),
+            new_index,
+            session_id
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{ProgressStatus, UpdateProgressDto};
+    use crate::database::repository::test_fixtures::{database, deleted, question, topic};
+    use crate::database::repository::{LazyQuestionsRepository, ProgressRepository};
+
+    fn exec(db: &Arc<LazyDatabase>, sql: &str, params: &[&str]) {
+        let conn = db.get_connection().unwrap();
+        conn.lock()
+            .unwrap()
+            .execute(sql, rusqlite::params_from_iter(params))
+            .unwrap();
+    }
+
+    fn track(db: &Arc<LazyDatabase>, question_id: &str) {
+        ProgressRepository::new(Arc::clone(db))
+            .update(
+                question_id,
+                UpdateProgressDto {
+                    status: Some(ProgressStatus::Studying),
+                    confidence_level: None,
+                    was_correct: None,
+                },
+            )
+            .unwrap();
+    }
+
+    /// Ids `check` reports for `kind`, then the same after `repair`
+    fn check_and_repair(db: &Arc<LazyDatabase>, kind: IssueKind) -> (Vec<String>, Vec<String>) {
+        let repo = IntegrityRepository::new(Arc::clone(db));
+        let found = repo
+            .check()
+            .unwrap()
+            .issues
+            .into_iter()
+            .find(|i| i.kind == kind)
+            .map(|i| i.sample_ids)
+            .unwrap_or_default();
+        let result = repo.repair().unwrap();
+        assert!(result.report.is_ok(), "{:?}", result.report.issues);
+        let repaired = result
+            .repaired
+            .into_iter()
+            .find(|i| i.kind == kind)
+            .map(|i| i.sample_ids)
+            .unwrap_or_default();
+        (found, repaired)
+    }
+
+    #[test]
+    fn test_orphaned_progress() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q = question(&db, &t, 1, "What is a trait?");
+        track(&db, &q);
+        exec(&db, "UPDATE questions SET deleted = 1 WHERE id = ?", &[&q]);
+
+        let (found, repaired) = check_and_repair(&db, IssueKind::OrphanedProgress);
+        assert_eq!(found, vec![q.clone()]);
+        assert_eq!(repaired, found);
+        assert_eq!(deleted(&db, "progress", "question_id", &q), Some(true));
+    }
+
+    #[test]
+    fn test_progress_topic_mismatch() {
+        let db = database();
+        let t1 = topic(&db, "Rust", None);
+        let t2 = topic(&db, "Go", None);
+        let q = question(&db, &t1, 1, "What is a trait?");
+        track(&db, &q);
+        exec(
+            &db,
+            "UPDATE progress SET topic_id = ? WHERE question_id = ?",
+            &[&t2, &q],
+        );
+
+        let (found, repaired) = check_and_repair(&db, IssueKind::ProgressTopicMismatch);
+        assert_eq!(found, vec![q.clone()]);
+        assert_eq!(repaired, found);
+        let progress = ProgressRepository::new(Arc::clone(&db))
+            .get_by_topic(&t1)
+            .unwrap();
+        assert_eq!(progress.len(), 1);
+    }
+
+    #[test]
+    fn test_duplicate_question_number() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q1 = question(&db, &t, 1, "What is a trait?");
+        let q2 = question(&db, &t, 2, "What is a crate?");
+        exec(
+            &db,
+            "UPDATE questions SET question_number = 1 WHERE id = ?",
+            &[&q2],
+        );
+
+        let (found, repaired) = check_and_repair(&db, IssueKind::DuplicateQuestionNumber);
+        assert_eq!(found, vec![q2.clone()]);
+        assert_eq!(repaired, found);
+        let repo = LazyQuestionsRepository::new(Arc::clone(&db));
+        let number = |id: &str| repo.get_by_id(id).unwrap().unwrap().question_number;
+        assert_eq!((number(&q1), number(&q2)), (1, 2));
+    }
+
+    #[test]
+    fn test_quiz_missing_questions_prunes_only_purged_ids() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let kept = question(&db, &t, 1, "What is a trait?");
+        let purged = question(&db, &t, 2, "What is a crate?");
+        let trashed = question(&db, &t, 3, "What is a move?");
+        exec(&db, "DELETE FROM questions WHERE id = ?", &[&purged]);
+        assert!(LazyQuestionsRepository::new(Arc::clone(&db))
+            .delete(&trashed)
+            .unwrap());
+
+        let ids = serde_json::to_string(&[&purged, &kept, &trashed]).unwrap();
+        let insert = "INSERT INTO quiz_sessions (id, session_type, question_ids, current_index, started_at, completed_at)
+             VALUES (?1, 'random', ?2, 1, '2025-01-01T00:00:00Z', NULLIF(?3, ''))";
+        exec(&db, insert, &["open", &ids, ""]);
+        exec(&db, insert, &["done", &ids, "2025-01-01T01:00:00Z"]);
+
+        let (found, repaired) = check_and_repair(&db, IssueKind::QuizMissingQuestions);
+        assert_eq!(found, vec!["open".to_string()]);
+        assert_eq!(repaired, found);
+
+        let session = |id: &str| -> (String, i32) {
+            db.read_connection()
+                .unwrap()
+                .query_row(
+                    "SELECT question_ids, current_index FROM quiz_sessions WHERE id = ?",
+                    [id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap()
+        };
+        let pruned = serde_json::to_string(&[&kept, &trashed]).unwrap();
+        assert_eq!(session("open"), (pruned, 0));
+        assert_eq!(session("done"), (ids, 1));
+    }
+
+    #[test]
+    fn test_invalid_topic_parent() {
+        let db = database();
+        let parent = topic(&db, "Rust", None);
+        let child = topic(&db, "Traits", Some(&parent));
+        let stray = topic(&db, "Go", None);
+        exec(
+            &db,
+            "UPDATE topics SET parent_id = 'missing' WHERE id = ?",
+            &[&stray],
+        );
+        exec(
+            &db,
+            "UPDATE topics SET parent_id = ? WHERE id = ?",
+            &[&child, &parent],
+        );
+
+        let (mut found, repaired) = check_and_repair(&db, IssueKind::InvalidTopicParent);
+        let mut expected = vec![parent, child, stray];
+        expected.sort();
+        found.sort();
+        assert_eq!(found, expected);
+        assert_eq!(repaired.len(), 3);
+    }
+}
@@ -1,7 +1,18 @@
 use crate::database::models::{
-    generate_id, Answer, CreateQuestionDto, Question, UpdateQuestionDto,
+    generate_id, Answer, CreateQuestionDto, Page, PageRequest, Question, QuestionSearchResult,
+    SortKey, UpdateQuestionDto,
 };
+use crate::database::repository::bulk_repo::active_question;
+use crate::database::repository::difficulty_repo;
+use crate::database::repository::filter_repo::{
+    compile_search, fts_term, QUESTIONS_WITH_PROGRESS_SQL,
+};
+use crate::database::repository::pagination::{fetch_page, Listing};
+use crate::database::repository::revisions_repo::record_revision;
 use crate::database::LazyDatabase;
+use crate::error::AppError;
+use crate::utils::search_query::parse_search_query;
+use rusqlite::types::Value;
 use rusqlite::Connection;
 use rusqlite::{params, OptionalExtension};
 use std::sync::{Arc, Mutex};
@@ -15,127 +26,154 @@ impl LazyQuestionsRepository {
         Self { db }
     }
 
-    pub fn get_all(&self) -> Result<Vec<Question>, String> {
+    pub fn get_all(&self) -> Result<Vec<Question>, AppError> {
         self.query_questions(
             "SELECT * FROM questions WHERE deleted = 0 OR deleted IS NULL ORDER BY topic_id, order_index",
             params![],
         )
     }
 
-    pub fn get_by_id(&self, id: &str) -> Result<Option<Question>, String> {
+    pub fn get_by_id(&self, id: &str) -> Result<Option<Question>, AppError> {
         let questions =
             self.query_questions("SELECT * FROM questions WHERE id = ? AND (deleted = 0 OR deleted IS NULL)", params![id])?;
         Ok(questions.into_iter().next())
     }
 
-    pub fn get_by_topic_id(&self, topic_id: &str) -> Result<Vec<Question>, String> {
+    pub fn get_by_topic_id(&self, topic_id: &str) -> Result<Vec<Question>, AppError> {
         self.query_questions(
             "SELECT * FROM questions WHERE topic_id = ? AND (deleted = 0 OR deleted IS NULL) ORDER BY order_index",
             params![topic_id],
         )
     }
 
+    /// A page of active questions, of one topic if `topic_id` is given.
+    /// Sorted by order unless asked otherwise.
+    pub fn get_page(
+        &self,
+        topic_id: Option<&str>,
+        request: &PageRequest,
+    ) -> Result<Page<Question>, AppError> {
+        let sort = request.sort.unwrap_or(SortKey::Order);
+        let sort_expr = match sort {
+            SortKey::Order => "q.order_index",
+            SortKey::Created => "q.created_at",
+            SortKey::Updated => "q.updated_at",
+            SortKey::QuestionNumber => "q.question_number",
+            // Never-reviewed questions sort first
+            SortKey::LastReviewed => "COALESCE(p.last_reviewed_at, '')",
+        };
+
+        let mut from = "FROM questions q
+             LEFT JOIN progress p ON p.question_id = q.id AND (p.deleted = 0 OR p.deleted IS NULL)
+             WHERE (q.deleted = 0 OR q.deleted IS NULL)"
+            .to_string();
+        let mut params = Vec::new();
+        if let Some(topic_id) = topic_id {
+            from.push_str(" AND q.topic_id = ?");
+            params.push(topic_id.to_string().into());
+        }
+
+        let conn = self.db.read_connection()?;
+        fetch_page(
+            &conn,
+            Listing {
+                columns: "q.*",
+                from: &from,
+                params,
+                id: "q.id",
+            },
+            sort,
+            sort_expr,
+            request,
+            false,
+            Self::map_question,
+        )
+    }
+
     fn query_questions(
         &self,
         sql: &str,
         params: impl rusqlite::Params,
-    ) -> Result<Vec<Question>, String> {
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
+    ) -> Result<Vec<Question>, AppError> {
+        let conn = self.db.read_connection()?;
 
-        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(sql)?;
 
         let rows = stmt
-            .query_map(params, |row| {
-                let answer_json: String = row.get(5)?;
-                let answer: Answer = serde_json::from_str(&answer_json).unwrap_or(Answer {
-                    markdown: "".to_string(),
-                });
-
-                let tags_json: Option<String> = row.get(6)?;
-                let tags: Vec<String> = if let Some(json) = tags_json {
-                    serde_json::from_str(&json).unwrap_or_default()
-                } else {
-                    Vec::new()
-                };
-
-                Ok(Question {
-                    id: row.get(0)?,
-                    topic_id: row.get(1)?,
-                    subtopic: row.get(2)?,
-                    question_number: row.get(3)?,
-                    question: row.get(4)?,
-                    answer,
-                    tags,
-                    difficulty: row.get(7)?,
-                    order: row.get(8)?,
-                    created_at: row.get(9)?,
-                    updated_at: row.get(10)?,
-                })
-            })
-            .map_err(|e| e.to_string())?;
+            .query_map(params, Self::map_question)?;
 
         let mut questions = Vec::new();
         for q in rows {
-            questions.push(q.map_err(|e| e.to_string())?);
+            questions.push(q?);
         }
         Ok(questions)
     }
 
-    pub fn create(&self, dto: CreateQuestionDto) -> Result<Question, String> {
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
+    /// Map the `questions` columns of a row by name, as selected by
+    /// `SELECT *`, `q.*` or a column list. Where a join repeats a name the
+    /// first column wins, so select the question's columns first.
+    pub(crate) fn map_question(row: &rusqlite::Row) -> rusqlite::Result<Question> {
+        let answer_json: String = row.get("answer")?;
+        let answer: Answer = serde_json::from_str(&answer_json).unwrap_or(Answer {
+            markdown: "".to_string(),
+        });
+
+        let tags_json: Option<String> = row.get("tags")?;
+        let tags: Vec<String> = if let Some(json) = tags_json {
+            serde_json::from_str(&json).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+
+        Ok(Question {
+            id: row.get("id")?,
+            topic_id: row.get("topic_id")?,
+            question_number: row.get("question_number")?,
+            question: row.get("question")?,
+            answer,
+            tags,
+            difficulty: row.get("difficulty")?,
+            order: row.get("order_index")?,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+        })
+    }
+
+    /// Create a question at `dto.question_number`. Active questions of the
+    /// topic at that number or after it move down one to make room.
+    pub fn create(&self, dto: CreateQuestionDto) -> Result<Question, AppError> {
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
 
         // Check if topic exists
-        let topic_count: i64 = conn
+        let topic_count: i64 = tx
             .query_row(
                 "SELECT count(*) FROM topics WHERE id = ?",
                 params![dto.topic_id],
                 |row| row.get(0),
-            )
-            .map_err(|e| e.to_string())?;
+            )?;
 
         if topic_count == 0 {
-            return Err(format!("Topic with id {} not found", dto.topic_id));
+            return Err(AppError::not_found("topic", &dto.topic_id));
         }
+        difficulty_repo::validate(&tx, &dto.difficulty)?;
 
-        // Calculate question number if duplicate or auto?
-        // Original logic: "Use requested number if not duplicate, otherwise use max + 1"
-        // Let's replicate this.
-        let count_with_num: i64 = conn
-            .query_row(
-                "SELECT count(*) FROM questions WHERE topic_id = ? AND question_number = ?",
-                params![dto.topic_id, dto.question_number],
-                |row| row.get(0),
-            )
-            .unwrap_or(0);
-
-        let question_number = if count_with_num > 0 {
-            let max_num: Option<i32> = conn
-                .query_row(
-                    "SELECT MAX(question_number) FROM questions WHERE topic_id = ?",
-                    params![dto.topic_id],
-                    |row| row.get(0),
-                )
-                .unwrap_or(None);
-            max_num.unwrap_or(0) + 1
-        } else {
-            dto.question_number
-        };
+        let question_number = dto.question_number;
+        let now = chrono::Utc::now().to_rfc3339();
+        make_room(&tx, &dto.topic_id, question_number, None, &now)?;
 
         let id = generate_id();
-        let now = chrono::Utc::now().to_rfc3339();
         let answer_json = serde_json::to_string(&dto.answer).unwrap_or("{}".to_string());
         let tags_json = serde_json::to_string(&dto.tags).unwrap_or("[]".to_string());
 
-        conn.execute(
+        tx.execute(
             "INSERT INTO questions (
-                id, topic_id, subtopic, question_number, question, answer, tags, difficulty, order_index, created_at, updated_at, sync_version, synced_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 1, NULL)",
+                id, topic_id, question_number, question, answer, tags, difficulty, order_index, created_at, updated_at, sync_version, synced_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 1, NULL)",
             params![
                 id,
                 dto.topic_id,
-                dto.subtopic,
                 question_number,
                 dto.question,
                 answer_json,
@@ -145,12 +183,12 @@ impl LazyQuestionsRepository {
                 now,
                 now
             ]
-        ).map_err(|e| e.to_string())?;
+        )?;
+        tx.commit()?;
 
         Ok(Question {
             id,
             topic_id: dto.topic_id,
-            subtopic: dto.subtopic,
             question_number,
             question: dto.question,
             answer: dto.answer,
@@ -162,46 +200,38 @@ impl LazyQuestionsRepository {
         })
     }
 
-    pub fn update(&self, id: &str, dto: UpdateQuestionDto) -> Result<Option<Question>, String> {
-        // Since logic is complex (re-numbering, moving topics), let's implementation minimal robust version.
-        // Full replication of logic:
+    pub fn update(&self, id: &str, dto: UpdateQuestionDto) -> Result<Option<Question>, AppError> {
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
 
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
+        // Read the current row under the writer lock so nothing changes it in between
+        let current = match active_question(&tx, id)? {
+            Some(question) => question,
+            None => return Ok(None),
+        };
 
-        // Get current
-        let mut stmt = conn
-            .prepare("SELECT * FROM questions WHERE id = ?")
-            .map_err(|e| e.to_string())?;
-
-        // Manual mapping again? Or helper?
-        // Let's just fetch fields needed.
-        let exists = stmt.exists(params![id]).unwrap_or(false);
-        if !exists {
-            return Ok(None);
+        if let Some(diff) = &dto.difficulty {
+            difficulty_repo::validate(&tx, diff)?;
         }
 
-        drop(stmt);
-        drop(conn); // Drop lock to use self methods or re-acquire
+        let now = chrono::Utc::now().to_rfc3339();
 
-        // Fetch current question full object
-        let current_opts = self.get_by_id(id)?;
-        if current_opts.is_none() {
-            return Ok(None);
+        // Keep the old content as a revision when any of it changes
+        let content_changed = dto.question.as_ref().is_some_and(|q| *q != current.question)
+            || dto.answer.as_ref().is_some_and(|a| a.markdown != current.answer.markdown)
+            || dto.tags.as_ref().is_some_and(|t| *t != current.tags)
+            || dto.difficulty.as_ref().is_some_and(|d| *d != current.difficulty);
+        if content_changed {
+            record_revision(&tx, &current, &now)?;
         }
-        let current = current_opts.unwrap();
-
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
-
-        let now = chrono::Utc::now().to_rfc3339();
 
         let new_topic_id = dto.topic_id.clone().unwrap_or(current.topic_id.clone());
         let topic_changed = new_topic_id != current.topic_id;
 
         // Check target topic exists
         if topic_changed {
-            let topic_exists: i64 = conn
+            let topic_exists: i64 = tx
                 .query_row(
                     "SELECT count(*) FROM topics WHERE id = ?",
                     params![new_topic_id],
@@ -209,7 +239,7 @@ impl LazyQuestionsRepository {
                 )
                 .unwrap_or(0);
             if topic_exists == 0 {
-                return Err(format!("Topic {} not found", new_topic_id));
+                return Err(AppError::not_found("topic", new_topic_id));
             }
         }
 
@@ -217,28 +247,11 @@ impl LazyQuestionsRepository {
         let mut final_question_number = current.question_number;
 
         if let Some(req_num) = dto.question_number {
-            // If topic changed or num changed, check duplicate in TARGET topic
-            let check_duplicate = topic_changed || req_num != current.question_number;
-
-            if check_duplicate {
-                let count: i64 = conn.query_row(
-                    "SELECT count(*) FROM questions WHERE topic_id = ? AND question_number = ? AND id != ?",
-                    params![new_topic_id, req_num, id],
-                    |row| row.get(0)
-                ).unwrap_or(0);
-
-                if count > 0 {
-                    let max: Option<i32> = conn
-                        .query_row(
-                            "SELECT MAX(question_number) FROM questions WHERE topic_id = ?",
-                            params![new_topic_id],
-                            |row| row.get(0),
-                        )
-                        .unwrap_or(None);
-                    final_question_number = max.unwrap_or(0) + 1;
-                } else {
-                    final_question_number = req_num;
-                }
+            // Moving onto a taken number in the target topic shifts the
+            // questions from there on down one, as `create` does
+            if topic_changed || req_num != current.question_number {
+                make_room(&tx, &new_topic_id, req_num, Some(id), &now)?;
+                final_question_number = req_num;
             }
         }
 
@@ -262,10 +275,6 @@ impl LazyQuestionsRepository {
             set_clauses.push("topic_id = ?".to_string());
             params_vec.push(Box::new(tid.clone()));
         }
-        if let Some(sub) = &dto.subtopic {
-            set_clauses.push("subtopic = ?".to_string());
-            params_vec.push(Box::new(sub.clone()));
-        }
 
         // Always set number if it might have changed due to logic
         if final_question_number != current.question_number {
@@ -303,67 +312,371 @@ impl LazyQuestionsRepository {
 
         let params_refs: Vec<&dyn rusqlite::ToSql> =
             params_vec.iter().map(|b| b.as_ref()).collect();
-        conn.execute(&sql, rusqlite::params_from_iter(params_refs))
-            .map_err(|e| e.to_string())?;
+        tx.execute(&sql, rusqlite::params_from_iter(params_refs))?;
+
+        // Progress follows the question to its new topic
+        if topic_changed {
+            tx.execute(
+                "UPDATE progress SET topic_id = ?, updated_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1 WHERE question_id = ?",
+                params![new_topic_id, now, id],
+            )?;
+        }
 
+        tx.commit()?;
         drop(conn);
 
         self.get_by_id(id)
     }
 
-    pub fn delete(&self, id: &str) -> Result<bool, String> {
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
+    /// Move a question and its progress to the trash. Returns `false` if the
+    /// question doesn't exist or is already trashed, which keeps the batch it
+    /// was trashed with.
+    pub fn delete(&self, id: &str) -> Result<bool, AppError> {
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .map(|d| d.as_secs() as i64)
             .unwrap_or(0);
+        let batch = generate_id();
+
+        // Soft-delete the question. Its links stay for a restore; link
+        // queries skip trashed questions.
+        let count = tx
+            .execute(
+                "UPDATE questions SET deleted = 1, deleted_at = ?, deleted_batch = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1 WHERE id = ? AND (deleted = 0 OR deleted IS NULL)",
+                params![now, batch, id],
+            )?;
+        if count == 0 {
+            return Ok(false);
+        }
 
         // Soft-delete child progress
-        conn.execute(
-            "UPDATE progress SET deleted = 1, deleted_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1 WHERE question_id = ? AND (deleted = 0 OR deleted IS NULL)",
-            params![now, id],
-        ).map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE progress SET deleted = 1, deleted_at = ?, deleted_batch = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1 WHERE question_id = ? AND (deleted = 0 OR deleted IS NULL)",
+            params![now, batch, id],
+        )?;
 
-        // Soft-delete the question
-        let count = conn
-            .execute(
-                "UPDATE questions SET deleted = 1, deleted_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1 WHERE id = ?",
-                params![now, id],
-            )
-            .map_err(|e| e.to_string())?;
-        Ok(count > 0)
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Put a topic's questions in the order of `ids`, which must list every
+    /// active question of the topic once. Returns how many moved.
+    pub fn reorder(&self, topic_id: &str, ids: &[String]) -> Result<usize, AppError> {
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let current = active_ids_in_topic(&tx, topic_id)?;
+        check_order(ids, &current)?;
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut moved = 0;
+        for (index, id) in ids.iter().enumerate() {
+            moved += tx
+                .execute(
+                    "UPDATE questions SET order_index = ?, updated_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                     WHERE id = ? AND order_index IS NOT ?",
+                    params![index as i32, now, id, index as i32],
+                )?;
+        }
+
+        tx.commit()?;
+        Ok(moved)
     }
 
-    pub fn count(&self) -> Result<usize, String> {
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
+    /// Number a topic's active questions 1, 2, 3... in their display order,
+    /// closing gaps and splitting duplicates. Returns how many changed.
+    pub fn renumber_topic(&self, topic_id: &str) -> Result<usize, AppError> {
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let current = active_ids_in_topic(&tx, topic_id)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut changed = 0;
+        for (index, id) in current.iter().enumerate() {
+            let number = index as i32 + 1;
+            changed += tx
+                .execute(
+                    "UPDATE questions SET question_number = ?, updated_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                     WHERE id = ? AND question_number != ?",
+                    params![number, now, id, number],
+                )?;
+        }
+
+        tx.commit()?;
+        Ok(changed)
+    }
+
+    pub fn count(&self) -> Result<usize, AppError> {
+        let conn = self.db.read_connection()?;
         let count: i64 = conn
-            .query_row("SELECT count(*) FROM questions", [], |r| r.get(0))
-            .map_err(|e| e.to_string())?;
+            .query_row("SELECT count(*) FROM questions", [], |r| r.get(0))?;
         Ok(count as usize)
     }
 
-    pub fn count_by_topic(&self, topic_id: &str) -> Result<usize, String> {
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
+    pub fn count_by_topic(&self, topic_id: &str) -> Result<usize, AppError> {
+        let conn = self.db.read_connection()?;
         let count: i64 = conn
             .query_row(
                 "SELECT count(*) FROM questions WHERE topic_id = ?",
                 params![topic_id],
                 |r| r.get(0),
-            )
-            .map_err(|e| e.to_string())?;
+            )?;
         Ok(count as usize)
     }
 
-    pub fn search(&self, keyword: &str) -> Result<Vec<Question>, String> {
-        let keyword_param = format!("%{}%", keyword);
-        // SQLite doesn't have easy JSON search, so we check question text and maybe answer text (which is JSON string)
-        // Or if answer is markdown inside JSON, simple LIKE works on the JSON string too.
-        self.query_questions(
-            "SELECT * FROM questions WHERE (deleted = 0 OR deleted IS NULL) AND (question LIKE ? OR answer LIKE ?)",
-            params![keyword_param, keyword_param],
-        )
+    /// Search with the query language in `utils::search_query`. Text terms
+    /// are matched over question, answer markdown and tags; results matching
+    /// them are ordered by BM25 rank (best first) and come with a snippet,
+    /// the rest follow in topic order with an empty snippet.
+    pub fn search(&self, keyword: &str) -> Result<Vec<QuestionSearchResult>, AppError> {
+        let query = match parse_search_query(keyword)? {
+            Some(q) => q,
+            None => return Ok(Vec::new()),
+        };
+
+        let conn = self.db.read_connection()?;
+        let (condition, condition_params) = compile_search(&conn, &query)?;
+
+        let rank_query: Vec<String> = query
+            .text_terms()
+            .into_iter()
+            .filter_map(fts_term)
+            .collect();
+        let mut params = Vec::new();
+        // Column weights follow the FTS column order:
+        // question_id (unindexed), question, answer, tags, subtopic
+        let ranked = if rank_query.is_empty() {
+            "SELECT NULL AS question_id, NULL AS rank, NULL AS snippet".to_string()
+        } else {
+            params.push(Value::Text(rank_query.join(" OR ")));
+            "SELECT question_id,
+                    bm25(questions_fts, 0.0, 10.0, 1.0, 5.0, 2.0) AS rank,
+                    snippet(questions_fts, -1, '<mark>', '</mark>', '…', 16) AS snippet
+             FROM questions_fts WHERE questions_fts MATCH ?"
+                .to_string()
+        };
+        params.extend(condition_params);
+        params.push(Value::Integer(SEARCH_LIMIT));
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT q.*, COALESCE(r.rank, 0.0) AS rank, COALESCE(r.snippet, '') AS snippet
+                 {}
+                 LEFT JOIN ({}) r ON r.question_id = q.id
+                 WHERE (q.deleted = 0 OR q.deleted IS NULL) AND {}
+                 ORDER BY r.rank IS NULL, r.rank, q.topic_id, q.order_index
+                 LIMIT ?",
+                QUESTIONS_WITH_PROGRESS_SQL, ranked, condition
+            ))?;
+
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(&params), |row| {
+                Ok(QuestionSearchResult {
+                    question: Self::map_question(row)?,
+                    rank: row.get("rank")?,
+                    snippet: row.get("snippet")?,
+                })
+            })?;
+
+        let mut results = Vec::new();
+        for r in rows {
+            results.push(r?);
+        }
+        Ok(results)
+    }
+}
+
+/// Maximum number of hits returned by a full-text search
+const SEARCH_LIMIT: i64 = 200;
+
+/// Turn free-form user input into a safe FTS5 MATCH expression.
+///
+/// Every whitespace-separated term becomes a quoted prefix query, so FTS
+/// operators and punctuation typed by the user are matched literally and all
+/// terms must be present. Returns `None` when there is nothing to search for.
+pub fn fts_match_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .map(|term| term.replace('"', ""))
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"*", term))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Active question ids of an active topic, in display order
+fn active_ids_in_topic(conn: &Connection, topic_id: &str) -> Result<Vec<String>, AppError> {
+    let topic_active: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM topics WHERE id = ? AND (deleted = 0 OR deleted IS NULL))",
+            params![topic_id],
+            |row| row.get(0),
+        )?;
+    if !topic_active {
+        return Err(AppError::not_found("topic", topic_id));
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id FROM questions WHERE topic_id = ? AND (deleted = 0 OR deleted IS NULL)
+             ORDER BY order_index, question_number, created_at, id",
+        )?;
+    let rows = stmt
+        .query_map(params![topic_id], |row| row.get(0))?;
+    let mut ids = Vec::new();
+    for id in rows {
+        ids.push(id?);
+    }
+    Ok(ids)
+}
+
+/// Check that `ids`, a requested order, lists each of `current` exactly once
+pub(crate) fn check_order(ids: &[String], current: &[String]) -> Result<(), AppError> {
+    let mut seen = std::collections::HashSet::new();
+    for id in ids {
+        if !seen.insert(id) {
+            return Err(
+                AppError::validation(format!("{} is listed more than once", id)).with_field("ids"),
+            );
+        }
+        if !current.contains(id) {
+            return Err(AppError::validation(format!(
+                "{} is not one of the items being ordered",
+                id
+            ))
+            .with_field("ids"));
+        }
+    }
+    if let Some(missing) = current.iter().find(|id| !seen.contains(id)) {
+        return Err(
+            AppError::validation(format!("{} is missing from the new order", missing))
+                .with_field("ids"),
+        );
+    }
+    Ok(())
+}
+
+/// Free `number` in a topic by moving the active questions at it and after
+/// it down one. `except` stays put, for a question moving itself.
+fn make_room(
+    conn: &Connection,
+    topic_id: &str,
+    number: i32,
+    except: Option<&str>,
+    now: &str,
+) -> Result<(), AppError> {
+    let taken: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM questions WHERE topic_id = ?1 AND question_number = ?2 AND id IS NOT ?3 AND (deleted = 0 OR deleted IS NULL))",
+        params![topic_id, number, except],
+        |row| row.get(0),
+    )?;
+    if taken {
+        conn.execute(
+            "UPDATE questions SET question_number = question_number + 1, updated_at = ?1, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+             WHERE topic_id = ?2 AND question_number >= ?3 AND id IS NOT ?4 AND (deleted = 0 OR deleted IS NULL)",
+            params![now, topic_id, number, except],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::repository::test_fixtures::{database, question, topic};
+    use crate::database::repository::{LazyTopicsRepository, TrashRepository};
+
+    #[test]
+    fn test_fts_match_query() {
+        assert_eq!(fts_match_query("rust"), Some("\"rust\"*".to_string()));
+        assert_eq!(
+            fts_match_query("  borrow   checker "),
+            Some("\"borrow\"* \"checker\"*".to_string())
+        );
+        assert_eq!(
+            fts_match_query("say \"hi\" OR-NOT"),
+            Some("\"say\"* \"hi\"* \"OR-NOT\"*".to_string())
+        );
+        assert_eq!(fts_match_query("   "), None);
+        assert_eq!(fts_match_query("\"\""), None);
+    }
+
+    #[test]
+    fn test_check_order() {
+        let current = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let order = |ids: &[&str]| ids.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(check_order(&order(&["c", "a", "b"]), &current).is_ok());
+        assert!(check_order(&order(&["c", "a"]), &current).is_err());
+        assert!(check_order(&order(&["c", "a", "b", "a"]), &current).is_err());
+        assert!(check_order(&order(&["c", "a", "d"]), &current).is_err());
+    }
+
+    #[test]
+    fn test_delete_leaves_trashed_question_in_its_batch() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q = question(&db, &t, 1, "What is a trait?");
+        LazyTopicsRepository::new(Arc::clone(&db))
+            .delete(&t)
+            .unwrap();
+
+        let repo = LazyQuestionsRepository::new(Arc::clone(&db));
+        assert!(!repo.delete(&q).unwrap());
+        assert!(!repo.delete("missing").unwrap());
+
+        TrashRepository::new(Arc::clone(&db))
+            .restore_topic(&t)
+            .unwrap();
+        assert!(repo.get_by_id(&q).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_update_to_taken_number_shifts_the_rest() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q1 = question(&db, &t, 1, "What is a trait?");
+        let q2 = question(&db, &t, 2, "What is a crate?");
+        let q3 = question(&db, &t, 3, "What is a move?");
+
+        let repo = LazyQuestionsRepository::new(Arc::clone(&db));
+        let moved = repo
+            .update(
+                &q3,
+                UpdateQuestionDto {
+                    question_number: Some(1),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(moved.question_number, 1);
+
+        let number = |id: &str| repo.get_by_id(id).unwrap().unwrap().question_number;
+        assert_eq!((number(&q1), number(&q2)), (2, 3));
+    }
+
+    #[test]
+    fn test_update_skips_trashed_question() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q = question(&db, &t, 1, "What is a trait?");
+
+        let repo = LazyQuestionsRepository::new(Arc::clone(&db));
+        assert!(repo.delete(&q).unwrap());
+        let dto = UpdateQuestionDto {
+            question: Some("What is a lifetime?".to_string()),
+            ..Default::default()
+        };
+        assert!(repo.update(&q, dto).unwrap().is_none());
     }
 }
@@ -1,9 +1,59 @@
-use crate::database::models::{generate_id, CreateTopicDto, Topic, UpdateTopicDto};
+use crate::database::models::{
+    generate_id, CreateTopicDto, Page, PageRequest, SortKey, Topic, TopicNode, UpdateTopicDto,
+};
+use crate::database::repository::lazy_questions_repo::{check_order, fts_match_query};
+use crate::database::repository::pagination::{fetch_page, unsupported_sort, Listing};
 use crate::database::LazyDatabase;
+use crate::error::AppError;
 use rusqlite::params;
 use rusqlite::Connection;
+use rusqlite::OptionalExtension;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
+/// Ids of topic `?1` and every topic below it, for use as a subquery
+pub(crate) const SUBTREE_SQL: &str = "WITH RECURSIVE subtree(id) AS (
+        SELECT ?1
+        UNION
+        SELECT t.id FROM topics t JOIN subtree s ON t.parent_id = s.id
+    )
+    SELECT id FROM subtree";
+
+/// Ids of archived topics and every topic below them, for use as a subquery
+pub(crate) const ARCHIVED_SQL: &str = "WITH RECURSIVE archived_tree(id) AS (
+        SELECT id FROM topics WHERE archived = 1
+        UNION
+        SELECT t.id FROM topics t JOIN archived_tree a ON t.parent_id = a.id
+    )
+    SELECT id FROM archived_tree";
+
+/// Ids of `root` and all its descendants, trashed ones included
+pub(crate) fn subtree_ids(conn: &Connection, root: &str) -> Result<Vec<String>, AppError> {
+    let mut stmt = conn.prepare(SUBTREE_SQL)?;
+    let rows = stmt
+        .query_map(params![root], |row| row.get(0))?;
+    let mut ids = Vec::new();
+    for id in rows {
+        ids.push(id?);
+    }
+    Ok(ids)
+}
+
+/// Fail unless `id` is an active topic
+fn check_active(conn: &Connection, id: &str) -> Result<(), AppError> {
+    let active: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM topics WHERE id = ? AND (deleted = 0 OR deleted IS NULL))",
+            params![id],
+            |row| row.get(0),
+        )?;
+    if active {
+        Ok(())
+    } else {
+        Err(AppError::not_found("topic", id))
+    }
+}
+
 pub struct LazyTopicsRepository {
     db: Arc<LazyDatabase>,
 }
@@ -13,96 +63,185 @@ impl LazyTopicsRepository {
         Self { db }
     }
 
-    pub fn get_all(&self) -> Result<Vec<Topic>, String> {
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
+    /// Map the standard topic column list (id, name, ..., updated_at, archived)
+    pub(crate) fn map_topic(row: &rusqlite::Row) -> rusqlite::Result<Topic> {
+        Ok(Topic {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            description: row.get(2).unwrap_or_default(),
+            slug: row.get(3)?,
+            icon: row.get(4).unwrap_or_default(),
+            color: row.get(5).unwrap_or_default(),
+            parent_id: row.get(6)?,
+            order: row.get(7)?,
+            created_at: row.get(8)?,
+            updated_at: row.get(9)?,
+            archived: row.get(10)?,
+        })
+    }
 
-        let mut stmt = conn.prepare(
-            "SELECT id, name, description, slug, icon, color, subtopics, order_index, created_at, updated_at
-             FROM topics WHERE deleted = 0 OR deleted IS NULL ORDER BY order_index ASC"
-        ).map_err(|e| e.to_string())?;
+    /// Active topics. Archived ones, and topics nested under them, are left
+    /// out unless `include_archived` is set.
+    pub fn get_all(&self, include_archived: bool) -> Result<Vec<Topic>, AppError> {
+        let conn = self.db.read_connection()?;
+
+        let archived_filter = if include_archived {
+            String::new()
+        } else {
+            format!("AND id NOT IN ({})", ARCHIVED_SQL)
+        };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, description, slug, icon, color, parent_id, order_index, created_at, updated_at, archived
+             FROM topics WHERE (deleted = 0 OR deleted IS NULL) {} ORDER BY order_index ASC",
+            archived_filter
+        ))?;
 
         let topic_iter = stmt
-            .query_map([], |row| {
-                let subtopics_json: Option<String> = row.get(6)?;
-                let subtopics = if let Some(json) = subtopics_json {
-                    serde_json::from_str(&json).unwrap_or(Some(Vec::new()))
-                } else {
-                    Some(Vec::new())
-                };
-
-                Ok(Topic {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    description: row.get(2).unwrap_or_default(), // Handle NULL if schema allows, but schema says TEXT (nullable in my def?)
-                    // schema: description TEXT. default NULL.
-                    slug: row.get(3)?,
-                    icon: row.get(4).unwrap_or_default(),
-                    color: row.get(5).unwrap_or_default(),
-                    subtopics,
-                    order: row.get(7)?,
-                    created_at: row.get(8)?,
-                    updated_at: row.get(9)?,
-                })
-            })
-            .map_err(|e| e.to_string())?;
+            .query_map([], Self::map_topic)?;
 
         let mut topics = Vec::new();
         for topic in topic_iter {
-            topics.push(topic.map_err(|e| e.to_string())?);
+            topics.push(topic?);
         }
 
         Ok(topics)
     }
 
-    pub fn get_by_id(&self, id: &str) -> Result<Option<Topic>, String> {
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
+    /// A page of active topics, sorted by order unless asked otherwise
+    pub fn get_page(
+        &self,
+        include_archived: bool,
+        request: &PageRequest,
+    ) -> Result<Page<Topic>, AppError> {
+        let sort = request.sort.unwrap_or(SortKey::Order);
+        let sort_expr = match sort {
+            SortKey::Order => "order_index",
+            SortKey::Created => "created_at",
+            SortKey::Updated => "updated_at",
+            other => return Err(unsupported_sort("Topics", other)),
+        };
+
+        let mut from = "FROM topics WHERE (deleted = 0 OR deleted IS NULL)".to_string();
+        if !include_archived {
+            from.push_str(&format!(" AND id NOT IN ({})", ARCHIVED_SQL));
+        }
 
-        let mut stmt = conn.prepare(
-            "SELECT id, name, description, slug, icon, color, subtopics, order_index, created_at, updated_at 
-             FROM topics WHERE id = ?1"
-        ).map_err(|e| e.to_string())?;
-
-        let mut rows = stmt.query(params![id]).map_err(|e| e.to_string())?;
-
-        if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-            let subtopics_json: Option<String> = row.get(6).unwrap_or(None);
-            let subtopics = if let Some(json) = subtopics_json {
-                serde_json::from_str(&json).unwrap_or(Some(Vec::new()))
-            } else {
-                Some(Vec::new())
-            };
+        let conn = self.db.read_connection()?;
+        fetch_page(
+            &conn,
+            Listing {
+                columns: "id, name, description, slug, icon, color, parent_id, order_index, created_at, updated_at, archived",
+                from: &from,
+                params: Vec::new(),
+                id: "id",
+            },
+            sort,
+            sort_expr,
+            request,
+            false,
+            Self::map_topic,
+        )
+    }
 
-            Ok(Some(Topic {
-                id: row.get(0).unwrap_or_default(), // Should not be needed if row exists
-                name: row.get(1).unwrap_or_default(),
-                description: row.get(2).unwrap_or_default(),
-                slug: row.get(3).unwrap_or_default(),
-                icon: row.get(4).unwrap_or_default(),
-                color: row.get(5).unwrap_or_default(),
-                subtopics,
-                order: row.get(7).unwrap_or_default(),
-                created_at: row.get(8).unwrap_or_default(),
-                updated_at: row.get(9).unwrap_or_default(),
-            }))
-        } else {
-            Ok(None)
+    pub fn get_by_id(&self, id: &str) -> Result<Option<Topic>, AppError> {
+        let conn = self.db.read_connection()?;
+
+        conn.query_row(
+            "SELECT id, name, description, slug, icon, color, parent_id, order_index, created_at, updated_at, archived
+             FROM topics WHERE id = ?1",
+            params![id],
+            Self::map_topic,
+        )
+        .optional()
+        .map_err(AppError::from)
+    }
+
+    /// Active topics as a forest, each with question counts rolled up from
+    /// its descendants. A topic whose parent is gone or trashed is shown at
+    /// the top level. Archived subtrees are left out unless `include_archived`.
+    pub fn get_tree(&self, include_archived: bool) -> Result<Vec<TopicNode>, AppError> {
+        let topics = self.get_all(include_archived)?;
+        let counts: HashMap<String, usize> = {
+            let conn = self.db.read_connection()?;
+            let mut stmt = conn
+                .prepare(
+                    "SELECT topic_id, count(*) FROM questions
+                     WHERE deleted = 0 OR deleted IS NULL GROUP BY topic_id",
+                )?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))?;
+            let mut counts = HashMap::new();
+            for r in rows {
+                let (topic_id, count) = r?;
+                counts.insert(topic_id, count);
+            }
+            counts
+        };
+
+        let ids: HashSet<&str> = topics.iter().map(|t| t.id.as_str()).collect();
+        let mut children: HashMap<&str, Vec<&Topic>> = HashMap::new();
+        let mut roots = Vec::new();
+        for topic in &topics {
+            match topic.parent_id.as_deref() {
+                Some(parent) if ids.contains(parent) => {
+                    children.entry(parent).or_default().push(topic)
+                }
+                _ => roots.push(topic),
+            }
+        }
+
+        fn build(
+            topic: &Topic,
+            children: &HashMap<&str, Vec<&Topic>>,
+            counts: &HashMap<String, usize>,
+            visited: &mut HashSet<String>,
+        ) -> TopicNode {
+            visited.insert(topic.id.clone());
+            let mut nodes = Vec::new();
+            for child in children.get(topic.id.as_str()).into_iter().flatten() {
+                if !visited.contains(&child.id) {
+                    nodes.push(build(child, children, counts, visited));
+                }
+            }
+            let question_count = counts.get(&topic.id).copied().unwrap_or(0);
+            TopicNode {
+                topic: topic.clone(),
+                question_count,
+                total_question_count: question_count
+                    + nodes.iter().map(|n| n.total_question_count).sum::<usize>(),
+                children: nodes,
+            }
+        }
+
+        let mut visited = HashSet::new();
+        let mut tree: Vec<TopicNode> = roots
+            .into_iter()
+            .map(|t| build(t, &children, &counts, &mut visited))
+            .collect();
+        // Topics caught in a parent cycle are unreachable from any root;
+        // show them at the top level rather than losing them
+        for topic in &topics {
+            if !visited.contains(&topic.id) {
+                tree.push(build(topic, &children, &counts, &mut visited));
+            }
         }
+        Ok(tree)
     }
 
-    pub fn create(&self, dto: CreateTopicDto) -> Result<Topic, String> {
-        let conn = self.db.get_connection();
+    pub fn create(&self, dto: CreateTopicDto) -> Result<Topic, AppError> {
+        let conn = self.db.get_connection()?;
         let conn = conn.lock().unwrap();
 
+        if let Some(parent_id) = &dto.parent_id {
+            check_active(&conn, parent_id)?;
+        }
+
         let id = generate_id();
         let now = chrono::Utc::now().to_rfc3339();
 
-        let subtopics_json = serde_json::to_string(&dto.subtopics).unwrap_or("[]".to_string());
-
         conn.execute(
             "INSERT INTO topics (
-                id, name, description, slug, icon, color, subtopics, order_index, created_at, updated_at, sync_version, synced_at
+                id, name, description, slug, icon, color, parent_id, order_index, created_at, updated_at, sync_version, synced_at
             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 1, NULL)",
             params![
                 id,
@@ -111,12 +250,12 @@ impl LazyTopicsRepository {
                 dto.slug,
                 dto.icon,
                 dto.color,
-                subtopics_json,
+                dto.parent_id,
                 dto.order,
                 now,
                 now
             ],
-        ).map_err(|e| e.to_string())?;
+        )?;
 
         Ok(Topic {
             id,
@@ -125,15 +264,16 @@ impl LazyTopicsRepository {
             slug: dto.slug,
             icon: dto.icon,
             color: dto.color,
-            subtopics: dto.subtopics,
+            parent_id: dto.parent_id,
             order: dto.order,
             created_at: now.clone(),
             updated_at: now,
+            archived: false,
         })
     }
 
-    pub fn update(&self, id: &str, dto: UpdateTopicDto) -> Result<Option<Topic>, String> {
-        let conn = self.db.get_connection();
+    pub fn update(&self, id: &str, dto: UpdateTopicDto) -> Result<Option<Topic>, AppError> {
+        let conn = self.db.get_connection()?;
         let conn = conn.lock().unwrap();
 
         // Check if exists
@@ -142,8 +282,7 @@ impl LazyTopicsRepository {
                 "SELECT count(*) FROM topics WHERE id = ?1",
                 params![id],
                 |row| row.get(0),
-            )
-            .map_err(|e| e.to_string())?;
+            )?;
 
         if exists == 0 {
             return Ok(None);
@@ -202,12 +341,6 @@ impl LazyTopicsRepository {
             param_values.push(Box::new(color.clone()));
             param_idx += 1;
         }
-        if let Some(subtopics) = &dto.subtopics {
-            set_clauses.push(format!("subtopics = ?{}", param_idx));
-            let json = serde_json::to_string(subtopics).unwrap_or("[]".to_string());
-            param_values.push(Box::new(json));
-            param_idx += 1;
-        }
         if let Some(order) = dto.order {
             set_clauses.push(format!("order_index = ?{}", param_idx));
             param_values.push(Box::new(order));
@@ -225,14 +358,13 @@ impl LazyTopicsRepository {
         );
 
         // Execute
-        let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(&query)?;
 
         // rusqlite trait object params are a bit tricky with `params_from_iter`.
         // We need `&dyn ToSql`.
         let params_refs: Vec<&dyn rusqlite::ToSql> =
             param_values.iter().map(|b| b.as_ref()).collect();
-        stmt.execute(rusqlite::params_from_iter(params_refs))
-            .map_err(|e| e.to_string())?;
+        stmt.execute(rusqlite::params_from_iter(params_refs))?;
 
         // Fetch updated
         // Reuse get_by_id? But `get_by_id` takes `&self` and implementation creates a new lock.
@@ -248,77 +380,270 @@ impl LazyTopicsRepository {
         self.get_by_id(id)
     }
 
-    pub fn delete(&self, id: &str) -> Result<bool, String> {
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
+    /// Move a topic, with everything below it, under `parent_id`, or to
+    /// the top level when `None`. A topic can't move into its own subtree.
+    pub fn move_topic(&self, id: &str, parent_id: Option<&str>) -> Result<Option<Topic>, AppError> {
+        {
+            let conn = self.db.get_connection()?;
+            let conn = conn.lock().unwrap();
+
+            if check_active(&conn, id).is_err() {
+                return Ok(None);
+            }
+            if let Some(parent_id) = parent_id {
+                check_active(&conn, parent_id)?;
+                if subtree_ids(&conn, id)?.iter().any(|t| t == parent_id) {
+                    return Err(AppError::validation(
+                        "A topic cannot be moved under itself or one of its subtopics",
+                    )
+                    .with_field("parentId"));
+                }
+            }
+
+            conn.execute(
+                "UPDATE topics SET parent_id = ?1, updated_at = ?2, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE id = ?3",
+                params![parent_id, chrono::Utc::now().to_rfc3339(), id],
+            )?;
+        }
+
+        self.get_by_id(id)
+    }
+
+    /// Archive or unarchive a topic. Returns `None` if it isn't active.
+    pub fn set_archived(&self, id: &str, archived: bool) -> Result<Option<Topic>, AppError> {
+        {
+            let conn = self.db.get_connection()?;
+            let conn = conn.lock().unwrap();
+            if check_active(&conn, id).is_err() {
+                return Ok(None);
+            }
+            conn.execute(
+                "UPDATE topics SET archived = ?1, updated_at = ?2, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE id = ?3 AND archived != ?1",
+                params![archived, chrono::Utc::now().to_rfc3339(), id],
+            )?;
+        }
+
+        self.get_by_id(id)
+    }
+
+    /// Put the topics under one parent in the order of `ids`, which must
+    /// list every active topic under that parent once. Returns how many moved.
+    pub fn reorder(&self, ids: &[String]) -> Result<usize, AppError> {
+        let Some(first) = ids.first() else {
+            return Ok(0);
+        };
+
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        check_active(&tx, first)?;
+        let parent_id: Option<String> = tx
+            .query_row(
+                "SELECT parent_id FROM topics WHERE id = ?",
+                params![first],
+                |row| row.get(0),
+            )?;
+        let mut stmt = tx
+            .prepare(
+                "SELECT id FROM topics WHERE parent_id IS ? AND (deleted = 0 OR deleted IS NULL)",
+            )?;
+        let siblings = stmt
+            .query_map(params![parent_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        drop(stmt);
+        check_order(ids, &siblings)?;
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut moved = 0;
+        for (index, id) in ids.iter().enumerate() {
+            moved += tx
+                .execute(
+                    "UPDATE topics SET order_index = ?1, updated_at = ?2, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                     WHERE id = ?3 AND order_index IS NOT ?1",
+                    params![index as i32, now, id],
+                )?;
+        }
+
+        tx.commit()?;
+        Ok(moved)
+    }
+
+    /// Move a topic and its whole subtree to the trash. Everything deleted
+    /// here shares one `deleted_batch`, so a restore brings it all back.
+    pub fn delete(&self, id: &str) -> Result<bool, AppError> {
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .map(|d| d.as_secs() as i64)
             .unwrap_or(0);
+        let batch = generate_id();
 
         // Soft-delete child questions first
-        conn.execute(
-            "UPDATE questions SET deleted = 1, deleted_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1 WHERE topic_id = ? AND (deleted = 0 OR deleted IS NULL)",
-            params![now, id],
-        ).map_err(|e| e.to_string())?;
+        tx.execute(
+            &format!(
+                "UPDATE questions SET deleted = 1, deleted_at = ?2, deleted_batch = ?3, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE topic_id IN ({}) AND (deleted = 0 OR deleted IS NULL)",
+                SUBTREE_SQL
+            ),
+            params![id, now, batch],
+        )?;
 
         // Soft-delete child progress
-        conn.execute(
-            "UPDATE progress SET deleted = 1, deleted_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1 WHERE topic_id = ? AND (deleted = 0 OR deleted IS NULL)",
-            params![now, id],
-        ).map_err(|e| e.to_string())?;
-
-        // Soft-delete the topic
-        let count = conn
+        tx.execute(
+            &format!(
+                "UPDATE progress SET deleted = 1, deleted_at = ?2, deleted_batch = ?3, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE topic_id IN ({}) AND (deleted = 0 OR deleted IS NULL)",
+                SUBTREE_SQL
+            ),
+            params![id, now, batch],
+        )?;
+
+        // Soft-delete the descendant topics, then the topic itself
+        tx.execute(
+            &format!(
+                "UPDATE topics SET deleted = 1, deleted_at = ?2, deleted_batch = ?3, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE id IN ({}) AND id != ?1 AND (deleted = 0 OR deleted IS NULL)",
+                SUBTREE_SQL
+            ),
+            params![id, now, batch],
+        )?;
+        let count = tx
             .execute(
-                "UPDATE topics SET deleted = 1, deleted_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1 WHERE id = ?",
-                params![now, id],
-            )
-            .map_err(|e| e.to_string())?;
+                "UPDATE topics SET deleted = 1, deleted_at = ?, deleted_batch = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1 WHERE id = ?",
+                params![now, batch, id],
+            )?;
+        tx.commit()?;
 
         Ok(count > 0)
     }
-    pub fn search(&self, keyword: &str) -> Result<Vec<Topic>, String> {
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
-        let keyword_param = format!("%{}%", keyword);
-
+    /// Search topics by name/description, plus topics whose questions match
+    /// the full-text index. Name/description matches come first, then topics
+    /// ordered by their best-ranked matching question.
+    pub fn search(&self, keyword: &str) -> Result<Vec<Topic>, AppError> {
+        let match_query = match fts_match_query(keyword) {
+            Some(q) => q,
+            None => return Ok(Vec::new()),
+        };
+
+        let conn = self.db.read_connection()?;
+        let keyword_param = format!("%{}%", keyword.trim());
+
+        // bm25() cannot be used inside an aggregate, so rank question hits in a
+        // materialized CTE first and aggregate per topic afterwards.
         let mut stmt = conn.prepare(
-            "SELECT id, name, description, slug, icon, color, subtopics, order_index, created_at, updated_at
-             FROM topics
-             WHERE (deleted = 0 OR deleted IS NULL) AND (name LIKE ?1 OR description LIKE ?2)
-             ORDER BY order_index ASC"
-        ).map_err(|e| e.to_string())?;
+            "WITH hits AS MATERIALIZED (
+                SELECT q.topic_id AS topic_id, bm25(questions_fts) AS score
+                FROM questions_fts
+                JOIN questions q ON q.id = questions_fts.question_id
+                WHERE questions_fts MATCH ?1 AND (q.deleted = 0 OR q.deleted IS NULL)
+             ),
+             topic_hits AS (
+                SELECT topic_id, MIN(score) AS best_score FROM hits GROUP BY topic_id
+             )
+             SELECT t.id, t.name, t.description, t.slug, t.icon, t.color, t.parent_id, t.order_index, t.created_at, t.updated_at, t.archived
+             FROM topics t
+             LEFT JOIN topic_hits h ON h.topic_id = t.id
+             WHERE (t.deleted = 0 OR t.deleted IS NULL)
+               AND (t.name LIKE ?2 OR t.description LIKE ?2 OR h.best_score IS NOT NULL)
+             ORDER BY (t.name LIKE ?2 OR t.description LIKE ?2) DESC, h.best_score IS NULL, h.best_score ASC, t.order_index ASC"
+        )?;
 
         let topic_iter = stmt
-            .query_map(params![keyword_param, keyword_param], |row| {
-                let subtopics_json: Option<String> = row.get(6)?;
-                let subtopics = if let Some(json) = subtopics_json {
-                    serde_json::from_str(&json).unwrap_or(Some(Vec::new()))
-                } else {
-                    Some(Vec::new())
-                };
-
-                Ok(Topic {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    description: row.get(2).unwrap_or_default(),
-                    slug: row.get(3)?,
-                    icon: row.get(4).unwrap_or_default(),
-                    color: row.get(5).unwrap_or_default(),
-                    subtopics,
-                    order: row.get(7)?,
-                    created_at: row.get(8)?,
-                    updated_at: row.get(9)?,
-                })
-            })
-            .map_err(|e| e.to_string())?;
+            .query_map(params![match_query, keyword_param], Self::map_topic)?;
 
         let mut topics = Vec::new();
         for topic in topic_iter {
-            topics.push(topic.map_err(|e| e.to_string())?);
+            topics.push(topic?);
         }
 
         Ok(topics)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{CreateQuizSessionDto, QuestionFilter, QuizSessionType};
+    use crate::database::repository::test_fixtures::{database, question, topic};
+    use crate::database::repository::{FilterRepository, QuizSessionRepository};
+
+    #[test]
+    fn test_archived_parent_hides_descendants() {
+        let db = database();
+        let parent = topic(&db, "Rust", None);
+        let child = topic(&db, "Traits", Some(&parent));
+        let grandchild = topic(&db, "Objects", Some(&child));
+        let other = topic(&db, "Go", None);
+        let q1 = question(&db, &grandchild, 1, "What is a trait object?");
+        let q2 = question(&db, &other, 1, "What is a goroutine?");
+
+        let repo = LazyTopicsRepository::new(Arc::clone(&db));
+        assert!(repo.set_archived(&parent, true).unwrap().unwrap().archived);
+
+        let topic_ids = |include_archived: bool| {
+            let mut ids: Vec<String> = repo
+                .get_all(include_archived)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(topic_ids(false), vec![other.clone()]);
+        let mut all = vec![parent.clone(), child, grandchild, other];
+        all.sort();
+        assert_eq!(topic_ids(true), all);
+        assert_eq!(repo.get_tree(false).unwrap().len(), 1);
+
+        let filters = FilterRepository::new(Arc::clone(&db));
+        let matched = |include_archived: bool| {
+            let filter = QuestionFilter {
+                include_archived,
+                ..Default::default()
+            };
+            let mut ids: Vec<String> = filters
+                .filter(&filter, &PageRequest::default())
+                .unwrap()
+                .items
+                .into_iter()
+                .map(|q| q.question.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        let mut both = vec![q1.clone(), q2.clone()];
+        both.sort();
+        assert_eq!(matched(false), vec![q2.clone()]);
+        assert_eq!(matched(true), both);
+
+        let quizzes = QuizSessionRepository::new(Arc::clone(&db));
+        let quiz = |topic_ids: Option<Vec<String>>, include_archived: bool| {
+            quizzes.create(CreateQuizSessionDto {
+                session_type: QuizSessionType::Sequential,
+                topic_ids,
+                difficulty: None,
+                min_difficulty: None,
+                max_difficulty: None,
+                max_questions: None,
+                include_archived,
+            })
+        };
+        assert_eq!(quiz(None, false).unwrap().question_ids, vec![q2.clone()]);
+        assert!(quiz(Some(vec![parent.clone()]), false).is_err());
+        assert_eq!(
+            quiz(Some(vec![parent.clone()]), true).unwrap().question_ids,
+            vec![q1.clone()]
+        );
+
+        // Unarchiving brings the whole subtree back
+        repo.set_archived(&parent, false).unwrap();
+        assert_eq!(topic_ids(false), all);
+        assert_eq!(matched(false), both);
+    }
+}
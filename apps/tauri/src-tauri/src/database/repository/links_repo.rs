@@ -0,0 +1,278 @@
+use rusqlite::params;
+use std::sync::Arc;
+
+use crate::database::{
+    models::{LinkType, LinkedQuestion, QuestionLinks},
+    LazyDatabase,
+};
+use crate::error::AppError;
+
+/// Directed, typed links between questions. A link whose source or target
+/// is in the trash stays stored, so restoring the question brings it back,
+/// but is left out of every query. Purging a question drops its links.
+pub struct LinksRepository {
+    db: Arc<LazyDatabase>,
+}
+
+impl LinksRepository {
+    pub fn new(db: Arc<LazyDatabase>) -> Self {
+        Self { db }
+    }
+
+    /// Link `source_id` to `target_id`. Adding a link that already exists is
+    /// a no-op; so is a related link that already exists the other way round.
+    pub fn add(&self, source_id: &str, target_id: &str, link_type: LinkType) -> Result<(), AppError> {
+        if source_id == target_id {
+            return Err(AppError::validation("A question cannot link to itself").with_field("targetId"));
+        }
+
+        let conn = self.db.get_connection()?;
+        let conn = conn.lock().unwrap();
+
+        for id in [source_id, target_id] {
+            let active: bool = conn
+                .query_row(
+                    "SELECT EXISTS(SELECT 1 FROM questions WHERE id = ? AND (deleted = 0 OR deleted IS NULL))",
+                    params![id],
+                    |row| row.get(0),
+                )?;
+            if !active {
+                return Err(AppError::not_found("question", id));
+            }
+        }
+
+        if link_type == LinkType::Related {
+            let reverse: bool = conn
+                .query_row(
+                    "SELECT EXISTS(SELECT 1 FROM question_links
+                                   WHERE source_id = ? AND target_id = ? AND link_type = 'related')",
+                    params![target_id, source_id],
+                    |row| row.get(0),
+                )?;
+            if reverse {
+                return Ok(());
+            }
+        }
+
+        conn.execute(
+            "INSERT OR IGNORE INTO question_links (source_id, target_id, link_type, created_at)
+             VALUES (?, ?, ?, ?)",
+            params![
+                source_id,
+                target_id,
+                link_type.as_str(),
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Remove a link. A related link is removed whichever way it was added.
+    pub fn remove(
+        &self,
+        source_id: &str,
+        target_id: &str,
+        link_type: LinkType,
+    ) -> Result<bool, AppError> {
+        let conn = self.db.get_connection()?;
+        let conn = conn.lock().unwrap();
+
+        let mut removed = conn
+            .execute(
+                "DELETE FROM question_links WHERE source_id = ? AND target_id = ? AND link_type = ?",
+                params![source_id, target_id, link_type.as_str()],
+            )?;
+        if link_type == LinkType::Related {
+            removed += conn
+                .execute(
+                    "DELETE FROM question_links WHERE source_id = ? AND target_id = ? AND link_type = 'related'",
+                    params![target_id, source_id],
+                )?;
+        }
+        Ok(removed > 0)
+    }
+
+    /// Links from a question and backlinks to it, skipping trashed questions.
+    /// Related links appear as outgoing from both ends.
+    pub fn get_for_question(&self, question_id: &str) -> Result<QuestionLinks, AppError> {
+        let conn = self.db.read_connection()?;
+        let _snapshot = conn.unchecked_transaction()?;
+
+        let query = |sql: &str| -> Result<Vec<LinkedQuestion>, AppError> {
+            let mut stmt = conn.prepare(sql)?;
+            let rows = stmt
+                .query_map(params![question_id], |row| {
+                    let link_type: String = row.get(0)?;
+                    Ok(LinkedQuestion {
+                        link_type: LinkType::parse(&link_type).unwrap_or(LinkType::Related),
+                        question_id: row.get(1)?,
+                        topic_id: row.get(2)?,
+                        question_number: row.get(3)?,
+                        question: row.get(4)?,
+                        created_at: row.get(5)?,
+                    })
+                })?;
+            let mut links = Vec::new();
+            for l in rows {
+                links.push(l?);
+            }
+            Ok(links)
+        };
+
+        // `this` is the end of the link belonging to `question_id`, `other`
+        // the question shown
+        let select = |this: &str, other: &str, filter: &str| {
+            format!(
+                "SELECT l.link_type, q.id, q.topic_id, q.question_number, q.question, l.created_at
+                 FROM question_links l
+                 JOIN questions q ON q.id = l.{other}
+                 JOIN questions cur ON cur.id = l.{this}
+                 WHERE l.{this} = ?1 AND {filter}
+                   AND (q.deleted = 0 OR q.deleted IS NULL)
+                   AND (cur.deleted = 0 OR cur.deleted IS NULL)"
+            )
+        };
+
+        let outgoing = query(&format!(
+            "{} UNION ALL {} ORDER BY 1, 6",
+            select("source_id", "target_id", "1"),
+            select("target_id", "source_id", "l.link_type = 'related'")
+        ))?;
+        let backlinks = query(&format!(
+            "{} ORDER BY 1, 6",
+            select("target_id", "source_id", "l.link_type != 'related'")
+        ))?;
+
+        Ok(QuestionLinks {
+            outgoing,
+            backlinks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::repository::test_fixtures::{database, question, topic};
+    use crate::database::repository::{LazyQuestionsRepository, TrashRepository};
+    use crate::error::ErrorCode;
+
+    fn linked(links: &[LinkedQuestion]) -> Vec<(LinkType, String)> {
+        links
+            .iter()
+            .map(|l| (l.link_type, l.question_id.clone()))
+            .collect()
+    }
+
+    fn link_count(db: &Arc<LazyDatabase>) -> i64 {
+        db.read_connection()
+            .unwrap()
+            .query_row("SELECT count(*) FROM question_links", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_add_shows_links_from_both_ends() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q1 = question(&db, &t, 1, "What is a trait?");
+        let q2 = question(&db, &t, 2, "What is a generic?");
+        let q3 = question(&db, &t, 3, "What is a trait object?");
+
+        let repo = LinksRepository::new(Arc::clone(&db));
+        repo.add(&q1, &q2, LinkType::Related).unwrap();
+        repo.add(&q3, &q1, LinkType::Prerequisite).unwrap();
+
+        let links = repo.get_for_question(&q1).unwrap();
+        assert_eq!(
+            linked(&links.outgoing),
+            vec![(LinkType::Related, q2.clone())]
+        );
+        assert_eq!(
+            linked(&links.backlinks),
+            vec![(LinkType::Prerequisite, q3.clone())]
+        );
+        let links = repo.get_for_question(&q2).unwrap();
+        assert_eq!(
+            linked(&links.outgoing),
+            vec![(LinkType::Related, q1.clone())]
+        );
+        assert!(links.backlinks.is_empty());
+    }
+
+    #[test]
+    fn test_add_rejects_self_links_and_ignores_duplicates() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q1 = question(&db, &t, 1, "What is a trait?");
+        let q2 = question(&db, &t, 2, "What is a generic?");
+
+        let repo = LinksRepository::new(Arc::clone(&db));
+        let err = repo.add(&q1, &q1, LinkType::Related).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Validation);
+        assert_eq!(err.field.as_deref(), Some("targetId"));
+        let err = repo.add(&q1, "missing", LinkType::Related).unwrap_err();
+        assert_eq!(err.code, ErrorCode::NotFound);
+
+        repo.add(&q1, &q2, LinkType::Related).unwrap();
+        repo.add(&q1, &q2, LinkType::Related).unwrap();
+        repo.add(&q2, &q1, LinkType::Related).unwrap();
+        assert_eq!(link_count(&db), 1);
+
+        // A different type is a separate link
+        repo.add(&q1, &q2, LinkType::FollowUp).unwrap();
+        assert_eq!(link_count(&db), 2);
+        assert!(repo.remove(&q2, &q1, LinkType::Related).unwrap());
+        assert!(!repo.remove(&q2, &q1, LinkType::Related).unwrap());
+        assert_eq!(link_count(&db), 1);
+    }
+
+    #[test]
+    fn test_trashed_end_hides_link_until_restored() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q1 = question(&db, &t, 1, "What is a trait?");
+        let q2 = question(&db, &t, 2, "What is a generic?");
+
+        let repo = LinksRepository::new(Arc::clone(&db));
+        repo.add(&q1, &q2, LinkType::FollowUp).unwrap();
+        LazyQuestionsRepository::new(Arc::clone(&db))
+            .delete(&q2)
+            .unwrap();
+
+        assert!(repo.get_for_question(&q1).unwrap().outgoing.is_empty());
+        assert!(repo.get_for_question(&q2).unwrap().backlinks.is_empty());
+        let err = repo.add(&q1, &q2, LinkType::Related).unwrap_err();
+        assert_eq!(err.code, ErrorCode::NotFound);
+
+        TrashRepository::new(Arc::clone(&db))
+            .restore_question(&q2)
+            .unwrap();
+        assert_eq!(
+            linked(&repo.get_for_question(&q1).unwrap().outgoing),
+            vec![(LinkType::FollowUp, q2.clone())]
+        );
+    }
+
+    #[test]
+    fn test_purging_either_end_drops_link() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q1 = question(&db, &t, 1, "What is a trait?");
+        let q2 = question(&db, &t, 2, "What is a generic?");
+        let q3 = question(&db, &t, 3, "What is a trait object?");
+
+        let repo = LinksRepository::new(Arc::clone(&db));
+        repo.add(&q1, &q2, LinkType::Prerequisite).unwrap();
+        repo.add(&q3, &q1, LinkType::Related).unwrap();
+
+        let questions = LazyQuestionsRepository::new(Arc::clone(&db));
+        let trash = TrashRepository::new(Arc::clone(&db));
+        questions.delete(&q1).unwrap();
+        assert!(trash.purge_question(&q1).unwrap());
+
+        assert_eq!(link_count(&db), 0);
+        assert!(repo.get_for_question(&q2).unwrap().backlinks.is_empty());
+        assert!(repo.get_for_question(&q3).unwrap().outgoing.is_empty());
+    }
+}
@@ -1,9 +1,36 @@
+pub mod annotations_repo;
+pub mod attachments_repo;
+pub mod bulk_repo;
+pub mod difficulty_repo;
+pub mod duplicates_repo;
+pub mod filter_repo;
+pub mod integrity_repo;
 pub mod lazy_topics_repo;
 pub mod lazy_questions_repo;
+pub mod links_repo;
+pub mod pagination;
 pub mod progress_repo;
 pub mod quiz_session_repo;
+pub mod revisions_repo;
+pub mod saved_searches_repo;
+pub mod tags_repo;
+#[cfg(test)]
+pub(crate) mod test_fixtures;
+pub mod trash_repo;
 
+pub use annotations_repo::AnnotationsRepository;
+pub use attachments_repo::AttachmentsRepository;
+pub use bulk_repo::BulkRepository;
+pub use difficulty_repo::DifficultyRepository;
+pub use duplicates_repo::DuplicatesRepository;
+pub use filter_repo::FilterRepository;
+pub use integrity_repo::IntegrityRepository;
 pub use lazy_questions_repo::LazyQuestionsRepository;
 pub use lazy_topics_repo::LazyTopicsRepository;
+pub use links_repo::LinksRepository;
 pub use progress_repo::ProgressRepository;
 pub use quiz_session_repo::QuizSessionRepository;
+pub use revisions_repo::RevisionsRepository;
+pub use saved_searches_repo::SavedSearchesRepository;
+pub use tags_repo::TagsRepository;
+pub use trash_repo::TrashRepository;
@@ -0,0 +1,185 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64, Engine};
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::database::models::{Page, PageRequest, SortKey};
+use crate::error::AppError;
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 500;
+
+/// What a cursor remembers: the sort it was made for and the last row
+/// returned. Opaque to the frontend.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Cursor {
+    sort: SortKey,
+    desc: bool,
+    value: serde_json::Value,
+    id: String,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        BASE64.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    fn decode(cursor: &str) -> Result<Self, AppError> {
+        BASE64
+            .decode(cursor)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or_else(|| AppError::validation("Invalid cursor").with_field("cursor"))
+    }
+
+    fn sort_value(&self) -> Value {
+        match &self.value {
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => Value::Integer(i),
+                None => Value::Real(n.as_f64().unwrap_or(0.0)),
+            },
+            serde_json::Value::String(s) => Value::Text(s.clone()),
+            _ => Value::Null,
+        }
+    }
+}
+
+fn json_value(value: Value) -> serde_json::Value {
+    match value {
+        Value::Integer(i) => i.into(),
+        Value::Real(f) => f.into(),
+        Value::Text(s) => s.into(),
+        _ => serde_json::Value::Null,
+    }
+}
+
+/// A listing to page through
+pub(crate) struct Listing<'a> {
+    /// Column list mapped by `fetch_page`'s `map`
+    pub columns: &'a str,
+    /// `FROM ... WHERE ...`; must include a WHERE clause
+    pub from: &'a str,
+    pub params: Vec<Value>,
+    /// Unique column that breaks ties
+    pub id: &'a str,
+}
+
+/// Fetch one page of `listing` sorted by `sort_expr`, which the caller
+/// resolves from `sort` and which must never be NULL. Pages are keyed on
+/// the last row's (sort value, id), so rows added or removed between calls
+/// don't shift the pages after them.
+pub(crate) fn fetch_page<T>(
+    conn: &Connection,
+    listing: Listing,
+    sort: SortKey,
+    sort_expr: &str,
+    request: &PageRequest,
+    default_desc: bool,
+    map: impl Fn(&rusqlite::Row) -> rusqlite::Result<T>,
+) -> Result<Page<T>, AppError> {
+    let desc = request.descending.unwrap_or(default_desc);
+    let limit = request.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    // Count and page from the same snapshot
+    let _snapshot = conn.unchecked_transaction()?;
+
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT count(*) {}", listing.from),
+            rusqlite::params_from_iter(&listing.params),
+            |row| row.get(0),
+        )?;
+
+    let (op, dir) = if desc { ("<", "DESC") } else { (">", "ASC") };
+    let mut sql = format!(
+        "SELECT {}, {} AS page_sort, {} AS page_id {}",
+        listing.columns, sort_expr, listing.id, listing.from
+    );
+    let mut params = listing.params;
+    if let Some(cursor) = &request.cursor {
+        let cursor = Cursor::decode(cursor)?;
+        if cursor.sort != sort || cursor.desc != desc {
+            return Err(
+                AppError::validation("Cursor was made for a different sort order")
+                    .with_field("cursor"),
+            );
+        }
+        sql.push_str(&format!(
+            " AND ({}, {}) {} (?, ?)",
+            sort_expr, listing.id, op
+        ));
+        params.push(cursor.sort_value());
+        params.push(Value::Text(cursor.id));
+    }
+    sql.push_str(&format!(
+        " ORDER BY page_sort {0}, page_id {0} LIMIT ?",
+        dir
+    ));
+    // One extra row tells whether there is another page
+    params.push(Value::Integer(limit as i64 + 1));
+
+    let mut stmt = conn.prepare(&sql)?;
+    let key_column = stmt.column_count() - 2;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(&params), |row| {
+            Ok((
+                map(row)?,
+                row.get::<_, Value>(key_column)?,
+                row.get::<_, String>(key_column + 1)?,
+            ))
+        })?;
+
+    let mut items = Vec::new();
+    let mut last = None;
+    let mut more = false;
+    for r in rows {
+        let (item, value, id) = r?;
+        if items.len() == limit as usize {
+            more = true;
+            break;
+        }
+        items.push(item);
+        last = Some((value, id));
+    }
+
+    let next_cursor = match last {
+        Some((value, id)) if more => Some(
+            Cursor {
+                sort,
+                desc,
+                value: json_value(value),
+                id,
+            }
+            .encode(),
+        ),
+        _ => None,
+    };
+    Ok(Page {
+        items,
+        next_cursor,
+        total: total as usize,
+    })
+}
+
+/// Error for a sort key a listing doesn't have
+pub(crate) fn unsupported_sort(listing: &str, sort: SortKey) -> AppError {
+    AppError::validation(format!("{} cannot be sorted by {:?}", listing, sort)).with_field("sort")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor {
+            sort: SortKey::QuestionNumber,
+            desc: true,
+            value: 42.into(),
+            id: "q1".to_string(),
+        };
+        let decoded = Cursor::decode(&cursor.encode()).unwrap();
+        assert_eq!(decoded, cursor);
+        assert_eq!(decoded.sort_value(), Value::Integer(42));
+        assert!(Cursor::decode("not a cursor").is_err());
+    }
+}
@@ -3,9 +3,21 @@ use rusqlite::{params, Connection};
 use std::sync::{Arc, Mutex};
 
 use crate::database::{
-    models::{ProgressStatistics, ProgressStatus, QuestionProgress, UpdateProgressDto},
+    models::{
+        Page, PageRequest, ProgressStatistics, ProgressStatus, QuestionProgress, SortKey,
+        UpdateProgressDto,
+    },
+    repository::{
+        lazy_topics_repo::{ARCHIVED_SQL, SUBTREE_SQL},
+        pagination::{fetch_page, unsupported_sort, Listing},
+    },
     LazyDatabase,
 };
+use crate::error::{AppError, ErrorCode};
+
+/// Columns in the order `query_progress` maps them. The table also has an
+/// `id` column second, so `SELECT *` doesn't line up.
+pub(crate) const PROGRESS_COLUMNS: &str = "question_id, topic_id, status, confidence_level, times_reviewed, times_correct, times_incorrect, last_reviewed_at, next_review_at, created_at, updated_at";
 
 pub struct ProgressRepository {
     db: Arc<LazyDatabase>,
@@ -16,69 +28,109 @@ impl ProgressRepository {
         Self { db }
     }
 
+    /// Map `PROGRESS_COLUMNS`
+    fn map_progress(row: &rusqlite::Row) -> rusqlite::Result<QuestionProgress> {
+        Self::map_progress_at(row, 0)
+    }
+
+    /// Map `PROGRESS_COLUMNS` selected starting at column `start`
+    pub(crate) fn map_progress_at(
+        row: &rusqlite::Row,
+        start: usize,
+    ) -> rusqlite::Result<QuestionProgress> {
+        let status_str: String = row.get(start + 2)?;
+        // Parse status enum manually or serde?
+        // Assuming stored as string "NotStudied" etc.
+        let status = match status_str.as_str() {
+            "NotStudied" => ProgressStatus::NotStudied,
+            "Studying" => ProgressStatus::Studying,
+            "Mastered" => ProgressStatus::Mastered,
+            "NeedsReview" => ProgressStatus::NeedsReview,
+            _ => ProgressStatus::NotStudied,
+        };
+
+        Ok(QuestionProgress {
+            question_id: row.get(start)?,
+            topic_id: row.get(start + 1)?,
+            status,
+            confidence_level: row.get(start + 3)?,
+            times_reviewed: row.get(start + 4)?,
+            times_correct: row.get(start + 5)?,
+            times_incorrect: row.get(start + 6)?,
+            last_reviewed_at: row.get(start + 7)?,
+            next_review_at: row.get(start + 8)?,
+            created_at: row.get(start + 9)?,
+            updated_at: row.get(start + 10)?,
+        })
+    }
+
     fn query_progress(
         &self,
         sql: &str,
         params: impl rusqlite::Params,
-    ) -> Result<Vec<QuestionProgress>, String> {
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
+    ) -> Result<Vec<QuestionProgress>, AppError> {
+        let conn = self.db.read_connection()?;
 
-        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(sql)?;
         let rows = stmt
-            .query_map(params, |row| {
-                let status_str: String = row.get(2)?;
-                // Parse status enum manually or serde?
-                // Assuming stored as string "NotStudied" etc.
-                let status = match status_str.as_str() {
-                    "NotStudied" => ProgressStatus::NotStudied,
-                    "Studying" => ProgressStatus::Studying,
-                    "Mastered" => ProgressStatus::Mastered,
-                    "NeedsReview" => ProgressStatus::NeedsReview,
-                    _ => ProgressStatus::NotStudied,
-                };
-
-                Ok(QuestionProgress {
-                    question_id: row.get(0)?,
-                    topic_id: row.get(1)?,
-                    status,
-                    confidence_level: row.get(3)?,
-                    times_reviewed: row.get(4)?,
-                    times_correct: row.get(5)?,
-                    times_incorrect: row.get(6)?,
-                    last_reviewed_at: row.get(7)?,
-                    next_review_at: row.get(8)?,
-                    created_at: row.get(9)?,
-                    updated_at: row.get(10)?,
-                })
-            })
-            .map_err(|e| e.to_string())?;
+            .query_map(params, Self::map_progress)?;
 
         let mut progress = Vec::new();
         for p in rows {
-            progress.push(p.map_err(|e| e.to_string())?);
+            progress.push(p?);
         }
         Ok(progress)
     }
 
-    pub fn get_all(&self) -> Result<Vec<QuestionProgress>, String> {
-        self.query_progress("SELECT * FROM progress WHERE deleted = 0 OR deleted IS NULL", params![])
+    pub fn get_all(&self) -> Result<Vec<QuestionProgress>, AppError> {
+        self.query_progress(
+            &format!("SELECT {} FROM progress WHERE deleted = 0 OR deleted IS NULL", PROGRESS_COLUMNS),
+            params![],
+        )
+    }
+
+    /// A page of active progress, most recently updated first unless asked
+    /// otherwise
+    pub fn get_page(&self, request: &PageRequest) -> Result<Page<QuestionProgress>, AppError> {
+        let sort = request.sort.unwrap_or(SortKey::Updated);
+        let sort_expr = match sort {
+            SortKey::Created => "created_at",
+            SortKey::Updated => "updated_at",
+            SortKey::LastReviewed => "COALESCE(last_reviewed_at, '')",
+            other => return Err(unsupported_sort("Progress", other)),
+        };
+
+        let conn = self.db.read_connection()?;
+        fetch_page(
+            &conn,
+            Listing {
+                columns: PROGRESS_COLUMNS,
+                from: "FROM progress WHERE (deleted = 0 OR deleted IS NULL)",
+                params: Vec::new(),
+                id: "question_id",
+            },
+            sort,
+            sort_expr,
+            request,
+            request.sort.is_none(),
+            Self::map_progress,
+        )
     }
 
     pub fn get_by_question_id(
         &self,
         question_id: &str,
-    ) -> Result<Option<QuestionProgress>, String> {
+    ) -> Result<Option<QuestionProgress>, AppError> {
         let res = self.query_progress(
-            "SELECT * FROM progress WHERE question_id = ?",
+            &format!("SELECT {} FROM progress WHERE question_id = ?", PROGRESS_COLUMNS),
             params![question_id],
         )?;
         Ok(res.into_iter().next())
     }
 
-    pub fn get_by_topic(&self, topic_id: &str) -> Result<Vec<QuestionProgress>, String> {
+    pub fn get_by_topic(&self, topic_id: &str) -> Result<Vec<QuestionProgress>, AppError> {
         self.query_progress(
-            "SELECT * FROM progress WHERE topic_id = ?",
+            &format!("SELECT {} FROM progress WHERE topic_id = ?", PROGRESS_COLUMNS),
             params![topic_id],
         )
     }
@@ -87,7 +139,7 @@ impl ProgressRepository {
         &self,
         question_id: &str,
         dto: UpdateProgressDto,
-    ) -> Result<QuestionProgress, String> {
+    ) -> Result<QuestionProgress, AppError> {
         // Fetch existing or create default
         let existing = self.get_by_question_id(question_id)?;
 
@@ -95,15 +147,14 @@ impl ProgressRepository {
             p
         } else {
             // Find topic_id for this question
-            let conn = self.db.get_connection();
-            let conn = conn.lock().unwrap();
+            let conn = self.db.read_connection()?;
             let topic_id: String = conn
                 .query_row(
                     "SELECT topic_id FROM questions WHERE id = ?",
                     params![question_id],
                     |r| r.get(0),
                 )
-                .map_err(|_| format!("Question {} not found", question_id))?;
+                .map_err(|_| AppError::not_found("question", question_id))?;
             drop(conn); // Drop lock
 
             QuestionProgress::new(question_id.to_string(), topic_id)
@@ -135,7 +186,7 @@ impl ProgressRepository {
         ));
 
         // Save
-        let conn = self.db.get_connection();
+        let conn = self.db.get_connection()?;
         let conn = conn.lock().unwrap();
 
         // Convert status enum to string
@@ -159,16 +210,20 @@ impl ProgressRepository {
                 current.created_at,
                 current.updated_at
             ]
-        ).map_err(|e| e.to_string())?;
+        )?;
 
         Ok(current)
     }
 
-    pub fn reset(&self, question_id: &str) -> Result<bool, String> {
+    pub fn reset(&self, question_id: &str) -> Result<bool, AppError> {
         // Just delete or update? original impl updated.
         let existing = self.get_by_question_id(question_id)?;
         if existing.is_none() {
-            return Err(format!("Progress not found for {}", question_id));
+            return Err(AppError::new(
+                ErrorCode::NotFound,
+                format!("Progress not found for {}", question_id),
+            )
+            .with_entity("progress", question_id));
         }
         let mut current = existing.unwrap();
 
@@ -182,7 +237,7 @@ impl ProgressRepository {
         current.next_review_at = None;
         current.updated_at = now;
 
-        let conn = self.db.get_connection();
+        let conn = self.db.get_connection()?;
         let conn = conn.lock().unwrap();
 
         let status_str = format!("{:?}", current.status);
@@ -190,15 +245,41 @@ impl ProgressRepository {
         conn.execute(
             "UPDATE progress SET status=?, confidence_level=?, times_reviewed=?, times_correct=?, times_incorrect=?, last_reviewed_at=?, next_review_at=?, updated_at=?, synced_at=NULL, sync_version=COALESCE(sync_version, 0)+1 WHERE question_id=?",
             params![status_str, 0, 0, 0, 0, Option::<String>::None, Option::<String>::None, current.updated_at, question_id]
-        ).map_err(|e| e.to_string())?;
+        )?;
 
         Ok(true)
     }
 
-    pub fn get_statistics(&self) -> Result<ProgressStatistics, String> {
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
+    /// Active progress, limited to the subtree of `topic_id` if given, and
+    /// leaving out archived topics unless `include_archived`
+    fn active_progress(
+        &self,
+        topic_id: Option<&str>,
+        include_archived: bool,
+    ) -> Result<Vec<QuestionProgress>, AppError> {
+        let mut sql = format!(
+            "SELECT {} FROM progress WHERE (deleted = 0 OR deleted IS NULL)",
+            PROGRESS_COLUMNS
+        );
+        if topic_id.is_some() {
+            sql.push_str(&format!(" AND topic_id IN ({})", SUBTREE_SQL));
+        }
+        if !include_archived {
+            sql.push_str(&format!(" AND topic_id NOT IN ({})", ARCHIVED_SQL));
+        }
+        match topic_id {
+            Some(topic_id) => self.query_progress(&sql, params![topic_id]),
+            None => self.query_progress(&sql, []),
+        }
+    }
 
+    /// Statistics over all progress, or over one topic and every topic
+    /// nested under it
+    pub fn get_statistics(
+        &self,
+        topic_id: Option<&str>,
+        include_archived: bool,
+    ) -> Result<ProgressStatistics, AppError> {
         // We can do this with SQL count queries or fetch all.
         // Fetching all is simpler to match original logic precisely (avg calculation etc)
         // But for performance, SQL is better.
@@ -207,9 +288,8 @@ impl ProgressRepository {
 
         // Let's reuse get_all logic but optimize if needed.
         // Original logic fetched specific structs.
-        drop(conn);
 
-        let all = self.get_all()?;
+        let all = self.active_progress(topic_id, include_archived)?;
         let total_questions = all.len();
 
         let not_studied = all
@@ -280,8 +360,11 @@ impl ProgressRepository {
     }
 
     // Duplicate of get_questions_due_for_review from original?
-    pub fn get_questions_due_for_review(&self) -> Result<Vec<QuestionProgress>, String> {
-        let all = self.get_all()?;
+    pub fn get_questions_due_for_review(
+        &self,
+        include_archived: bool,
+    ) -> Result<Vec<QuestionProgress>, AppError> {
+        let all = self.active_progress(None, include_archived)?;
         let now = Utc::now();
         Ok(all
             .into_iter()
@@ -311,19 +394,18 @@ impl ProgressRepository {
         next.to_rfc3339()
     }
 
-    pub fn ensure_progress_for_all_questions(&self) -> Result<usize, String> {
-        let conn = self.db.get_connection();
+    pub fn ensure_progress_for_all_questions(&self) -> Result<usize, AppError> {
+        let conn = self.db.get_connection()?;
         let conn = conn.lock().unwrap();
 
         // Find questions that don't have progress
         let sql = "SELECT q.id, q.topic_id FROM questions q LEFT JOIN progress p ON q.id = p.question_id WHERE p.question_id IS NULL";
-        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(sql)?;
 
         let rows = stmt
             .query_map([], |row| {
                 Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-            })
-            .map_err(|e| e.to_string())?;
+            })?;
 
         let missing: Vec<(String, String)> = rows.map(|r| r.unwrap()).collect();
         let count = missing.len();
@@ -336,7 +418,7 @@ impl ProgressRepository {
                 conn.execute(
                      "INSERT INTO progress (question_id, topic_id, status, confidence_level, times_reviewed, times_correct, times_incorrect, created_at, updated_at, id, sync_version, synced_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 1, NULL)",
                      params![qid, tid, "NotStudied", 0, 0, 0, 0, now, now, progress_id]
-                 ).map_err(|e| e.to_string())?;
+                 )?;
             }
         }
 
@@ -4,9 +4,18 @@ use rusqlite::{params, Connection};
 use std::sync::{Arc, Mutex};
 
 use crate::database::{
-    models::{CreateQuizSessionDto, QuizResult, QuizSession, QuizSessionType},
+    models::{
+        CreateQuizSessionDto, Page, PageRequest, QuestionFilter, QuizResult, QuizSession,
+        QuizSessionType, SortKey,
+    },
+    repository::{
+        difficulty_repo, filter_repo,
+        lazy_topics_repo::{subtree_ids, ARCHIVED_SQL},
+        pagination::{fetch_page, unsupported_sort, Listing},
+    },
     LazyDatabase,
 };
+use crate::error::AppError;
 
 pub struct QuizSessionRepository {
     db: Arc<LazyDatabase>,
@@ -17,56 +26,57 @@ impl QuizSessionRepository {
         Self { db }
     }
 
+    /// Map the `quiz_sessions` columns of `SELECT *`
+    fn map_session(row: &rusqlite::Row) -> rusqlite::Result<QuizSession> {
+        let topic_ids_json: Option<String> = row.get(2)?;
+        let question_ids_json: Option<String> = row.get(3)?;
+        let results_json: Option<String> = row.get(7)?;
+
+        let topic_ids = topic_ids_json
+            .and_then(|j| serde_json::from_str(&j).ok())
+            .unwrap_or_default();
+        let question_ids = question_ids_json
+            .and_then(|j| serde_json::from_str(&j).ok())
+            .unwrap_or_default();
+        let results = results_json
+            .and_then(|j| serde_json::from_str(&j).ok())
+            .unwrap_or_default();
+
+        let type_str: String = row.get(1)?;
+        let session_type = serde_json::from_str(&format!("\"{}\"", type_str))
+            .unwrap_or(QuizSessionType::Random); // Hacky enum parsing or simple string match
+
+        Ok(QuizSession {
+            id: row.get(0)?,
+            session_type,
+            topic_ids,
+            question_ids,
+            current_index: row.get(4)?,
+            started_at: row.get(5)?,
+            completed_at: row.get(6)?,
+            results,
+        })
+    }
+
     fn query_sessions(
         &self,
         sql: &str,
         params: impl rusqlite::Params,
-    ) -> Result<Vec<QuizSession>, String> {
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
+    ) -> Result<Vec<QuizSession>, AppError> {
+        let conn = self.db.read_connection()?;
 
-        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(sql)?;
         let rows = stmt
-            .query_map(params, |row| {
-                let topic_ids_json: Option<String> = row.get(2)?;
-                let question_ids_json: Option<String> = row.get(3)?;
-                let results_json: Option<String> = row.get(7)?;
-
-                let topic_ids = topic_ids_json
-                    .and_then(|j| serde_json::from_str(&j).ok())
-                    .unwrap_or_default();
-                let question_ids = question_ids_json
-                    .and_then(|j| serde_json::from_str(&j).ok())
-                    .unwrap_or_default();
-                let results = results_json
-                    .and_then(|j| serde_json::from_str(&j).ok())
-                    .unwrap_or_default();
-
-                let type_str: String = row.get(1)?;
-                let session_type = serde_json::from_str(&format!("\"{}\"", type_str))
-                    .unwrap_or(QuizSessionType::Random); // Hacky enum parsing or simple string match
-
-                Ok(QuizSession {
-                    id: row.get(0)?,
-                    session_type,
-                    topic_ids,
-                    question_ids,
-                    current_index: row.get(4)?,
-                    started_at: row.get(5)?,
-                    completed_at: row.get(6)?,
-                    results,
-                })
-            })
-            .map_err(|e| e.to_string())?;
+            .query_map(params, Self::map_session)?;
 
         let mut sessions = Vec::new();
         for s in rows {
-            sessions.push(s.map_err(|e| e.to_string())?);
+            sessions.push(s?);
         }
         Ok(sessions)
     }
 
-    pub fn create(&self, dto: CreateQuizSessionDto) -> Result<QuizSession, String> {
+    pub fn create(&self, dto: CreateQuizSessionDto) -> Result<QuizSession, AppError> {
         // reuse selection logic which uses other repos...
         // Wait, other repos queries need to be accessible.
         // `select_questions` calls `self.db.read_topics()` which we removed.
@@ -74,14 +84,50 @@ impl QuizSessionRepository {
 
         let question_ids = self.select_questions(&dto)?;
         if question_ids.is_empty() {
-            return Err("No questions available".to_string());
+            return Err(AppError::validation("No questions available"));
         }
 
         let topic_ids = dto.topic_ids.unwrap_or_default();
 
         let session = QuizSession::new(dto.session_type, topic_ids, question_ids);
+        self.insert(&session)?;
+        Ok(session)
+    }
+
+    /// Start a session over the questions matching `filter`, such as a saved
+    /// search. Questions are ordered by `session_type` as in `create`.
+    pub fn create_from_filter(
+        &self,
+        filter: &QuestionFilter,
+        session_type: QuizSessionType,
+        max_questions: Option<i32>,
+    ) -> Result<QuizSession, AppError> {
+        let candidates = {
+            let conn = self.db.read_connection()?;
+            let (from, params) = filter_repo::compile(&conn, filter)?;
+            let mut stmt = conn
+                .prepare(&format!("SELECT q.id, q.order_index, p.status {}", from))?;
+            let rows = stmt
+                .query_map(rusqlite::params_from_iter(&params), Candidate::from_row)?;
+            let mut candidates = Vec::new();
+            for r in rows {
+                candidates.push(r?);
+            }
+            candidates
+        };
+
+        let question_ids = arrange(candidates, &session_type, max_questions)?;
+        if question_ids.is_empty() {
+            return Err(AppError::validation("No questions available"));
+        }
 
-        let conn = self.db.get_connection();
+        let session = QuizSession::new(session_type, filter.topic_ids.clone(), question_ids);
+        self.insert(&session)?;
+        Ok(session)
+    }
+
+    fn insert(&self, session: &QuizSession) -> Result<(), AppError> {
+        let conn = self.db.get_connection()?;
         let conn = conn.lock().unwrap();
 
         let topic_ids_json = serde_json::to_string(&session.topic_ids).unwrap();
@@ -92,18 +138,18 @@ impl QuizSessionRepository {
         conn.execute(
             "INSERT INTO quiz_sessions (id, session_type, topic_ids, question_ids, current_index, started_at, completed_at, results, sync_version, synced_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, 1, NULL)",
             params![session.id, type_str, topic_ids_json, question_ids_json, session.current_index, session.started_at, session.completed_at, results_json]
-        ).map_err(|e| e.to_string())?;
+        )?;
 
-        Ok(session)
+        Ok(())
     }
 
-    pub fn get_by_id(&self, id: &str) -> Result<Option<QuizSession>, String> {
+    pub fn get_by_id(&self, id: &str) -> Result<Option<QuizSession>, AppError> {
         let sessions =
             self.query_sessions("SELECT * FROM quiz_sessions WHERE id = ?", params![id])?;
         Ok(sessions.into_iter().next())
     }
 
-    pub fn get_active(&self) -> Result<Option<QuizSession>, String> {
+    pub fn get_active(&self) -> Result<Option<QuizSession>, AppError> {
         let sessions = self.query_sessions("SELECT * FROM quiz_sessions WHERE completed_at IS NULL AND (deleted = 0 OR deleted IS NULL) ORDER BY started_at DESC LIMIT 1", params![])?;
         Ok(sessions.into_iter().next())
     }
@@ -112,21 +158,21 @@ impl QuizSessionRepository {
         &self,
         session_id: &str,
         result: QuizResult,
-    ) -> Result<QuizSession, String> {
-        let mut session = self.get_by_id(session_id)?.ok_or("Session not found")?;
+    ) -> Result<QuizSession, AppError> {
+        let mut session = self.get_by_id(session_id)?.ok_or_else(|| AppError::not_found("quiz_session", session_id))?;
 
         if session
             .results
             .iter()
             .any(|r| r.question_id == result.question_id)
         {
-            return Err("Question already answered".to_string());
+            return Err(AppError::conflict("Question already answered"));
         }
 
         session.results.push(result);
         session.current_index = session.results.len() as i32;
 
-        let conn = self.db.get_connection();
+        let conn = self.db.get_connection()?;
         let conn = conn.lock().unwrap();
 
         let results_json = serde_json::to_string(&session.results).unwrap();
@@ -134,55 +180,75 @@ impl QuizSessionRepository {
         conn.execute(
             "UPDATE quiz_sessions SET results = ?, current_index = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1 WHERE id = ?",
             params![results_json, session.current_index, session_id],
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
         Ok(session)
     }
 
-    pub fn complete(&self, session_id: &str) -> Result<QuizSession, String> {
-        let mut session = self.get_by_id(session_id)?.ok_or("Session not found")?;
+    pub fn complete(&self, session_id: &str) -> Result<QuizSession, AppError> {
+        let mut session = self.get_by_id(session_id)?.ok_or_else(|| AppError::not_found("quiz_session", session_id))?;
         if session.completed_at.is_some() {
-            return Err("Already completed".to_string());
+            return Err(AppError::conflict("Already completed"));
         }
 
         let now = Utc::now().to_rfc3339();
         session.completed_at = Some(now.clone());
 
-        let conn = self.db.get_connection();
+        let conn = self.db.get_connection()?;
         let conn = conn.lock().unwrap();
         conn.execute(
             "UPDATE quiz_sessions SET completed_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1 WHERE id = ?",
             params![now, session_id],
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
 
         Ok(session)
     }
 
-    pub fn get_history(&self, limit: Option<i32>) -> Result<Vec<QuizSession>, String> {
-        let limit = limit.unwrap_or(10);
+    pub fn get_history(&self, limit: Option<i32>) -> Result<Vec<QuizSession>, AppError> {
         self.query_sessions(
-            &format!(
-                "SELECT * FROM quiz_sessions ORDER BY started_at DESC LIMIT {}",
-                limit
-            ),
-            params![],
+            "SELECT * FROM quiz_sessions WHERE (deleted = 0 OR deleted IS NULL)
+             ORDER BY started_at DESC LIMIT ?",
+            params![limit.unwrap_or(10)],
         )
     }
 
-    pub fn get_all_sessions(&self) -> Result<Vec<QuizSession>, String> {
+    /// A page of quiz history, newest first unless asked otherwise
+    pub fn get_history_page(&self, request: &PageRequest) -> Result<Page<QuizSession>, AppError> {
+        let sort = request.sort.unwrap_or(SortKey::Created);
+        let sort_expr = match sort {
+            SortKey::Created => "started_at",
+            SortKey::Updated => "COALESCE(completed_at, started_at)",
+            other => return Err(unsupported_sort("Quiz history", other)),
+        };
+
+        let conn = self.db.read_connection()?;
+        fetch_page(
+            &conn,
+            Listing {
+                columns: "*",
+                from: "FROM quiz_sessions WHERE (deleted = 0 OR deleted IS NULL)",
+                params: Vec::new(),
+                id: "id",
+            },
+            sort,
+            sort_expr,
+            request,
+            request.sort.is_none(),
+            Self::map_session,
+        )
+    }
+
+    pub fn get_all_sessions(&self) -> Result<Vec<QuizSession>, AppError> {
         self.query_sessions("SELECT * FROM quiz_sessions", params![])
     }
 
     // Helper functions for question selection needing direct DB queries
     // replacing `collect_questions` which used `db.read_topics()`
-    fn select_questions(&self, dto: &CreateQuizSessionDto) -> Result<Vec<String>, String> {
+    fn select_questions(&self, dto: &CreateQuizSessionDto) -> Result<Vec<String>, AppError> {
         // This logic is complex because it involves filtering and randomizing.
         // Let's implement a simpler version that pulls candidates from DB.
 
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
+        let conn = self.db.read_connection()?;
 
         // Build Query
         let mut sql = "SELECT q.id, q.order_index, p.status, p.id FROM questions q LEFT JOIN progress p ON q.id = p.question_id".to_string(); // p.id just to check existence
@@ -193,77 +259,122 @@ impl QuizSessionRepository {
 
         if let Some(ids) = &dto.topic_ids {
             if !ids.is_empty() {
-                let placeholders: Vec<String> = ids.iter().map(|_| "?".to_string()).collect();
-                where_clauses.push(format!("q.topic_id IN ({})", placeholders.join(",")));
+                // A topic includes everything nested under it
+                let mut subtree: Vec<String> = Vec::new();
                 for id in ids {
-                    params_vec.push(Box::new(id.clone()));
+                    for tid in subtree_ids(&conn, id)? {
+                        if !subtree.contains(&tid) {
+                            subtree.push(tid);
+                        }
+                    }
+                }
+                let placeholders: Vec<String> = subtree.iter().map(|_| "?".to_string()).collect();
+                where_clauses.push(format!("q.topic_id IN ({})", placeholders.join(",")));
+                for id in subtree {
+                    params_vec.push(Box::new(id));
                 }
             }
         }
 
+        if !dto.include_archived {
+            where_clauses.push(format!("q.topic_id NOT IN ({})", ARCHIVED_SQL));
+        }
+
         if let Some(diff) = &dto.difficulty {
+            difficulty_repo::validate(&conn, diff)?;
             where_clauses.push("q.difficulty = ?".to_string());
             params_vec.push(Box::new(diff.clone()));
         }
 
+        // Ranges compare level ranks, so custom levels fall where they are
+        // ordered rather than being skipped
+        for (bound, op) in [(&dto.min_difficulty, ">="), (&dto.max_difficulty, "<=")] {
+            if let Some(diff) = bound {
+                let rank = difficulty_repo::rank_of(&conn, diff)?
+                    .ok_or_else(|| {
+                        AppError::validation(format!("Unknown difficulty \"{}\"", diff))
+                            .with_field(if op == ">=" { "minDifficulty" } else { "maxDifficulty" })
+                    })?;
+                where_clauses.push(format!(
+                    "(SELECT rank FROM difficulty_levels WHERE key = q.difficulty) {} ?",
+                    op
+                ));
+                params_vec.push(Box::new(rank));
+            }
+        }
+
         if !where_clauses.is_empty() {
             sql.push_str(" WHERE ");
             sql.push_str(&where_clauses.join(" AND "));
         }
 
-        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(&sql)?;
         let params_refs: Vec<&dyn rusqlite::ToSql> =
             params_vec.iter().map(|b| b.as_ref()).collect();
 
-        struct Candidate {
-            id: String,
-            order: i32,
-            status: String,
-        }
-
         let rows = stmt
-            .query_map(rusqlite::params_from_iter(params_refs), |row| {
-                let status: Option<String> = row.get(2)?;
-                Ok(Candidate {
-                    id: row.get(0)?,
-                    order: row.get(1)?,
-                    status: status.unwrap_or("NotStudied".to_string()),
-                })
-            })
-            .map_err(|e| e.to_string())?;
+            .query_map(rusqlite::params_from_iter(params_refs), Candidate::from_row)?;
 
         let mut candidates = Vec::new();
         for r in rows {
-            candidates.push(r.map_err(|e| e.to_string())?);
+            candidates.push(r?);
         }
 
         // drop(conn); // let it drop at end of scope
 
-        // now apply strategy
-        match dto.session_type {
-            QuizSessionType::Random
-            | QuizSessionType::TopicFocused
-            | QuizSessionType::DifficultyFocused => {
-                use rand::seq::SliceRandom;
-                let mut rng = rand::thread_rng();
-                candidates.shuffle(&mut rng);
-            }
-            QuizSessionType::Sequential => {
-                candidates.sort_by_key(|c| c.order);
-            }
-            QuizSessionType::QuickRefresher => {
-                // Filter mastered
-                candidates.retain(|c| c.status == "Mastered");
-                if candidates.is_empty() {
-                    return Err("No mastered questions".to_string());
-                }
-                use rand::seq::SliceRandom;
-                let mut rng = rand::thread_rng();
-                candidates.shuffle(&mut rng);
+        arrange(candidates, &dto.session_type, dto.max_questions)
+    }
+}
+
+/// A question that may go into a quiz
+struct Candidate {
+    id: String,
+    order: i32,
+    status: String,
+}
+
+impl Candidate {
+    /// Map `q.id, q.order_index, p.status`
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        let status: Option<String> = row.get(2)?;
+        Ok(Candidate {
+            id: row.get(0)?,
+            order: row.get(1)?,
+            status: status.unwrap_or("NotStudied".to_string()),
+        })
+    }
+}
+
+/// Order candidates for `session_type` and keep the first `max_questions`
+fn arrange(
+    mut candidates: Vec<Candidate>,
+    session_type: &QuizSessionType,
+    max_questions: Option<i32>,
+) -> Result<Vec<String>, AppError> {
+    // now apply strategy
+    match session_type {
+        QuizSessionType::Random
+        | QuizSessionType::TopicFocused
+        | QuizSessionType::DifficultyFocused => {
+            use rand::seq::SliceRandom;
+            let mut rng = rand::thread_rng();
+            candidates.shuffle(&mut rng);
+        }
+        QuizSessionType::Sequential => {
+            candidates.sort_by_key(|c| c.order);
+        }
+        QuizSessionType::QuickRefresher => {
+            // Filter mastered
+            candidates.retain(|c| c.status == "Mastered");
+            if candidates.is_empty() {
+                return Err(AppError::validation("No mastered questions"));
             }
+            use rand::seq::SliceRandom;
+            let mut rng = rand::thread_rng();
+            candidates.shuffle(&mut rng);
         }
-
-        let max = dto.max_questions.unwrap_or(candidates.len() as i32) as usize;
-        Ok(candidates.into_iter().take(max).map(|c| c.id).collect())
     }
+
+    let max = max_questions.unwrap_or(candidates.len() as i32) as usize;
+    Ok(candidates.into_iter().take(max).map(|c| c.id).collect())
 }
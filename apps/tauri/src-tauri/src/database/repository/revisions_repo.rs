@@ -0,0 +1,161 @@
+use rusqlite::{params, OptionalExtension, Transaction};
+use std::sync::Arc;
+
+use crate::database::{
+    models::{Answer, Question, QuestionRevision, RevisionDiff, UpdateQuestionDto},
+    repository::LazyQuestionsRepository,
+    LazyDatabase,
+};
+use crate::error::AppError;
+use crate::utils::diff::{diff_lines, diff_tags};
+
+/// Read side of the question revision history. Revisions are written by
+/// `LazyQuestionsRepository::update` through `record_revision`.
+pub struct RevisionsRepository {
+    db: Arc<LazyDatabase>,
+}
+
+impl RevisionsRepository {
+    pub fn new(db: Arc<LazyDatabase>) -> Self {
+        Self { db }
+    }
+
+    fn map_revision(row: &rusqlite::Row) -> rusqlite::Result<QuestionRevision> {
+        let answer_json: String = row.get(3)?;
+        let tags_json: Option<String> = row.get(4)?;
+
+        Ok(QuestionRevision {
+            id: row.get(0)?,
+            question_id: row.get(1)?,
+            question: row.get(2)?,
+            answer: serde_json::from_str(&answer_json).unwrap_or(Answer {
+                markdown: "".to_string(),
+            }),
+            tags: tags_json
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+            difficulty: row.get(5)?,
+            updated_at: row.get(6)?,
+            replaced_at: row.get(7)?,
+        })
+    }
+
+    /// Revisions of a question, newest first
+    pub fn list(&self, question_id: &str) -> Result<Vec<QuestionRevision>, AppError> {
+        let conn = self.db.read_connection()?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, question_id, question, answer, tags, difficulty, updated_at, replaced_at
+                 FROM question_revisions WHERE question_id = ? ORDER BY id DESC",
+            )?;
+        let rows = stmt
+            .query_map(params![question_id], Self::map_revision)?;
+
+        let mut revisions = Vec::new();
+        for r in rows {
+            revisions.push(r?);
+        }
+        Ok(revisions)
+    }
+
+    pub fn get(&self, question_id: &str, id: i64) -> Result<Option<QuestionRevision>, AppError> {
+        let conn = self.db.read_connection()?;
+        conn.query_row(
+            "SELECT id, question_id, question, answer, tags, difficulty, updated_at, replaced_at
+             FROM question_revisions WHERE question_id = ? AND id = ?",
+            params![question_id, id],
+            Self::map_revision,
+        )
+        .optional()
+        .map_err(AppError::from)
+    }
+
+    /// Diff revision `from` against revision `to`, or against the current
+    /// question when `to` is `None`
+    pub fn diff(
+        &self,
+        question_id: &str,
+        from: i64,
+        to: Option<i64>,
+    ) -> Result<RevisionDiff, AppError> {
+        let old = self
+            .get(question_id, from)?
+            .ok_or_else(|| AppError::not_found("revision", from))?;
+        let new = match to {
+            Some(to) => self
+                .get(question_id, to)?
+                .ok_or_else(|| AppError::not_found("revision", to))?,
+            None => {
+                let current = LazyQuestionsRepository::new(Arc::clone(&self.db))
+                    .get_by_id(question_id)?
+                    .ok_or_else(|| AppError::not_found("question", question_id))?;
+                QuestionRevision {
+                    id: 0,
+                    question_id: current.id,
+                    question: current.question,
+                    answer: current.answer,
+                    tags: current.tags,
+                    difficulty: current.difficulty,
+                    updated_at: current.updated_at.clone(),
+                    replaced_at: current.updated_at,
+                }
+            }
+        };
+
+        let (tags_added, tags_removed) = diff_tags(&old.tags, &new.tags);
+        Ok(RevisionDiff {
+            from,
+            to,
+            question: diff_lines(&old.question, &new.question),
+            answer: diff_lines(&old.answer.markdown, &new.answer.markdown),
+            tags_added,
+            tags_removed,
+            difficulty: (old.difficulty != new.difficulty)
+                .then_some((old.difficulty, new.difficulty)),
+        })
+    }
+
+    /// Restore a question's content from one of its revisions. The revert is
+    /// an ordinary update, so the content it replaces becomes a new revision.
+    pub fn revert(&self, question_id: &str, id: i64) -> Result<Option<Question>, AppError> {
+        let revision = self
+            .get(question_id, id)?
+            .ok_or_else(|| AppError::not_found("revision", id))?;
+
+        LazyQuestionsRepository::new(Arc::clone(&self.db)).update(
+            question_id,
+            UpdateQuestionDto {
+                topic_id: None,
+                question_number: None,
+                question: Some(revision.question),
+                answer: Some(revision.answer),
+                tags: Some(revision.tags),
+                difficulty: Some(revision.difficulty),
+                order: None,
+            },
+        )
+    }
+}
+
+/// Save `current` as a revision before it is overwritten
+pub(crate) fn record_revision(
+    tx: &Transaction,
+    current: &Question,
+    replaced_at: &str,
+) -> Result<(), AppError> {
+    tx.execute(
+        "INSERT INTO question_revisions (question_id, question, answer, tags, difficulty, updated_at, replaced_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![
+            current.id,
+            current.question,
+            serde_json::to_string(&current.answer).unwrap_or("{}".to_string()),
+            serde_json::to_string(&current.tags).unwrap_or("[]".to_string()),
+            current.difficulty,
+            current.updated_at,
+            replaced_at,
+        ],
+    )?;
+    Ok(())
+}
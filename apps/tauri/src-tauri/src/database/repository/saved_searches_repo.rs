@@ -0,0 +1,282 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::Arc;
+
+use crate::database::{
+    models::{
+        generate_id, CreateSavedSearchDto, Page, PageRequest, QuestionFilter, QuestionWithProgress,
+        SavedSearch, UpdateSavedSearchDto,
+    },
+    repository::{filter_repo, FilterRepository},
+    LazyDatabase,
+};
+use crate::error::AppError;
+
+/// Saved searches ("smart collections"). Only the filter is stored; members
+/// and counts are evaluated on every read. Deletes are soft until pushed,
+/// like annotations.
+pub struct SavedSearchesRepository {
+    db: Arc<LazyDatabase>,
+}
+
+impl SavedSearchesRepository {
+    pub fn new(db: Arc<LazyDatabase>) -> Self {
+        Self { db }
+    }
+
+    /// Map `id, name, filter, created_at, updated_at`, leaving the count
+    fn map_saved_search(row: &rusqlite::Row) -> rusqlite::Result<SavedSearch> {
+        let filter_json: String = row.get(2)?;
+        Ok(SavedSearch {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            filter: serde_json::from_str(&filter_json).unwrap_or_default(),
+            question_count: None,
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+        })
+    }
+
+    /// All saved searches by name, with their current counts
+    pub fn get_all(&self) -> Result<Vec<SavedSearch>, AppError> {
+        let conn = self.db.read_connection()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, name, filter, created_at, updated_at FROM saved_searches
+                 WHERE deleted = 0 OR deleted IS NULL
+                 ORDER BY name COLLATE NOCASE, id",
+            )?;
+        let rows = stmt
+            .query_map([], Self::map_saved_search)?;
+
+        let mut searches = Vec::new();
+        for s in rows {
+            let mut search = s?;
+            search.question_count = filter_repo::count(&conn, &search.filter).ok();
+            searches.push(search);
+        }
+        Ok(searches)
+    }
+
+    pub fn get_by_id(&self, id: &str) -> Result<Option<SavedSearch>, AppError> {
+        let conn = self.db.read_connection()?;
+        let search = conn
+            .query_row(
+                "SELECT id, name, filter, created_at, updated_at FROM saved_searches
+                 WHERE id = ? AND (deleted = 0 OR deleted IS NULL)",
+                params![id],
+                Self::map_saved_search,
+            )
+            .optional()?;
+        Ok(search.map(|mut search| {
+            search.question_count = filter_repo::count(&conn, &search.filter).ok();
+            search
+        }))
+    }
+
+    pub fn create(&self, dto: CreateSavedSearchDto) -> Result<SavedSearch, AppError> {
+        let name = dto.name.trim();
+        let id = generate_id();
+        let now = chrono::Utc::now().to_rfc3339();
+        {
+            let conn = self.db.get_connection()?;
+            let conn = conn.lock().unwrap();
+            check_name_free(&conn, name, None)?;
+            filter_repo::compile(&conn, &dto.filter)?;
+
+            conn.execute(
+                "INSERT INTO saved_searches (id, name, filter, created_at, updated_at, sync_version, synced_at, deleted)
+                 VALUES (?, ?, ?, ?, ?, 1, NULL, 0)",
+                params![id, name, filter_json(&dto.filter), now, now],
+            )?;
+        }
+
+        self.get_by_id(&id)?
+            .ok_or_else(|| AppError::internal("Failed to create saved search"))
+    }
+
+    pub fn update(
+        &self,
+        id: &str,
+        dto: UpdateSavedSearchDto,
+    ) -> Result<Option<SavedSearch>, AppError> {
+        let current = match self.get_by_id(id)? {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        let name = dto.name.as_deref().map(str::trim).unwrap_or(&current.name);
+        let filter = dto.filter.as_ref().unwrap_or(&current.filter);
+
+        {
+            let conn = self.db.get_connection()?;
+            let conn = conn.lock().unwrap();
+            check_name_free(&conn, name, Some(id))?;
+            filter_repo::compile(&conn, filter)?;
+
+            conn.execute(
+                "UPDATE saved_searches SET name = ?, filter = ?, updated_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE id = ? AND (deleted = 0 OR deleted IS NULL)",
+                params![name, filter_json(filter), chrono::Utc::now().to_rfc3339(), id],
+            )?;
+        }
+
+        self.get_by_id(id)
+    }
+
+    /// Soft-delete until the deletion is pushed, then sync removes the row
+    pub fn delete(&self, id: &str) -> Result<bool, AppError> {
+        let conn = self.db.get_connection()?;
+        let conn = conn.lock().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let count = conn
+            .execute(
+                "UPDATE saved_searches SET deleted = 1, deleted_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE id = ? AND (deleted = 0 OR deleted IS NULL)",
+                params![now, id],
+            )?;
+        Ok(count > 0)
+    }
+
+    /// The questions currently matching a saved search, one page at a time
+    pub fn members(
+        &self,
+        id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<QuestionWithProgress>, AppError> {
+        let search = self
+            .get_by_id(id)?
+            .ok_or_else(|| AppError::not_found("saved_search", id))?;
+        FilterRepository::new(Arc::clone(&self.db)).filter(&search.filter, request)
+    }
+}
+
+fn filter_json(filter: &QuestionFilter) -> String {
+    serde_json::to_string(filter).unwrap_or("{}".to_string())
+}
+
+/// Names are unique among active saved searches, ignoring case
+fn check_name_free(conn: &Connection, name: &str, except: Option<&str>) -> Result<(), AppError> {
+    if name.is_empty() {
+        return Err(AppError::validation("Saved search name cannot be empty").with_field("name"));
+    }
+    let taken: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM saved_searches
+             WHERE name = ? COLLATE NOCASE AND id IS NOT ? AND (deleted = 0 OR deleted IS NULL))",
+            params![name, except],
+            |row| row.get(0),
+        )?;
+    if taken {
+        return Err(AppError::conflict(format!(
+            "A saved search named \"{}\" already exists",
+            name
+        ))
+        .with_field("name"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{ProgressStatus, QuizSessionType, UpdateProgressDto};
+    use crate::database::repository::test_fixtures::{database, question, topic};
+    use crate::database::repository::{ProgressRepository, QuizSessionRepository};
+    use crate::error::ErrorCode;
+
+    fn mastered() -> QuestionFilter {
+        QuestionFilter {
+            statuses: vec![ProgressStatus::Mastered],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_members_and_count_follow_progress() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        question(&db, &t, 1, "What is a trait?");
+        let q2 = question(&db, &t, 2, "What is a crate?");
+
+        let repo = SavedSearchesRepository::new(Arc::clone(&db));
+        let search = repo
+            .create(CreateSavedSearchDto {
+                name: "Mastered".to_string(),
+                filter: mastered(),
+            })
+            .unwrap();
+        assert_eq!(search.question_count, Some(0));
+
+        ProgressRepository::new(Arc::clone(&db))
+            .update(
+                &q2,
+                UpdateProgressDto {
+                    status: Some(ProgressStatus::Mastered),
+                    confidence_level: None,
+                    was_correct: None,
+                },
+            )
+            .unwrap();
+
+        let search = repo.get_by_id(&search.id).unwrap().unwrap();
+        assert_eq!(search.question_count, Some(1));
+        let members = repo.members(&search.id, &PageRequest::default()).unwrap();
+        assert_eq!(members.total, 1);
+        assert_eq!(members.items[0].question.id, q2);
+
+        let quiz = QuizSessionRepository::new(Arc::clone(&db))
+            .create_from_filter(&search.filter, QuizSessionType::Sequential, None)
+            .unwrap();
+        assert_eq!(quiz.question_ids, vec![q2]);
+    }
+
+    #[test]
+    fn test_save_rejects_invalid_filter_and_taken_name() {
+        let db = database();
+        let repo = SavedSearchesRepository::new(Arc::clone(&db));
+        let invalid = QuestionFilter {
+            min_confidence: Some(4),
+            max_confidence: Some(1),
+            ..Default::default()
+        };
+
+        let err = repo
+            .create(CreateSavedSearchDto {
+                name: "Shaky".to_string(),
+                filter: invalid.clone(),
+            })
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Validation);
+        assert_eq!(err.field.as_deref(), Some("minConfidence"));
+        assert!(repo.get_all().unwrap().is_empty());
+
+        let search = repo
+            .create(CreateSavedSearchDto {
+                name: "Shaky".to_string(),
+                filter: mastered(),
+            })
+            .unwrap();
+        let err = repo
+            .update(
+                &search.id,
+                UpdateSavedSearchDto {
+                    name: None,
+                    filter: Some(invalid),
+                },
+            )
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Validation);
+        assert_eq!(
+            repo.get_by_id(&search.id).unwrap().unwrap().filter,
+            mastered()
+        );
+
+        let err = repo
+            .create(CreateSavedSearchDto {
+                name: "shaky".to_string(),
+                filter: mastered(),
+            })
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+        assert_eq!(err.field.as_deref(), Some("name"));
+    }
+}
@@ -0,0 +1,236 @@
+use rusqlite::{params, OptionalExtension, Transaction};
+use std::sync::Arc;
+
+use crate::database::{models::Tag, LazyDatabase};
+use crate::error::{AppError, ErrorCode};
+
+/// Tags live in `questions.tags` (JSON, synced) and are mirrored into the
+/// `tags` / `question_tags` tables by triggers. Reads go through the tables;
+/// writes rewrite the JSON of affected questions so the change syncs.
+pub struct TagsRepository {
+    db: Arc<LazyDatabase>,
+}
+
+impl TagsRepository {
+    pub fn new(db: Arc<LazyDatabase>) -> Self {
+        Self { db }
+    }
+
+    fn query_tags(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<Tag>, AppError> {
+        let conn = self.db.read_connection()?;
+
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt
+            .query_map(params, |row| {
+                let topic_ids: Option<String> = row.get(3)?;
+                Ok(Tag {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    question_count: row.get::<_, i64>(2)? as usize,
+                    topic_ids: topic_ids
+                        .map(|ids| ids.split(',').map(|s| s.to_string()).collect())
+                        .unwrap_or_default(),
+                })
+            })?;
+
+        let mut tags = Vec::new();
+        for t in rows {
+            tags.push(t?);
+        }
+        Ok(tags)
+    }
+
+    /// All tags in use, most used first
+    pub fn get_all(&self) -> Result<Vec<Tag>, AppError> {
+        self.query_tags(
+            "SELECT t.id, t.name, count(q.id), group_concat(DISTINCT q.topic_id)
+             FROM tags t
+             JOIN question_tags qt ON qt.tag_id = t.id
+             JOIN questions q ON q.id = qt.question_id AND (q.deleted = 0 OR q.deleted IS NULL)
+             GROUP BY t.id
+             ORDER BY count(q.id) DESC, t.name ASC",
+            params![],
+        )
+    }
+
+    /// Tags starting with `prefix` (case-insensitive), most used first
+    pub fn autocomplete(&self, prefix: &str, limit: Option<i32>) -> Result<Vec<Tag>, AppError> {
+        let pattern = format!(
+            "{}%",
+            prefix
+                .trim()
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        self.query_tags(
+            "SELECT t.id, t.name, count(q.id), group_concat(DISTINCT q.topic_id)
+             FROM tags t
+             JOIN question_tags qt ON qt.tag_id = t.id
+             JOIN questions q ON q.id = qt.question_id AND (q.deleted = 0 OR q.deleted IS NULL)
+             WHERE t.name LIKE ?1 ESCAPE '\\'
+             GROUP BY t.id
+             ORDER BY count(q.id) DESC, t.name ASC
+             LIMIT ?2",
+            params![pattern, limit.unwrap_or(10)],
+        )
+    }
+
+    /// Rename a tag on every question. Renaming onto an existing tag merges them.
+    /// Returns the number of questions updated.
+    pub fn rename(&self, old_name: &str, new_name: &str) -> Result<usize, AppError> {
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+            return Err(AppError::validation("Tag name cannot be empty").with_field("newName"));
+        }
+        self.rewrite(old_name, Some(new_name), false)
+    }
+
+    /// Fold `source` into `target`, which must already exist.
+    /// Returns the number of questions updated.
+    pub fn merge(&self, source: &str, target: &str) -> Result<usize, AppError> {
+        if source.trim().eq_ignore_ascii_case(target.trim()) {
+            return Err(AppError::validation("Cannot merge a tag into itself").with_field("target"));
+        }
+        self.rewrite(source, Some(target.trim()), true)
+    }
+
+    /// Remove a tag from every question. Returns the number of questions updated.
+    pub fn delete(&self, name: &str) -> Result<usize, AppError> {
+        self.rewrite(name, None, false)
+    }
+
+    fn rewrite(
+        &self,
+        name: &str,
+        replacement: Option<&str>,
+        replacement_must_exist: bool,
+    ) -> Result<usize, AppError> {
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let tag_id = find_tag_id(&tx, name)?.ok_or_else(|| tag_not_found(name.trim()))?;
+        if let Some(target) = replacement {
+            if replacement_must_exist && find_tag_id(&tx, target)?.is_none() {
+                return Err(tag_not_found(target));
+            }
+        }
+
+        let affected: Vec<(String, Option<String>)> = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT q.id, q.tags FROM questions q
+                     JOIN question_tags qt ON qt.question_id = q.id
+                     WHERE qt.tag_id = ? AND (q.deleted = 0 OR q.deleted IS NULL)",
+                )?;
+            let rows = stmt
+                .query_map(params![tag_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            let mut affected = Vec::new();
+            for r in rows {
+                affected.push(r?);
+            }
+            affected
+        };
+
+        let now = chrono::Utc::now().to_rfc3339();
+        for (question_id, tags_json) in &affected {
+            let tags: Vec<String> = tags_json
+                .as_deref()
+                .and_then(|json| serde_json::from_str(json).ok())
+                .unwrap_or_default();
+            let new_tags = replace_tag(&tags, name.trim(), replacement);
+            let new_json = serde_json::to_string(&new_tags).unwrap_or("[]".to_string());
+
+            // Triggers relink question_tags from the new JSON
+            tx.execute(
+                "UPDATE questions SET tags = ?, updated_at = ?, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1 WHERE id = ?",
+                params![new_json, now, question_id],
+            )?;
+        }
+
+        match replacement {
+            Some(new_name) => {
+                // Case-only renames hit the existing row; store the new spelling
+                tx.execute(
+                    "UPDATE tags SET name = ?1 WHERE name = ?1",
+                    params![new_name],
+                )?;
+                // Keep the old row while soft-deleted questions still reference it
+                tx.execute(
+                    "DELETE FROM tags WHERE id = ?1 AND NOT EXISTS (SELECT 1 FROM question_tags WHERE tag_id = ?1)",
+                    params![tag_id],
+                )?;
+            }
+            None => {
+                tx.execute("DELETE FROM tags WHERE id = ?", params![tag_id])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(affected.len())
+    }
+}
+
+fn find_tag_id(tx: &Transaction, name: &str) -> Result<Option<i64>, AppError> {
+    tx.query_row(
+        "SELECT id FROM tags WHERE name = ?",
+        params![name.trim()],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(AppError::from)
+}
+
+/// Replace (or with `None`, remove) `old` in a tag list, matching
+/// case-insensitively like the `tags` table, and drop resulting duplicates.
+fn replace_tag(tags: &[String], old: &str, new: Option<&str>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = if tag.trim().eq_ignore_ascii_case(old) {
+            match new {
+                Some(n) => n.to_string(),
+                None => continue,
+            }
+        } else {
+            tag.clone()
+        };
+        if !result.iter().any(|t| t.trim().eq_ignore_ascii_case(tag.trim())) {
+            result.push(tag);
+        }
+    }
+    result
+}
+
+fn tag_not_found(name: &str) -> AppError {
+    AppError::new(ErrorCode::NotFound, format!("Tag '{}' not found", name)).with_entity("tag", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_replace_tag_renames_and_dedupes() {
+        assert_eq!(
+            replace_tag(&tags(&["rust", "memory"]), "Rust", Some("rustlang")),
+            tags(&["rustlang", "memory"])
+        );
+        assert_eq!(
+            replace_tag(&tags(&["java", "jvm", "JVM-old"]), "jvm-old", Some("JVM")),
+            tags(&["java", "jvm"])
+        );
+    }
+
+    #[test]
+    fn test_replace_tag_removes() {
+        assert_eq!(
+            replace_tag(&tags(&["legacy", "spring"]), "LEGACY", None),
+            tags(&["spring"])
+        );
+    }
+}
@@ -0,0 +1,65 @@
+//! Helpers for repository tests that run against an in-memory database
+
+use std::sync::Arc;
+
+use crate::database::{
+    models::{Answer, CreateQuestionDto, CreateTopicDto, Difficulty},
+    repository::{LazyQuestionsRepository, LazyTopicsRepository},
+    LazyDatabase,
+};
+
+pub fn database() -> Arc<LazyDatabase> {
+    Arc::new(LazyDatabase::open_in_memory().unwrap())
+}
+
+/// Create a topic, returning its id
+pub fn topic(db: &Arc<LazyDatabase>, name: &str, parent_id: Option<&str>) -> String {
+    LazyTopicsRepository::new(Arc::clone(db))
+        .create(CreateTopicDto {
+            name: name.to_string(),
+            description: String::new(),
+            slug: name.to_lowercase(),
+            icon: String::new(),
+            color: String::new(),
+            parent_id: parent_id.map(str::to_string),
+            order: 0,
+        })
+        .unwrap()
+        .id
+}
+
+/// Create a question, returning its id
+pub fn question(db: &Arc<LazyDatabase>, topic_id: &str, number: i32, text: &str) -> String {
+    LazyQuestionsRepository::new(Arc::clone(db))
+        .create(CreateQuestionDto {
+            topic_id: topic_id.to_string(),
+            question_number: number,
+            question: text.to_string(),
+            answer: Answer {
+                markdown: String::new(),
+            },
+            tags: Vec::new(),
+            difficulty: Difficulty::Beginner,
+            order: number,
+        })
+        .unwrap()
+        .id
+}
+
+/// `deleted` flag of a row, `None` if the row is gone
+pub fn deleted(db: &Arc<LazyDatabase>, table: &str, pk_col: &str, id: &str) -> Option<bool> {
+    use rusqlite::OptionalExtension;
+
+    db.read_connection()
+        .unwrap()
+        .query_row(
+            &format!(
+                "SELECT COALESCE(deleted, 0) FROM {} WHERE {} = ?",
+                table, pk_col
+            ),
+            [id],
+            |row| row.get(0),
+        )
+        .optional()
+        .unwrap()
+}
@@ -0,0 +1,412 @@
+use rusqlite::{params, OptionalExtension, Transaction};
+use std::sync::Arc;
+
+use crate::database::{
+    models::{TrashedQuestion, TrashedTopic},
+    repository::{lazy_topics_repo::SUBTREE_SQL, LazyQuestionsRepository, LazyTopicsRepository},
+    LazyDatabase,
+};
+use crate::error::AppError;
+
+/// Soft-deleted topics and questions stay in their tables with `deleted = 1`
+/// until purged. Every delete stamps the rows it trashes with a fresh
+/// `deleted_batch`: a topic shares it with its subtopics, questions and
+/// progress, which is how a restore tells them apart from questions that
+/// were deleted on their own, even within the same second.
+pub struct TrashRepository {
+    db: Arc<LazyDatabase>,
+}
+
+impl TrashRepository {
+    pub fn new(db: Arc<LazyDatabase>) -> Self {
+        Self { db }
+    }
+
+    /// Trashed topics, most recently deleted first
+    pub fn list_topics(&self) -> Result<Vec<TrashedTopic>, AppError> {
+        let conn = self.db.read_connection()?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT t.id, t.name, t.description, t.slug, t.icon, t.color, t.parent_id, t.order_index, t.created_at, t.updated_at,
+                        t.archived, t.deleted_at,
+                        (SELECT count(*) FROM questions q
+                         WHERE q.topic_id = t.id AND q.deleted = 1 AND q.deleted_batch = t.deleted_batch)
+                 FROM topics t
+                 WHERE t.deleted = 1
+                 ORDER BY t.deleted_at DESC, t.name ASC",
+            )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(TrashedTopic {
+                    topic: LazyTopicsRepository::map_topic(row)?,
+                    deleted_at: row.get::<_, Option<i64>>(11)?.unwrap_or(0),
+                    question_count: row.get::<_, i64>(12)? as usize,
+                })
+            })?;
+
+        let mut topics = Vec::new();
+        for t in rows {
+            topics.push(t?);
+        }
+        Ok(topics)
+    }
+
+    /// Trashed questions, most recently deleted first
+    pub fn list_questions(&self) -> Result<Vec<TrashedQuestion>, AppError> {
+        let conn = self.db.read_connection()?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT q.id, q.topic_id, q.question_number, q.question, q.answer, q.tags, q.difficulty, q.order_index, q.created_at, q.updated_at,
+                        q.deleted_at,
+                        COALESCE(t.deleted, 1) = 1 AS topic_deleted
+                 FROM questions q
+                 LEFT JOIN topics t ON t.id = q.topic_id
+                 WHERE q.deleted = 1
+                 ORDER BY q.deleted_at DESC, q.question_number ASC",
+            )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(TrashedQuestion {
+                    question: LazyQuestionsRepository::map_question(row)?,
+                    deleted_at: row.get::<_, Option<i64>>("deleted_at")?.unwrap_or(0),
+                    topic_deleted: row.get("topic_deleted")?,
+                })
+            })?;
+
+        let mut questions = Vec::new();
+        for q in rows {
+            questions.push(q?);
+        }
+        Ok(questions)
+    }
+
+    /// Restore a topic along with the subtopics, questions and progress
+    /// deleted with it. Fails while its parent topic is in the trash.
+    /// Returns the number of questions restored.
+    pub fn restore_topic(&self, id: &str) -> Result<usize, AppError> {
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let batch = trash_batch(&tx, "topics", "id", id)?
+            .ok_or_else(|| not_in_trash("topic", id))?;
+        let parent_trashed: bool = tx
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM topics c JOIN topics p ON p.id = c.parent_id
+                               WHERE c.id = ? AND p.deleted = 1)",
+                params![id],
+                |row| row.get(0),
+            )?;
+        if parent_trashed {
+            return Err(AppError::conflict("Restore the parent topic first"));
+        }
+        let now = chrono::Utc::now().to_rfc3339();
+
+        // ?1 is the subtree root
+        let restored = tx
+            .execute(
+                &format!(
+                    "UPDATE questions SET deleted = 0, deleted_at = NULL, deleted_batch = NULL, updated_at = ?2, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                     WHERE topic_id IN ({}) AND deleted = 1 AND deleted_batch = ?3",
+                    SUBTREE_SQL
+                ),
+                params![id, now, batch],
+            )?;
+        tx.execute(
+            &format!(
+                "UPDATE progress SET deleted = 0, deleted_at = NULL, deleted_batch = NULL, updated_at = ?2, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE topic_id IN ({}) AND deleted = 1 AND deleted_batch = ?3",
+                SUBTREE_SQL
+            ),
+            params![id, now, batch],
+        )?;
+        tx.execute(
+            &format!(
+                "UPDATE topics SET deleted = 0, deleted_at = NULL, deleted_batch = NULL, updated_at = ?2, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+                 WHERE id IN ({}) AND (id = ?1 OR (deleted = 1 AND deleted_batch = ?3))",
+                SUBTREE_SQL
+            ),
+            params![id, now, batch],
+        )?;
+
+        tx.commit()?;
+        Ok(restored)
+    }
+
+    /// Restore a single question and its progress. Fails while the
+    /// question's topic is itself in the trash.
+    pub fn restore_question(&self, id: &str) -> Result<bool, AppError> {
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let batch = trash_batch(&tx, "questions", "id", id)?
+            .ok_or_else(|| not_in_trash("question", id))?;
+        let topic_active: bool = tx
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM topics t JOIN questions q ON q.topic_id = t.id
+                               WHERE q.id = ? AND (t.deleted = 0 OR t.deleted IS NULL))",
+                params![id],
+                |row| row.get(0),
+            )?;
+        if !topic_active {
+            return Err(AppError::conflict("Restore the question's topic first"));
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        tx.execute(
+            "UPDATE progress SET deleted = 0, deleted_at = NULL, deleted_batch = NULL, updated_at = ?1, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+             WHERE question_id = ?2 AND deleted = 1 AND deleted_batch = ?3",
+            params![now, id, batch],
+        )?;
+        tx.execute(
+            "UPDATE questions SET deleted = 0, deleted_at = NULL, deleted_batch = NULL, updated_at = ?1, synced_at = NULL, sync_version = COALESCE(sync_version, 0) + 1
+             WHERE id = ?2",
+            params![now, id],
+        )?;
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Permanently delete a trashed topic and everything under it, including
+    /// subtopics and annotations
+    pub fn purge_topic(&self, id: &str) -> Result<bool, AppError> {
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        if trash_batch(&tx, "topics", "id", id)?.is_none() {
+            return Err(not_in_trash("topic", id));
+        }
+        let in_subtree = format!("topic_id IN ({})", SUBTREE_SQL);
+        purge_rows(&tx, "progress", "question_id", &in_subtree, &[&id])?;
+        purge_rows(
+            &tx,
+            "annotations",
+            "id",
+            &format!("question_id IN (SELECT id FROM questions WHERE {})", in_subtree),
+            &[&id],
+        )?;
+        purge_rows(&tx, "questions", "id", &in_subtree, &[&id])?;
+        purge_rows(&tx, "topics", "id", &format!("id IN ({})", SUBTREE_SQL), &[&id])?;
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Permanently delete a trashed question with its progress and annotations
+    pub fn purge_question(&self, id: &str) -> Result<bool, AppError> {
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        if trash_batch(&tx, "questions", "id", id)?.is_none() {
+            return Err(not_in_trash("question", id));
+        }
+        purge_rows(&tx, "progress", "question_id", "question_id = ?1", &[&id])?;
+        purge_rows(&tx, "annotations", "id", "question_id = ?1", &[&id])?;
+        purge_rows(&tx, "questions", "id", "id = ?1", &[&id])?;
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Permanently delete everything in the trash.
+    /// Returns the number of topics and questions removed.
+    pub fn empty_trash(&self) -> Result<usize, AppError> {
+        let conn = self.db.get_connection()?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let count: i64 = tx
+            .query_row(
+                "SELECT (SELECT count(*) FROM topics WHERE deleted = 1)
+                      + (SELECT count(*) FROM questions WHERE deleted = 1)",
+                [],
+                |row| row.get(0),
+            )?;
+
+        // Anything under a trashed topic goes with it
+        purge_rows(
+            &tx,
+            "progress",
+            "question_id",
+            "deleted = 1 OR topic_id IN (SELECT id FROM topics WHERE deleted = 1)",
+            &[],
+        )?;
+        purge_rows(
+            &tx,
+            "annotations",
+            "id",
+            "question_id IN (SELECT id FROM questions
+                             WHERE deleted = 1 OR topic_id IN (SELECT id FROM topics WHERE deleted = 1))",
+            &[],
+        )?;
+        purge_rows(
+            &tx,
+            "questions",
+            "id",
+            "deleted = 1 OR topic_id IN (SELECT id FROM topics WHERE deleted = 1)",
+            &[],
+        )?;
+        purge_rows(&tx, "topics", "id", "deleted = 1", &[])?;
+
+        tx.commit()?;
+        Ok(count as usize)
+    }
+}
+
+/// `deleted_batch` of a row that is in the trash, `None` if it isn't. Rows
+/// trashed on another device have no batch and match nothing else.
+fn trash_batch(
+    tx: &Transaction,
+    table: &str,
+    pk_col: &str,
+    id: &str,
+) -> Result<Option<String>, AppError> {
+    tx.query_row(
+        &format!(
+            "SELECT COALESCE(deleted_batch, '') FROM {} WHERE {} = ? AND deleted = 1",
+            table, pk_col
+        ),
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(AppError::from)
+}
+
+fn not_in_trash(entity: &str, id: &str) -> AppError {
+    let mut err = AppError::not_found(entity, id);
+    err.message = err.message.replace("not found", "is not in the trash");
+    err
+}
+
+/// Hard-delete the matching rows, recording a sync tombstone for each so the
+/// purge reaches the server even if the soft delete was never pushed.
+fn purge_rows(
+    tx: &Transaction,
+    table: &str,
+    pk_col: &str,
+    filter: &str,
+    params: &[&dyn rusqlite::ToSql],
+) -> Result<usize, AppError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    tx.execute(
+        &format!(
+            "INSERT OR REPLACE INTO sync_tombstones (table_name, row_id, sync_version, deleted_at)
+             SELECT '{0}', {1}, COALESCE(sync_version, 0) + 1, {2} FROM {0} WHERE {3}",
+            table, pk_col, now, filter
+        ),
+        params,
+    )?;
+    tx.execute(&format!("DELETE FROM {} WHERE {}", table, filter), params)
+        .map_err(AppError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::repository::test_fixtures::{database, deleted, question, topic};
+    use crate::database::repository::ProgressRepository;
+
+    #[test]
+    fn test_restore_topic_brings_back_subtree() {
+        let db = database();
+        let parent = topic(&db, "Rust", None);
+        let child = topic(&db, "Ownership", Some(&parent));
+        let q1 = question(&db, &parent, 1, "What is Cargo?");
+        let q2 = question(&db, &child, 1, "What is a move?");
+        ProgressRepository::new(Arc::clone(&db))
+            .ensure_progress_for_all_questions()
+            .unwrap();
+
+        LazyTopicsRepository::new(Arc::clone(&db))
+            .delete(&parent)
+            .unwrap();
+        assert_eq!(deleted(&db, "topics", "id", &child), Some(true));
+        assert_eq!(deleted(&db, "progress", "question_id", &q2), Some(true));
+
+        let trash = TrashRepository::new(Arc::clone(&db));
+        assert_eq!(trash.restore_topic(&parent).unwrap(), 2);
+        assert_eq!(deleted(&db, "topics", "id", &child), Some(false));
+        for id in [&q1, &q2] {
+            assert_eq!(deleted(&db, "questions", "id", id), Some(false));
+            assert_eq!(deleted(&db, "progress", "question_id", id), Some(false));
+        }
+        assert!(trash.list_topics().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_restore_topic_leaves_question_trashed_on_its_own() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let alone = question(&db, &t, 1, "What is Cargo?");
+        let with_topic = question(&db, &t, 2, "What is a move?");
+
+        // Within the same second, so `deleted_at` can't tell them apart
+        LazyQuestionsRepository::new(Arc::clone(&db))
+            .delete(&alone)
+            .unwrap();
+        LazyTopicsRepository::new(Arc::clone(&db))
+            .delete(&t)
+            .unwrap();
+
+        let trash = TrashRepository::new(Arc::clone(&db));
+        assert_eq!(trash.list_topics().unwrap()[0].question_count, 1);
+        assert_eq!(trash.restore_topic(&t).unwrap(), 1);
+        assert_eq!(deleted(&db, "questions", "id", &with_topic), Some(false));
+        assert_eq!(deleted(&db, "questions", "id", &alone), Some(true));
+
+        let still_trashed = trash.list_questions().unwrap();
+        assert_eq!(still_trashed.len(), 1);
+        assert_eq!(still_trashed[0].question.id, alone);
+    }
+
+    #[test]
+    fn test_purge_topic_writes_tombstones() {
+        let db = database();
+        let t = topic(&db, "Rust", None);
+        let q = question(&db, &t, 1, "What is Cargo?");
+        ProgressRepository::new(Arc::clone(&db))
+            .ensure_progress_for_all_questions()
+            .unwrap();
+        LazyTopicsRepository::new(Arc::clone(&db))
+            .delete(&t)
+            .unwrap();
+
+        let trash = TrashRepository::new(Arc::clone(&db));
+        assert!(trash.purge_topic(&t).unwrap());
+        assert_eq!(deleted(&db, "topics", "id", &t), None);
+        assert_eq!(deleted(&db, "questions", "id", &q), None);
+        assert_eq!(deleted(&db, "progress", "question_id", &q), None);
+
+        let conn = db.read_connection().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT table_name, row_id FROM sync_tombstones ORDER BY table_name")
+            .unwrap();
+        let tombstones: Vec<(String, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            tombstones,
+            vec![
+                ("progress".to_string(), q.clone()),
+                ("questions".to_string(), q.clone()),
+                ("topics".to_string(), t.clone()),
+            ]
+        );
+
+        let err = trash.purge_topic(&t).unwrap_err();
+        assert_eq!(err.code, crate::error::ErrorCode::NotFound);
+    }
+}
@@ -1,158 +1,513 @@
-use rusqlite::{Connection, Result};
+use crate::error::{AppError, ErrorCode};
+use super::migrations;
+use super::models::{BackupInfo, DatabaseStatus, Workspace};
+use super::pool::{open_connection, PooledConnection, ReadPool};
+use super::workspaces::WorkspaceRegistry;
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::{Connection, OpenFlags, Result};
 use std::fs;
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
 use tauri::AppHandle;
 use tauri::Manager;
 
-pub struct SqliteDatabase {
+/// Number of pooled read-only connections
+const READ_POOL_SIZE: usize = 4;
+
+/// Timestamp part of backup file names: `database-<time>-<reason>.sqlite`
+const BACKUP_TIME_FORMAT: &str = "%Y%m%dT%H%M%S%3fZ";
+
+fn locked() -> AppError {
+    AppError::new(
+        ErrorCode::DatabaseLocked,
+        "Database is locked. Unlock it with your passphrase first.",
+    )
+}
+
+/// Connections to an unlocked database
+struct OpenDatabase {
+    /// The only connection allowed to write
     conn: Arc<Mutex<Connection>>,
-    base_path: PathBuf,
+    readers: Arc<ReadPool>,
+    /// SQLCipher passphrase, `None` for a plain SQLite file
+    key: Option<String>,
+}
+
+/// The active workspace's database. An encrypted file starts out locked:
+/// every connection request fails until `unlock` is called with the right
+/// passphrase. Locks are taken in field order: `open`, then `workspaces`.
+pub struct SqliteDatabase {
+    open: RwLock<Option<OpenDatabase>>,
+    workspaces: RwLock<WorkspaceRegistry>,
 }
 
 impl SqliteDatabase {
-    pub fn init(app: &AppHandle) -> Result<Self, String> {
+    pub fn init(app: &AppHandle) -> Result<Self, AppError> {
         let app_data_dir = app
             .path()
             .app_data_dir()
-            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+            .map_err(|e| {
+                AppError::new(
+                    ErrorCode::Io,
+                    format!("Failed to get app data directory: {}", e),
+                )
+            })?;
 
         if !app_data_dir.exists() {
             fs::create_dir_all(&app_data_dir)
-                .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+                .map_err(|e| AppError::from(e).context("Failed to create app data directory"))?;
         }
 
-        let db_path = app_data_dir.join("database.sqlite");
+        Self::open_dir(&app_data_dir)
+    }
+
+    /// Open the active workspace under `app_data_dir`
+    fn open_dir(app_data_dir: &Path) -> Result<Self, AppError> {
+        let db = Self {
+            open: RwLock::new(None),
+            workspaces: RwLock::new(WorkspaceRegistry::load(app_data_dir)?),
+        };
+        let db_path = db.get_path();
         println!("Initializing SQLite database at: {:?}", db_path);
 
-        let conn =
-            Connection::open(db_path).map_err(|e| format!("Failed to open database: {}", e))?;
+        *db.open.write().unwrap() = Self::open_if_plain(&db_path)?;
+        Ok(db)
+    }
+
+    /// A fresh, migrated database that lives in shared memory, for
+    /// repository tests
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self, AppError> {
+        let name = uuid::Uuid::new_v4().to_string();
+        let db_path = PathBuf::from(format!("file:{}?mode=memory&cache=shared", name));
+        Ok(Self {
+            open: RwLock::new(Some(Self::open_database(&db_path, None)?)),
+            workspaces: RwLock::new(WorkspaceRegistry::load(&std::env::temp_dir().join(name))?),
+        })
+    }
+
+    /// Open the database unless it is encrypted, in which case it stays
+    /// locked until `unlock`
+    fn open_if_plain(db_path: &Path) -> Result<Option<OpenDatabase>, AppError> {
+        if is_encrypted(db_path)? {
+            println!("Database is encrypted; waiting for passphrase");
+            return Ok(None);
+        }
+        Ok(Some(Self::open_database(db_path, None)?))
+    }
+
+    fn open_database(db_path: &Path, key: Option<&str>) -> Result<OpenDatabase, AppError> {
+        let mut conn = open_connection(
+            db_path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE
+                | OpenFlags::SQLITE_OPEN_CREATE
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            key,
+        )?;
 
         // Enable foreign keys
         conn.execute("PRAGMA foreign_keys = ON", [])
-            .map_err(|e| format!("Failed to enable foreign keys: {}", e))?;
+            .map_err(|e| AppError::from(e).context("Failed to enable foreign keys"))?;
 
-        let db = Self {
+        // WAL lets the read pool run alongside the writer
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(|e| AppError::from(e).context("Failed to enable WAL mode"))?;
+        conn.pragma_update(None, "synchronous", "NORMAL")
+            .map_err(|e| AppError::from(e).context("Failed to set synchronous mode"))?;
+
+        // Bring the schema up to date, refusing databases from newer app versions
+        let version = migrations::run(&mut conn)?;
+        println!("Database schema at version {}", version);
+
+        // Open readers only after migrations so they see the final schema
+        let readers = ReadPool::open(db_path, READ_POOL_SIZE, key)?;
+
+        Ok(OpenDatabase {
             conn: Arc::new(Mutex::new(conn)),
-            base_path: app_data_dir,
-        };
+            readers,
+            key: key.map(|k| k.to_string()),
+        })
+    }
 
-        db.create_schema()
-            .map_err(|e| format!("Failed to create schema: {}", e))?;
+    /// The writer connection. Use for anything that modifies the database.
+    pub fn get_connection(&self) -> Result<Arc<Mutex<Connection>>, AppError> {
+        let open = self.open.read().unwrap();
+        Ok(open.as_ref().ok_or_else(locked)?.conn.clone())
+    }
 
-        Ok(db)
+    /// A pooled read-only connection. Use for queries so they don't wait on writes.
+    pub fn read_connection(&self) -> Result<PooledConnection, AppError> {
+        // Borrow under the lock so a concurrent re-key can't close the pool first
+        let open = self.open.read().unwrap();
+        Ok(open.as_ref().ok_or_else(locked)?.readers.get())
     }
 
-    fn create_schema(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+    pub fn get_path(&self) -> PathBuf {
+        self.base_path().join("database.sqlite")
+    }
 
-        // Topics Table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS topics (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                description TEXT,
-                slug TEXT NOT NULL,
-                icon TEXT,
-                color TEXT,
-                subtopics TEXT, -- JSON array
-                order_index INTEGER DEFAULT 0,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                sync_version INTEGER DEFAULT 1,
-                synced_at INTEGER,
-                deleted INTEGER DEFAULT 0,
-                deleted_at INTEGER
-            )",
-            [],
-        )?;
+    /// Folder of the active workspace
+    pub fn base_path(&self) -> PathBuf {
+        let workspaces = self.workspaces.read().unwrap();
+        workspaces.dir(workspaces.active_id())
+    }
 
-        // Questions Table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS questions (
-                id TEXT PRIMARY KEY,
-                topic_id TEXT NOT NULL,
-                subtopic TEXT,
-                question_number INTEGER NOT NULL,
-                question TEXT NOT NULL,
-                answer TEXT NOT NULL, -- JSON { markdown: ... }
-                tags TEXT, -- JSON array
-                difficulty TEXT,
-                order_index INTEGER DEFAULT 0,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                sync_version INTEGER DEFAULT 1,
-                synced_at INTEGER,
-                deleted INTEGER DEFAULT 0,
-                deleted_at INTEGER,
-                FOREIGN KEY (topic_id) REFERENCES topics(id) ON DELETE CASCADE
-            )",
-            [],
-        )?;
+    pub fn workspace_dir(&self, id: &str) -> PathBuf {
+        self.workspaces.read().unwrap().dir(id)
+    }
 
-        // Progress Table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS progress (
-                question_id TEXT PRIMARY KEY,
-                id TEXT,
-                topic_id TEXT NOT NULL,
-                status TEXT NOT NULL,
-                confidence_level INTEGER DEFAULT 0,
-                times_reviewed INTEGER DEFAULT 0,
-                times_correct INTEGER DEFAULT 0,
-                times_incorrect INTEGER DEFAULT 0,
-                last_reviewed_at TEXT,
-                next_review_at TEXT,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                sync_version INTEGER DEFAULT 1,
-                synced_at INTEGER,
-                deleted INTEGER DEFAULT 0,
-                deleted_at INTEGER,
-                FOREIGN KEY (question_id) REFERENCES questions(id) ON DELETE CASCADE,
-                FOREIGN KEY (topic_id) REFERENCES topics(id) ON DELETE CASCADE
-            )",
-            [],
-        )?;
+    // =========================================================================
+    // Workspaces
+    // =========================================================================
 
-        // Quiz Sessions Table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS quiz_sessions (
-                id TEXT PRIMARY KEY,
-                session_type TEXT NOT NULL,
-                topic_ids TEXT, -- JSON array
-                question_ids TEXT, -- JSON array
-                current_index INTEGER DEFAULT 0,
-                started_at TEXT NOT NULL,
-                completed_at TEXT,
-                results TEXT, -- JSON array of QuizResult
-                sync_version INTEGER DEFAULT 1,
-                synced_at INTEGER,
-                deleted INTEGER DEFAULT 0,
-                deleted_at INTEGER
-            )",
-            [],
-        )?;
+    pub fn active_workspace_id(&self) -> String {
+        self.workspaces.read().unwrap().active_id().to_string()
+    }
 
-        // Sync metadata table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS sync_metadata (
-                key TEXT PRIMARY KEY,
-                value TEXT
-            )",
-            [],
-        )?;
+    pub fn list_workspaces(&self) -> Vec<Workspace> {
+        self.workspaces.read().unwrap().list()
+    }
+
+    pub fn create_workspace(&self, name: &str) -> Result<Workspace, AppError> {
+        self.workspaces.write().unwrap().create(name)
+    }
+
+    pub fn rename_workspace(&self, id: &str, name: &str) -> Result<Workspace, AppError> {
+        self.workspaces.write().unwrap().rename(id, name)
+    }
+
+    /// Delete a workspace and every file in its folder. The active and
+    /// default workspaces can't be deleted.
+    pub fn delete_workspace(&self, id: &str) -> Result<(), AppError> {
+        let mut workspaces = self.workspaces.write().unwrap();
+        let dir = workspaces.dir(id);
+        workspaces.remove(id)?;
+        match fs::remove_dir_all(&dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(AppError::from(e).context("Failed to delete workspace folder"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Close the current database and open the workspace `id` in its place.
+    /// The new database is opened first, so a failure leaves the current one
+    /// in use. An encrypted workspace is left locked.
+    pub fn switch_workspace(&self, id: &str) -> Result<DatabaseStatus, AppError> {
+        {
+            let mut open = self.open.write().unwrap();
+            let mut workspaces = self.workspaces.write().unwrap();
+            if workspaces.active_id() != id {
+                if workspaces.find(id).is_none() {
+                    return Err(AppError::not_found("workspace", id));
+                }
+                let dir = workspaces.dir(id);
+                fs::create_dir_all(&dir)
+                    .map_err(|e| AppError::from(e).context("Failed to create workspace folder"))?;
+                let next = Self::open_if_plain(&dir.join("database.sqlite"))?;
+
+                workspaces.set_active(id)?;
+                if let Some(db) = std::mem::replace(&mut *open, next) {
+                    Self::close_database(db)?;
+                }
+                println!("Switched to workspace {}", id);
+            }
+        }
+        self.status()
+    }
+
+    // =========================================================================
+    // Encryption
+    // =========================================================================
+
+    pub fn status(&self) -> Result<DatabaseStatus, AppError> {
+        let open = self.open.read().unwrap();
+        Ok(DatabaseStatus {
+            encrypted: match open.as_ref() {
+                Some(db) => db.key.is_some(),
+                None => is_encrypted(&self.get_path())?,
+            },
+            locked: open.is_none(),
+        })
+    }
+
+    /// Open an encrypted database with its passphrase. Returns false when it
+    /// was already open.
+    pub fn unlock(&self, passphrase: &str) -> Result<bool, AppError> {
+        let mut open = self.open.write().unwrap();
+        if open.is_some() {
+            return Ok(false);
+        }
+        *open = Some(Self::open_database(&self.get_path(), Some(passphrase))?);
+        Ok(true)
+    }
+
+    /// Encrypt a plain database with `passphrase`
+    pub fn enable_encryption(&self, passphrase: &str) -> Result<(), AppError> {
+        if passphrase.is_empty() {
+            return Err(AppError::validation("Passphrase cannot be empty").with_field("passphrase"));
+        }
+        self.rewrite(None, Some(passphrase))
+    }
 
+    pub fn change_passphrase(&self, current: &str, new: &str) -> Result<(), AppError> {
+        if new.is_empty() {
+            return Err(AppError::validation("Passphrase cannot be empty").with_field("newPassphrase"));
+        }
+        self.rewrite(Some(current), Some(new))
+    }
+
+    /// Decrypt back to a plain SQLite file
+    pub fn disable_encryption(&self, passphrase: &str) -> Result<(), AppError> {
+        self.rewrite(Some(passphrase), None)
+    }
+
+    /// Copy the database into a new file under `new_key` with
+    /// `sqlcipher_export`, then swap it in place of the current file.
+    /// `current_key` must match the key the database is open with.
+    fn rewrite(&self, current_key: Option<&str>, new_key: Option<&str>) -> Result<(), AppError> {
+        let mut open = self.open.write().unwrap();
+        let db = open.as_ref().ok_or_else(locked)?;
+        match (&db.key, current_key) {
+            (None, Some(_)) => return Err(AppError::conflict("Database is not encrypted")),
+            (Some(_), None) => return Err(AppError::conflict("Database is already encrypted")),
+            (Some(key), Some(given)) if key != given => {
+                return Err(AppError::new(
+                    ErrorCode::IncorrectPassphrase,
+                    "Incorrect passphrase",
+                ))
+            }
+            _ => {}
+        }
+
+        let tmp_path = self.base_path().join("database.sqlite.rekey");
+        let _ = fs::remove_file(&tmp_path);
+
+        {
+            let conn = db.conn.lock().unwrap();
+            if let Err(e) = export_to(&conn, &tmp_path, new_key) {
+                let _ = fs::remove_file(&tmp_path);
+                return Err(AppError::from(e).context("Failed to re-encrypt database"));
+            }
+        }
+
+        self.swap_in(&mut open, &tmp_path, new_key)?;
+        // Snapshots taken before encryption would otherwise stay readable
+        if let (None, Some(key)) = (current_key, new_key) {
+            self.encrypt_backups(key)?;
+        }
         Ok(())
     }
 
+    /// Close every connection, move `new_file` over the database file and
+    /// reopen it with `key`. The caller holds the `open` write lock.
+    fn swap_in(
+        &self,
+        open: &mut Option<OpenDatabase>,
+        new_file: &Path,
+        key: Option<&str>,
+    ) -> Result<(), AppError> {
+        if let Some(db) = open.take() {
+            Self::close_database(db)?;
+        }
+
+        let base_path = self.base_path();
+        let db_path = base_path.join("database.sqlite");
+        let _ = fs::remove_file(base_path.join("database.sqlite-wal"));
+        let _ = fs::remove_file(base_path.join("database.sqlite-shm"));
+        fs::rename(new_file, &db_path)
+            .map_err(|e| AppError::from(e).context("Failed to replace database file"))?;
 
-    pub fn get_connection(&self) -> Arc<Mutex<Connection>> {
-        self.conn.clone()
+        *open = Some(Self::open_database(&db_path, key)?);
+        Ok(())
     }
 
-    pub fn get_path(&self) -> PathBuf {
-        self.base_path.join("database.sqlite")
+    /// Wait for borrowed readers to come back, then close every connection.
+    /// A writer handle cloned before the `open` lock was taken is left
+    /// pointing at an empty in-memory database, so a late write fails
+    /// instead of landing in the old file.
+    fn close_database(db: OpenDatabase) -> Result<(), AppError> {
+        db.readers.close();
+        let mut conn = db.conn.lock().unwrap();
+        let old = std::mem::replace(&mut *conn, Connection::open_in_memory()?);
+        old.close()
+            .map_err(|(_, e)| AppError::from(e).context("Failed to close database"))
+    }
+
+    // =========================================================================
+    // Backups
+    // =========================================================================
+
+    fn backup_dir(&self) -> PathBuf {
+        self.base_path().join("backups")
+    }
+
+    /// Snapshot the database into the backups folder with the online backup
+    /// API, then delete the oldest snapshots beyond `retention`.
+    /// Snapshots of an encrypted database use the same passphrase.
+    pub fn create_backup(&self, reason: &str, retention: usize) -> Result<BackupInfo, AppError> {
+        // Hold the lock throughout so a workspace switch can't move the folder
+        let open = self.open.read().unwrap();
+        let db = open.as_ref().ok_or_else(locked)?;
+        let dir = self.backup_dir();
+        fs::create_dir_all(&dir)
+            .map_err(|e| AppError::from(e).context("Failed to create backup directory"))?;
+
+        let now = chrono::Utc::now();
+        let file_name = format!(
+            "database-{}-{}.sqlite",
+            now.format(BACKUP_TIME_FORMAT),
+            reason
+        );
+        let path = dir.join(&file_name);
+
+        {
+            let src = db.readers.get();
+            let mut dst = open_connection(
+                &path,
+                OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE,
+                db.key.as_deref(),
+            )?;
+            // One step copies every page under a single read transaction
+            let result = Backup::new(&src, &mut dst).and_then(|backup| backup.step(-1));
+            if !matches!(result, Ok(StepResult::Done)) {
+                drop(dst);
+                let _ = fs::remove_file(&path);
+                return Err(match result {
+                    Err(e) => AppError::from(e).context("Failed to back up database"),
+                    Ok(_) => AppError::new(
+                        ErrorCode::DatabaseBusy,
+                        "Failed to back up database: database is busy",
+                    ),
+                });
+            }
+        }
+
+        let backups = self.list_backups()?;
+        for old in backups.iter().skip(retention.max(1)) {
+            fs::remove_file(dir.join(&old.file_name))
+                .map_err(|e| AppError::from(e).context("Failed to remove old backup"))?;
+        }
+
+        backups
+            .into_iter()
+            .find(|b| b.file_name == file_name)
+            .ok_or_else(|| AppError::internal("Backup was not written"))
+    }
+
+    /// Rewrite every plain backup under `key`. A backup that can't be
+    /// rewritten is deleted rather than left unencrypted.
+    fn encrypt_backups(&self, key: &str) -> Result<(), AppError> {
+        let dir = self.backup_dir();
+        let tmp_path = dir.join("backup.sqlite.rekey");
+        for backup in self.list_backups()? {
+            if backup.encrypted {
+                continue;
+            }
+            let path = dir.join(&backup.file_name);
+            let _ = fs::remove_file(&tmp_path);
+            let result = open_connection(
+                &path,
+                OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE,
+                None,
+            )
+            .and_then(|conn| Ok(export_to(&conn, &tmp_path, Some(key))?))
+            .and_then(|_| Ok(fs::rename(&tmp_path, &path)?));
+            if let Err(e) = result {
+                eprintln!("Failed to encrypt backup {}: {}", backup.file_name, e);
+                let _ = fs::remove_file(&tmp_path);
+                fs::remove_file(&path).map_err(|e| {
+                    AppError::from(e).context("Failed to remove unencrypted backup")
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Backups on disk, newest first
+    pub fn list_backups(&self) -> Result<Vec<BackupInfo>, AppError> {
+        let entries = match fs::read_dir(self.backup_dir()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(AppError::from(e).context("Failed to read backup directory")),
+        };
+
+        let mut backups = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let Some((created_at, reason)) = parse_backup_name(&file_name) else {
+                continue;
+            };
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            backups.push(BackupInfo {
+                file_name,
+                reason,
+                created_at,
+                size,
+                encrypted: is_encrypted(&entry.path())?,
+            });
+        }
+        backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(backups)
+    }
+
+    /// Replace the database with a backup. The backup is first rebuilt into a
+    /// temporary file under the current passphrase, then renamed over the
+    /// database in one step, so a failure leaves the current data untouched.
+    /// `passphrase` is only needed when the backup was encrypted under a
+    /// different passphrase than the current one.
+    pub fn restore_backup(&self, file_name: &str, passphrase: Option<&str>) -> Result<(), AppError> {
+        if parse_backup_name(file_name).is_none() || file_name.contains(['/', '\\']) {
+            return Err(
+                AppError::validation(format!("Invalid backup name: {}", file_name))
+                    .with_field("fileName"),
+            );
+        }
+
+        let mut open = self.open.write().unwrap();
+        let key = open.as_ref().ok_or_else(locked)?.key.clone();
+        let backup_path = self.backup_dir().join(file_name);
+        if !backup_path.exists() {
+            return Err(AppError::not_found("backup", file_name));
+        }
+        let tmp_path = self.base_path().join("database.sqlite.restore");
+        let _ = fs::remove_file(&tmp_path);
+
+        let backup_key = if is_encrypted(&backup_path)? {
+            passphrase.map(|p| p.to_string()).or(key.clone())
+        } else {
+            None
+        };
+        let export = || -> Result<(), AppError> {
+            // ATTACH inherits these flags, so they must allow creating the temp file
+            let conn = open_connection(
+                &backup_path,
+                OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE,
+                backup_key.as_deref(),
+            )?;
+            let version = migrations::current_version(&conn)?;
+            if version > migrations::latest_version() {
+                return Err(AppError::new(
+                    ErrorCode::UnsupportedVersion,
+                    format!(
+                        "Backup schema version {} is newer than this app supports ({})",
+                        version,
+                        migrations::latest_version()
+                    ),
+                ));
+            }
+            export_to(&conn, &tmp_path, key.as_deref())
+                .map_err(|e| AppError::from(e).context("Failed to restore backup"))
+        };
+        if let Err(e) = export() {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+
+        self.swap_in(&mut open, &tmp_path, key.as_deref())
     }
 
     // =========================================================================
@@ -160,8 +515,8 @@ impl SqliteDatabase {
     // =========================================================================
 
     /// Get checkpoint from sync_metadata
-    pub fn get_checkpoint(&self) -> Result<Option<(String, String)>, String> {
-        let conn = self.conn.lock().unwrap();
+    pub fn get_checkpoint(&self) -> Result<Option<(String, String)>, AppError> {
+        let conn = self.read_connection()?;
         let updated_at: Option<String> = conn
             .query_row(
                 "SELECT value FROM sync_metadata WHERE key = 'checkpoint_updated_at'",
@@ -184,34 +539,254 @@ impl SqliteDatabase {
     }
 
     /// Save checkpoint to sync_metadata
-    pub fn save_checkpoint(&self, updated_at: &str, id: &str) -> Result<(), String> {
-        let conn = self.conn.lock().unwrap();
+    pub fn save_checkpoint(&self, updated_at: &str, id: &str) -> Result<(), AppError> {
+        let conn = self.get_connection()?;
+        let conn = conn.lock().unwrap();
         conn.execute(
             "INSERT OR REPLACE INTO sync_metadata (key, value) VALUES ('checkpoint_updated_at', ?)",
             rusqlite::params![updated_at],
-        ).map_err(|e| e.to_string())?;
+        )?;
         conn.execute(
             "INSERT OR REPLACE INTO sync_metadata (key, value) VALUES ('checkpoint_id', ?)",
             rusqlite::params![id],
-        ).map_err(|e| e.to_string())?;
+        )?;
         Ok(())
     }
 
     /// Execute arbitrary SQL (for sync service)
-    pub fn execute_sql(&self, sql: &str, params: &[&str]) -> Result<(), String> {
-        let conn = self.conn.lock().unwrap();
+    pub fn execute_sql(&self, sql: &str, params: &[&str]) -> Result<(), AppError> {
+        let conn = self.get_connection()?;
+        let conn = conn.lock().unwrap();
         let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
-        conn.execute(sql, rusqlite::params_from_iter(params_refs))
-            .map_err(|e| e.to_string())?;
+        conn.execute(sql, rusqlite::params_from_iter(params_refs))?;
         Ok(())
     }
 
     /// Query count (for sync service)
-    pub fn query_count(&self, sql: &str) -> Result<usize, String> {
-        let conn = self.conn.lock().unwrap();
+    pub fn query_count(&self, sql: &str) -> Result<usize, AppError> {
+        let conn = self.read_connection()?;
         let count: i64 = conn
-            .query_row(sql, [], |row| row.get(0))
-            .map_err(|e| e.to_string())?;
+            .query_row(sql, [], |row| row.get(0))?;
         Ok(count as usize)
     }
 }
+
+/// A plain SQLite file starts with a fixed header; SQLCipher files start with
+/// random salt. Missing or empty files count as plain.
+fn is_encrypted(path: &Path) -> Result<bool, AppError> {
+    let mut header = [0u8; 16];
+    match fs::File::open(path) {
+        Ok(mut file) => match file.read_exact(&mut header) {
+            Ok(()) => Ok(&header != b"SQLite format 3\0"),
+            Err(_) => Ok(false),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(AppError::from(e).context("Failed to read database file")),
+    }
+}
+
+/// Copy the database open on `conn` into a new file at `path` with
+/// `sqlcipher_export`, encrypted under `key` or plain when it is `None`.
+/// The schema version is copied along, since the export leaves it at 0.
+fn export_to(conn: &Connection, path: &Path, key: Option<&str>) -> Result<()> {
+    conn.execute(
+        "ATTACH DATABASE ?1 AS exported KEY ?2",
+        rusqlite::params![path.to_string_lossy(), key.unwrap_or("")],
+    )?;
+    let result = conn
+        .query_row("SELECT sqlcipher_export('exported')", [], |_| Ok(()))
+        .and_then(|_| migrations::current_version(conn))
+        .and_then(|version| {
+            conn.execute_batch(&format!("PRAGMA exported.user_version = {}", version))
+        });
+    let detached = conn.execute_batch("DETACH DATABASE exported");
+    result.and(detached)
+}
+
+/// Split `database-<time>-<reason>.sqlite` into an RFC 3339 time and reason
+fn parse_backup_name(file_name: &str) -> Option<(String, String)> {
+    let stem = file_name.strip_prefix("database-")?.strip_suffix(".sqlite")?;
+    let (time, reason) = stem.split_once('-')?;
+    let time = chrono::NaiveDateTime::parse_from_str(time, BACKUP_TIME_FORMAT).ok()?;
+    Some((time.and_utc().to_rfc3339(), reason.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_backup_name() {
+        assert_eq!(
+            parse_backup_name("database-20250102T030405006Z-pre-import.sqlite"),
+            Some((
+                "2025-01-02T03:04:05.006+00:00".to_string(),
+                "pre-import".to_string()
+            ))
+        );
+        assert_eq!(parse_backup_name("database.sqlite"), None);
+        assert_eq!(parse_backup_name("database-garbage-x.sqlite"), None);
+    }
+
+    /// A folder to open test databases in, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("code-notes-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn open(&self) -> SqliteDatabase {
+            SqliteDatabase::open_dir(&self.0).unwrap()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn add_note(db: &SqliteDatabase, id: &str) {
+        let conn = db.get_connection().unwrap();
+        conn.lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO sync_metadata (key, value) VALUES (?1, 'note')",
+                [id],
+            )
+            .unwrap();
+    }
+
+    fn has_note(db: &SqliteDatabase, id: &str) -> bool {
+        db.read_connection()
+            .unwrap()
+            .query_row(
+                "SELECT count(*) FROM sync_metadata WHERE key = ?1",
+                [id],
+                |row| row.get::<_, i64>(0),
+            )
+            .unwrap()
+            == 1
+    }
+
+    fn error_code(result: Result<impl std::fmt::Debug, AppError>) -> ErrorCode {
+        result.unwrap_err().code
+    }
+
+    #[test]
+    fn test_enable_encryption_reopens_with_key() {
+        let dir = TempDir::new();
+        let db = dir.open();
+        add_note(&db, "n1");
+        db.enable_encryption("secret").unwrap();
+        assert!(has_note(&db, "n1"));
+        drop(db);
+
+        let db = dir.open();
+        assert!(db.status().unwrap().encrypted);
+        assert!(db.status().unwrap().locked);
+        assert_eq!(error_code(db.get_connection()), ErrorCode::DatabaseLocked);
+        db.unlock("secret").unwrap();
+        assert!(has_note(&db, "n1"));
+    }
+
+    #[test]
+    fn test_change_passphrase_reopens_with_new_key() {
+        let dir = TempDir::new();
+        let db = dir.open();
+        add_note(&db, "n1");
+        db.enable_encryption("old").unwrap();
+        assert_eq!(
+            error_code(db.change_passphrase("wrong", "new")),
+            ErrorCode::IncorrectPassphrase
+        );
+        db.change_passphrase("old", "new").unwrap();
+        drop(db);
+
+        let db = dir.open();
+        assert_eq!(error_code(db.unlock("old")), ErrorCode::IncorrectPassphrase);
+        db.unlock("new").unwrap();
+        assert!(has_note(&db, "n1"));
+    }
+
+    #[test]
+    fn test_disable_encryption_reopens_plain() {
+        let dir = TempDir::new();
+        let db = dir.open();
+        add_note(&db, "n1");
+        db.enable_encryption("secret").unwrap();
+        db.disable_encryption("secret").unwrap();
+        drop(db);
+
+        let db = dir.open();
+        let status = db.status().unwrap();
+        assert!(!status.encrypted && !status.locked);
+        assert!(has_note(&db, "n1"));
+    }
+
+    #[test]
+    fn test_enable_encryption_encrypts_earlier_backups() {
+        let dir = TempDir::new();
+        let db = dir.open();
+        add_note(&db, "n1");
+        let backup = db.create_backup("manual", 10).unwrap();
+        assert!(!backup.encrypted);
+        add_note(&db, "n2");
+
+        db.enable_encryption("secret").unwrap();
+        let backups = db.list_backups().unwrap();
+        assert_eq!(backups.len(), 1);
+        assert!(backups[0].encrypted);
+
+        db.restore_backup(&backup.file_name, None).unwrap();
+        assert!(has_note(&db, "n1"));
+        assert!(!has_note(&db, "n2"));
+        assert!(db.status().unwrap().encrypted);
+    }
+
+    #[test]
+    fn test_restore_plain_backup_into_encrypted_database() {
+        let dir = TempDir::new();
+        let db = dir.open();
+        add_note(&db, "n1");
+        let backup = db.create_backup("manual", 10).unwrap();
+        add_note(&db, "n2");
+
+        // Kept plain by hand, as a copy made outside the app would be
+        let path = dir.0.join("backups").join(&backup.file_name);
+        let plain = fs::read(&path).unwrap();
+        db.enable_encryption("secret").unwrap();
+        fs::write(&path, plain).unwrap();
+        assert!(!db.list_backups().unwrap()[0].encrypted);
+
+        db.restore_backup(&backup.file_name, None).unwrap();
+        assert!(has_note(&db, "n1"));
+        assert!(!has_note(&db, "n2"));
+        drop(db);
+
+        let db = dir.open();
+        db.unlock("secret").unwrap();
+        assert!(has_note(&db, "n1"));
+    }
+
+    #[test]
+    fn test_unlock_with_wrong_key_stays_locked() {
+        let dir = TempDir::new();
+        let db = dir.open();
+        db.enable_encryption("secret").unwrap();
+        drop(db);
+
+        let db = dir.open();
+        assert_eq!(
+            error_code(db.unlock("guess")),
+            ErrorCode::IncorrectPassphrase
+        );
+        assert!(db.status().unwrap().locked);
+        assert!(db.unlock("secret").unwrap());
+        assert!(!db.status().unwrap().locked);
+        assert!(!db.unlock("secret").unwrap());
+    }
+}
@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::models::Workspace;
+use crate::error::AppError;
+
+/// Id of the workspace that lives directly in the app data directory, where
+/// the database was kept before workspaces existed
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+const REGISTRY_FILE: &str = "workspaces.json";
+
+#[derive(Serialize, Deserialize)]
+struct RegistryFile {
+    active: String,
+    workspaces: Vec<Workspace>,
+}
+
+/// The list of workspaces and which one is active, kept in `workspaces.json`
+/// in the app data directory. Every other workspace gets its own folder
+/// under `workspaces/<id>`, holding its database, backups and login.
+pub struct WorkspaceRegistry {
+    root: PathBuf,
+    file: RegistryFile,
+}
+
+impl WorkspaceRegistry {
+    /// Read the registry, starting with just the default workspace if there
+    /// is none yet
+    pub fn load(root: &Path) -> Result<Self, AppError> {
+        let file = match fs::read_to_string(root.join(REGISTRY_FILE)) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::internal(format!("Failed to parse workspace list: {}", e)))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => RegistryFile {
+                active: DEFAULT_WORKSPACE.to_string(),
+                workspaces: vec![Workspace {
+                    id: DEFAULT_WORKSPACE.to_string(),
+                    name: "Default".to_string(),
+                    created_at: chrono::Utc::now().to_rfc3339(),
+                    active: false,
+                }],
+            },
+            Err(e) => return Err(AppError::from(e).context("Failed to read workspace list")),
+        };
+
+        let mut registry = Self {
+            root: root.to_path_buf(),
+            file,
+        };
+        // Fall back to the default workspace if the active one went missing
+        if registry.find(&registry.file.active.clone()).is_none() {
+            registry.file.active = DEFAULT_WORKSPACE.to_string();
+        }
+        Ok(registry)
+    }
+
+    fn save(&self) -> Result<(), AppError> {
+        let json = serde_json::to_string_pretty(&self.file)?;
+        // Write then rename so a crash never leaves a half-written list
+        let path = self.root.join(REGISTRY_FILE);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json)
+            .map_err(|e| AppError::from(e).context("Failed to save workspace list"))?;
+        fs::rename(&tmp, &path)
+            .map_err(|e| AppError::from(e).context("Failed to save workspace list"))
+    }
+
+    pub fn active_id(&self) -> &str {
+        &self.file.active
+    }
+
+    /// Folder holding a workspace's files
+    pub fn dir(&self, id: &str) -> PathBuf {
+        if id == DEFAULT_WORKSPACE {
+            self.root.clone()
+        } else {
+            self.root.join("workspaces").join(id)
+        }
+    }
+
+    pub fn find(&self, id: &str) -> Option<&Workspace> {
+        self.file.workspaces.iter().find(|w| w.id == id)
+    }
+
+    /// Workspaces in creation order, with the active one flagged
+    pub fn list(&self) -> Vec<Workspace> {
+        self.file
+            .workspaces
+            .iter()
+            .map(|w| Workspace {
+                active: w.id == self.file.active,
+                ..w.clone()
+            })
+            .collect()
+    }
+
+    pub fn create(&mut self, name: &str) -> Result<Workspace, AppError> {
+        let name = self.check_name(name, None)?;
+        let workspace = Workspace {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            active: false,
+        };
+        fs::create_dir_all(self.dir(&workspace.id))
+            .map_err(|e| AppError::from(e).context("Failed to create workspace folder"))?;
+        self.file.workspaces.push(workspace.clone());
+        self.save()?;
+        Ok(workspace)
+    }
+
+    pub fn rename(&mut self, id: &str, name: &str) -> Result<Workspace, AppError> {
+        let name = self.check_name(name, Some(id))?;
+        let active = self.file.active == id;
+        let workspace = self
+            .file
+            .workspaces
+            .iter_mut()
+            .find(|w| w.id == id)
+            .ok_or_else(|| AppError::not_found("workspace", id))?;
+        workspace.name = name;
+        let renamed = Workspace {
+            active,
+            ..workspace.clone()
+        };
+        self.save()?;
+        Ok(renamed)
+    }
+
+    /// Remove a workspace from the list. The caller deletes its folder.
+    pub fn remove(&mut self, id: &str) -> Result<(), AppError> {
+        if id == DEFAULT_WORKSPACE {
+            return Err(AppError::conflict("The default workspace cannot be deleted"));
+        }
+        if id == self.file.active {
+            return Err(AppError::conflict(
+                "Switch to another workspace before deleting this one",
+            ));
+        }
+        let before = self.file.workspaces.len();
+        self.file.workspaces.retain(|w| w.id != id);
+        if self.file.workspaces.len() == before {
+            return Err(AppError::not_found("workspace", id));
+        }
+        self.save()
+    }
+
+    pub fn set_active(&mut self, id: &str) -> Result<(), AppError> {
+        if self.find(id).is_none() {
+            return Err(AppError::not_found("workspace", id));
+        }
+        self.file.active = id.to_string();
+        self.save()
+    }
+
+    /// Trimmed, non-empty and not already used by another workspace
+    fn check_name(&self, name: &str, id: Option<&str>) -> Result<String, AppError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::validation("Workspace name cannot be empty").with_field("name"));
+        }
+        let taken = self
+            .file
+            .workspaces
+            .iter()
+            .any(|w| Some(w.id.as_str()) != id && w.name.eq_ignore_ascii_case(name));
+        if taken {
+            return Err(
+                AppError::conflict(format!("A workspace named \"{}\" already exists", name))
+                    .with_field("name"),
+            );
+        }
+        Ok(name.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("code-notes-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_registry_round_trip() {
+        let root = temp_root("workspaces");
+        let mut registry = WorkspaceRegistry::load(&root).unwrap();
+        assert_eq!(registry.active_id(), DEFAULT_WORKSPACE);
+        assert_eq!(registry.dir(DEFAULT_WORKSPACE), root);
+
+        let work = registry.create("  Work ").unwrap();
+        assert_eq!(work.name, "Work");
+        assert!(registry.dir(&work.id).is_dir());
+        assert!(registry.create("work").is_err());
+        assert!(registry.rename(DEFAULT_WORKSPACE, "WORK").is_err());
+        registry.set_active(&work.id).unwrap();
+        assert!(registry.remove(&work.id).is_err());
+        assert!(registry.remove(DEFAULT_WORKSPACE).is_err());
+
+        let registry = WorkspaceRegistry::load(&root).unwrap();
+        assert_eq!(registry.active_id(), work.id);
+        let listed = registry.list();
+        assert_eq!(listed.len(), 2);
+        assert!(listed.iter().any(|w| w.id == work.id && w.active));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
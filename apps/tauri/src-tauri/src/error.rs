@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::utils::search_query::SearchQueryError;
+
+/// Machine-readable kind of an `AppError`. The serialized names are part of
+/// the command API: add new codes rather than renaming existing ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The record doesn't exist or has been deleted
+    NotFound,
+    /// The input was rejected; `field` names it when known
+    Validation,
+    /// The change clashes with existing data, e.g. a name already in use
+    Conflict,
+    /// The database is encrypted and hasn't been unlocked yet
+    DatabaseLocked,
+    /// Another connection held the database past the busy timeout
+    DatabaseBusy,
+    /// The passphrase doesn't open the database or backup
+    IncorrectPassphrase,
+    /// The database or backup was written by a newer version of the app
+    UnsupportedVersion,
+    /// Any other SQLite failure
+    Database,
+    /// Reading or writing a file failed
+    Io,
+    /// Not logged in to the sync server, or the login has expired
+    Unauthenticated,
+    /// The sync server couldn't be reached or rejected the request
+    Network,
+    /// Anything else; the message is the only detail
+    Internal,
+}
+
+/// Error returned by every command, repository and service. Serializes as
+/// `{ "code": "not_found", "message": "Question 42 not found",
+/// "entity": "question", "entityId": "42" }`, leaving out details that
+/// don't apply.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppError {
+    pub code: ErrorCode,
+    /// Human-readable description, fit to show as is
+    pub message: String,
+    /// Input field that failed validation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Kind of record involved, e.g. `question` or `saved_search`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity: Option<String>,
+    #[serde(rename = "entityId", default, skip_serializing_if = "Option::is_none")]
+    pub entity_id: Option<String>,
+    /// Character offset into the input, for parse errors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            field: None,
+            entity: None,
+            entity_id: None,
+            position: None,
+        }
+    }
+
+    /// `entity` is snake_case, e.g. `saved_search` reads "Saved search <id> not found"
+    pub fn not_found(entity: &str, id: impl fmt::Display) -> Self {
+        let id = id.to_string();
+        Self::new(
+            ErrorCode::NotFound,
+            format!("{} {} not found", capitalize(&entity.replace('_', " ")), id),
+        )
+        .with_entity(entity, id)
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Validation, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    pub fn with_field(mut self, field: &str) -> Self {
+        self.field = Some(field.to_string());
+        self
+    }
+
+    pub fn with_entity(mut self, entity: &str, id: impl Into<String>) -> Self {
+        self.entity = Some(entity.to_string());
+        self.entity_id = Some(id.into());
+        self
+    }
+
+    /// Prefix the message with what was being done, keeping the code
+    pub fn context(mut self, context: impl fmt::Display) -> Self {
+        self.message = format!("{}: {}", context, self.message);
+        self
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        let code = match e.sqlite_error_code() {
+            Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked) => {
+                ErrorCode::DatabaseBusy
+            }
+            Some(rusqlite::ErrorCode::ConstraintViolation) => ErrorCode::Conflict,
+            _ if matches!(e, rusqlite::Error::QueryReturnedNoRows) => ErrorCode::NotFound,
+            _ => ErrorCode::Database,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        Self::new(ErrorCode::Io, e.to_string())
+    }
+}
+
+/// JSON the app wrote itself; parse caller input with an explicit
+/// `validation` error instead
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::internal(e.to_string())
+    }
+}
+
+impl From<SearchQueryError> for AppError {
+    fn from(e: SearchQueryError) -> Self {
+        let mut err = Self::validation(e.to_string()).with_field("query");
+        err.position = Some(e.position);
+        err
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_code_and_present_details_only() {
+        let json = serde_json::to_value(AppError::not_found("saved_search", "abc")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "code": "not_found",
+                "message": "Saved search abc not found",
+                "entity": "saved_search",
+                "entityId": "abc",
+            })
+        );
+
+        let json = serde_json::to_value(AppError::validation("Too long").with_field("name")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "code": "validation", "message": "Too long", "field": "name" })
+        );
+    }
+
+    #[test]
+    fn test_from_rusqlite_error() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (id TEXT PRIMARY KEY)").unwrap();
+        conn.execute("INSERT INTO t VALUES ('a')", []).unwrap();
+
+        let duplicate = conn.execute("INSERT INTO t VALUES ('a')", []).unwrap_err();
+        assert_eq!(AppError::from(duplicate).code, ErrorCode::Conflict);
+
+        let missing = conn
+            .query_row("SELECT id FROM t WHERE id = 'b'", [], |row| row.get::<_, String>(0))
+            .unwrap_err();
+        assert_eq!(AppError::from(missing).code, ErrorCode::NotFound);
+
+        let bad_sql = conn.execute("SELECT * FROM nope", []).unwrap_err();
+        assert_eq!(AppError::from(bad_sql).code, ErrorCode::Database);
+    }
+}
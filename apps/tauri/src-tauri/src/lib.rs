@@ -1,11 +1,14 @@
 mod auth;
 mod commands;
 mod database;
+mod error;
 mod sync;
 mod utils;
 
 use crate::auth::AuthService;
+use crate::database::models::ATTACHMENT_SCHEME;
 use crate::database::LazyDatabase;
+use crate::error::AppError;
 use crate::sync::SyncService;
 use commands::*;
 use std::sync::Arc;
@@ -13,35 +16,48 @@ use tauri::Manager;
 
 // Sync commands
 #[tauri::command]
-async fn sync_now(app: tauri::AppHandle) -> Result<sync::SyncResult, String> {
+async fn sync_now(app: tauri::AppHandle) -> Result<sync::SyncResult, AppError> {
     let sync_service = app.state::<Arc<SyncService>>();
     sync_service.sync_now(&app).await
 }
 
 #[tauri::command]
-async fn get_sync_status(app: tauri::AppHandle) -> Result<sync::SyncStatus, String> {
+async fn get_sync_status(app: tauri::AppHandle) -> Result<sync::SyncStatus, AppError> {
     let sync_service = app.state::<Arc<SyncService>>();
     sync_service.get_sync_status(&app).await
 }
 
 #[tauri::command]
-async fn sync_login(app: tauri::AppHandle, email: String, password: String) -> Result<auth::AuthResponse, String> {
+async fn sync_login(
+    app: tauri::AppHandle,
+    email: String,
+    password: String,
+) -> Result<auth::AuthResponse, AppError> {
     let auth_service = app.state::<Arc<std::sync::Mutex<AuthService>>>();
-    let auth = auth_service.lock().map_err(|e| format!("Failed to lock auth: {}", e))?.clone();
+    let auth = auth_service
+        .lock()
+        .map_err(|e| AppError::internal(format!("Failed to lock auth: {}", e)))?
+        .clone();
     auth.login(&app, email, password).await
 }
 
 #[tauri::command]
-async fn sync_logout(app: tauri::AppHandle) -> Result<(), String> {
+async fn sync_logout(app: tauri::AppHandle) -> Result<(), AppError> {
     let auth_service = app.state::<Arc<std::sync::Mutex<AuthService>>>();
-    let auth = auth_service.lock().map_err(|e| format!("Failed to lock auth: {}", e))?.clone();
+    let auth = auth_service
+        .lock()
+        .map_err(|e| AppError::internal(format!("Failed to lock auth: {}", e)))?
+        .clone();
     auth.logout(&app).await
 }
 
 #[tauri::command]
-async fn get_auth_status(app: tauri::AppHandle) -> Result<auth::AuthStatus, String> {
+async fn get_auth_status(app: tauri::AppHandle) -> Result<auth::AuthStatus, AppError> {
     let auth_service = app.state::<Arc<std::sync::Mutex<AuthService>>>();
-    let auth = auth_service.lock().map_err(|e| format!("Failed to lock auth: {}", e))?.clone();
+    let auth = auth_service
+        .lock()
+        .map_err(|e| AppError::internal(format!("Failed to lock auth: {}", e)))?
+        .clone();
     Ok(auth.get_auth_status(&app).await)
 }
 
@@ -52,12 +68,22 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_store::Builder::default().build())
+        .register_uri_scheme_protocol(ATTACHMENT_SCHEME, |ctx, request| {
+            attachment_protocol(ctx.app_handle(), &request)
+        })
         .setup(|app| {
             // Initialize Database (SQLite)
             let db = LazyDatabase::init(&app.handle()).expect("Failed to initialize database");
             let db = Arc::new(db);
             app.manage(db.clone());
 
+            // Snapshot on every start; an encrypted database is backed up on unlock
+            if !db.status().map(|s| s.locked).unwrap_or(true) {
+                if let Err(e) = db.create_backup("startup", backup_retention(&app.handle())) {
+                    eprintln!("Startup backup failed: {}", e);
+                }
+            }
+
             // Initialize Auth Service
             let server_url = std::env::var("SYNC_SERVER_URL").unwrap_or_default();
             let app_id = std::env::var("SYNC_APP_ID").unwrap_or_else(|_| "code-notes".to_string());
@@ -77,24 +103,45 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             // Topic commands
             get_topics,
+            get_topics_page,
+            get_topic_tree,
             get_topic_by_id,
             create_topic,
             update_topic,
+            move_topic,
+            archive_topic,
+            unarchive_topic,
+            reorder_topics,
             delete_topic,
             // Question commands
             get_questions,
             get_question_by_id,
             get_questions_by_topic,
+            get_questions_page,
             create_question,
             update_question,
             delete_question,
+            reorder_questions,
+            renumber_topic,
+            // Bulk commands
+            bulk_move_questions,
+            bulk_add_tags,
+            bulk_remove_tags,
+            bulk_set_difficulty,
+            bulk_delete_questions,
+            bulk_reset_progress,
+            // Duplicate commands
+            find_duplicate_questions,
+            merge_questions,
             // Query commands
             query_database,
+            filter_questions,
             search_questions,
             search_topics,
             get_topic_stats,
             // Progress commands
             get_all_progress,
+            get_progress_page,
             get_progress_by_question,
             get_progress_by_topic,
             update_question_progress,
@@ -109,10 +156,78 @@ pub fn run() {
             submit_quiz_answer,
             complete_quiz_session,
             get_quiz_history,
+            get_quiz_history_page,
+            // Tag commands
+            get_tags,
+            autocomplete_tags,
+            rename_tag,
+            merge_tags,
+            delete_tag,
+            // Difficulty commands
+            get_difficulty_levels,
+            create_difficulty_level,
+            update_difficulty_level,
+            delete_difficulty_level,
+            // Trash commands
+            get_trashed_topics,
+            get_trashed_questions,
+            restore_topic,
+            restore_question,
+            purge_topic,
+            purge_question,
+            empty_trash,
+            // Link commands
+            add_question_link,
+            remove_question_link,
+            get_question_links,
+            // Annotation commands
+            get_annotations,
+            create_annotation,
+            update_annotation,
+            delete_annotation,
+            // Saved search commands
+            get_saved_searches,
+            get_saved_search,
+            create_saved_search,
+            update_saved_search,
+            delete_saved_search,
+            get_saved_search_members,
+            start_saved_search_quiz,
+            // Attachment commands
+            upload_attachment,
+            get_attachments,
+            delete_attachment,
+            // Revision commands
+            get_question_revisions,
+            diff_question_revisions,
+            revert_question,
+            // Encryption commands
+            get_database_status,
+            unlock_database,
+            enable_encryption,
+            change_passphrase,
+            disable_encryption,
+            // Backup commands
+            get_backups,
+            create_backup,
+            restore_backup,
+            get_backup_retention,
+            set_backup_retention,
+            // Integrity commands
+            check_database,
+            repair_database,
+            // Workspace commands
+            get_workspaces,
+            create_workspace,
+            rename_workspace,
+            delete_workspace,
+            switch_workspace,
             // Data Management commands
             export_database,
             import_database,
             get_database_stats,
+            // Import commands
+            import_from_markdown,
             // Sync commands
             sync_now,
             get_sync_status,
@@ -6,7 +6,10 @@ use serde::{Deserialize, Serialize};
 use qm_sync_client::{Checkpoint, ReqwestHttpClient, QmSyncClient, SyncClientConfig, SyncRecord};
 
 use crate::auth::AuthService;
-use crate::database::LazyDatabase;
+use crate::database::models::Difficulty;
+use crate::database::repository::difficulty_repo;
+use crate::database::{run_blocking, LazyDatabase};
+use crate::error::{AppError, ErrorCode};
 
 /// Sync service for synchronizing local data with qm-sync
 #[derive(Clone)]
@@ -50,14 +53,20 @@ impl SyncService {
     pub async fn get_sync_status(
         &self,
         app_handle: &tauri::AppHandle,
-    ) -> Result<SyncStatus, String> {
+    ) -> Result<SyncStatus, AppError> {
         let is_authenticated = {
-            let auth = self.auth.lock().map_err(|e| format!("Failed to lock auth: {}", e))?.clone();
+            let auth = self
+                .auth
+                .lock()
+                .map_err(|e| AppError::internal(format!("Failed to lock auth: {}", e)))?
+                .clone();
             auth.is_authenticated(app_handle).await
         };
 
-        let last_sync_at = self.get_last_sync_timestamp()?;
-        let pending_changes = self.count_pending_changes()?;
+        let (last_sync_at, pending_changes) = {
+            let this = self.clone();
+            run_blocking(move || Ok((this.get_last_sync_timestamp()?, this.count_pending_changes()?))).await?
+        };
         let server_url = std::env::var("SYNC_SERVER_URL").ok();
 
         Ok(SyncStatus {
@@ -73,18 +82,24 @@ impl SyncService {
     pub async fn sync_now(
         &self,
         app_handle: &tauri::AppHandle,
-    ) -> Result<SyncResult, String> {
+    ) -> Result<SyncResult, AppError> {
         let start_time = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
-            .map_err(|e| format!("Time error: {}", e))?
+            .map_err(|e| AppError::internal(format!("Time error: {}", e)))?
             .as_secs() as i64;
+        // The login and checkpoint below belong to this workspace
+        let workspace_id = self.db.active_workspace_id();
 
         let (server_url, app_id, api_key, access_token, refresh_token) = {
-            let auth = self.auth.lock().map_err(|e| format!("Failed to lock auth: {}", e))?.clone();
+            let auth = self
+                .auth
+                .lock()
+                .map_err(|e| AppError::internal(format!("Failed to lock auth: {}", e)))?
+                .clone();
             let access_token = auth.get_access_token(app_handle).await?;
             let refresh_token = auth.get_refresh_token(app_handle).await?;
             let server_url = std::env::var("SYNC_SERVER_URL")
-                .map_err(|_| "SYNC_SERVER_URL not configured")?;
+                .map_err(|_| AppError::internal("SYNC_SERVER_URL not configured"))?;
             let app_id = self.get_app_id(app_handle).await?;
             let api_key = auth.get_stored_api_key(app_handle)?;
             (server_url, app_id, api_key, access_token, refresh_token)
@@ -96,13 +111,15 @@ impl SyncService {
 
         client.set_tokens(access_token, refresh_token, None).await;
 
-        let local_changes = self.collect_local_changes()?;
-        let checkpoint = self.get_checkpoint()?;
+        let (local_changes, checkpoint) = {
+            let this = self.clone();
+            run_blocking(move || Ok((this.collect_local_changes()?, this.get_checkpoint()?))).await?
+        };
 
         println!("Syncing {} local changes, checkpoint: {:?}", local_changes.len(), checkpoint);
 
         let response = client.delta(local_changes.clone(), checkpoint).await
-            .map_err(|e| format!("Sync failed: {}", e))?;
+            .map_err(|e| AppError::new(ErrorCode::Network, format!("Sync failed: {}", e)))?;
 
         let mut pushed = 0;
         let mut conflicts = 0;
@@ -111,7 +128,13 @@ impl SyncService {
         if let Some(push) = &response.push {
             pushed = push.synced;
             conflicts = push.conflicts.len();
-            self.mark_records_synced(&local_changes, start_time)?;
+            let this = self.clone();
+            let workspace_id = workspace_id.clone();
+            run_blocking(move || {
+                this.ensure_workspace(&workspace_id)?;
+                this.mark_records_synced(&local_changes, start_time)
+            })
+            .await?;
         }
 
         if let Some(pull) = &response.pull {
@@ -125,8 +148,14 @@ impl SyncService {
                 deleted: r.deleted,
             }).collect();
 
-            self.apply_remote_changes(&sync_records)?;
-            self.save_checkpoint(&pull.checkpoint)?;
+            let checkpoint = Checkpoint::new(pull.checkpoint.updated_at, pull.checkpoint.id.clone());
+            let this = self.clone();
+            run_blocking(move || {
+                this.ensure_workspace(&workspace_id)?;
+                this.apply_remote_changes(&sync_records)?;
+                this.save_checkpoint(&checkpoint)
+            })
+            .await?;
         }
 
         Ok(SyncResult {
@@ -139,22 +168,32 @@ impl SyncService {
         })
     }
 
+    /// Refuse to write sync results after the user switched workspaces
+    /// mid-sync, since they belong to the previous workspace's database
+    fn ensure_workspace(&self, workspace_id: &str) -> Result<(), AppError> {
+        if self.db.active_workspace_id() != workspace_id {
+            return Err(AppError::conflict("Workspace changed during sync"));
+        }
+        Ok(())
+    }
+
     /// Collect local changes since last sync
-    fn collect_local_changes(&self) -> Result<Vec<SyncRecord>, String> {
+    fn collect_local_changes(&self) -> Result<Vec<SyncRecord>, AppError> {
         let mut records = Vec::new();
-        let conn = self.db.get_connection();
-        let conn = conn.lock().unwrap();
+        let conn = self.db.read_connection()?;
+        // Read every table from one snapshot without holding the writer
+        let _snapshot = conn.unchecked_transaction()?;
 
         // Collect deleted topics
         {
             let mut stmt = conn.prepare(
                 "SELECT id, sync_version FROM topics WHERE deleted = 1 AND synced_at IS NULL"
-            ).map_err(|e| e.to_string())?;
+            )?;
             let rows = stmt.query_map([], |row| {
                 Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
-            }).map_err(|e| e.to_string())?;
+            })?;
             for row in rows {
-                let (id, version) = row.map_err(|e| e.to_string())?;
+                let (id, version) = row?;
                 records.push(SyncRecord {
                     table_name: "topics".to_string(),
                     row_id: id,
@@ -168,9 +207,9 @@ impl SyncService {
         // Collect unsynced active topics
         {
             let mut stmt = conn.prepare(
-                "SELECT id, name, description, slug, icon, color, subtopics, order_index, created_at, updated_at, sync_version
+                "SELECT id, name, description, slug, icon, color, parent_id, order_index, created_at, updated_at, sync_version, archived
                  FROM topics WHERE deleted = 0 AND synced_at IS NULL"
-            ).map_err(|e| e.to_string())?;
+            )?;
             let rows = stmt.query_map([], |row| {
                 Ok((
                     row.get::<_, String>(0)?,
@@ -184,20 +223,22 @@ impl SyncService {
                     row.get::<_, String>(8)?,
                     row.get::<_, String>(9)?,
                     row.get::<_, i64>(10)?,
+                    row.get::<_, bool>(11)?,
                 ))
-            }).map_err(|e| e.to_string())?;
+            })?;
             for row in rows {
-                let (id, name, description, slug, icon, color, subtopics, order_index, created_at, updated_at, sync_version) = row.map_err(|e| e.to_string())?;
+                let (id, name, description, slug, icon, color, parent_id, order_index, created_at, updated_at, sync_version, archived) = row?;
                 let mut data = serde_json::json!({
                     "name": name,
                     "description": description,
                     "slug": slug,
                     "icon": icon,
                     "color": color,
-                    "subtopics": subtopics,
+                    "parentSyncUuid": parent_id,
                     "orderIndex": order_index,
                     "createdAt": created_at,
                     "updatedAt": updated_at,
+                    "archived": archived,
                 });
                 if let Some(obj) = data.as_object_mut() {
                     obj.retain(|_, v| !v.is_null());
@@ -216,12 +257,12 @@ impl SyncService {
         {
             let mut stmt = conn.prepare(
                 "SELECT id, sync_version FROM questions WHERE deleted = 1 AND synced_at IS NULL"
-            ).map_err(|e| e.to_string())?;
+            )?;
             let rows = stmt.query_map([], |row| {
                 Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
-            }).map_err(|e| e.to_string())?;
+            })?;
             for row in rows {
-                let (id, version) = row.map_err(|e| e.to_string())?;
+                let (id, version) = row?;
                 records.push(SyncRecord {
                     table_name: "questions".to_string(),
                     row_id: id,
@@ -235,30 +276,28 @@ impl SyncService {
         // Collect unsynced active questions
         {
             let mut stmt = conn.prepare(
-                "SELECT id, topic_id, subtopic, question_number, question, answer, tags, difficulty, order_index, created_at, updated_at, sync_version
+                "SELECT id, topic_id, question_number, question, answer, tags, difficulty, order_index, created_at, updated_at, sync_version
                  FROM questions WHERE deleted = 0 AND synced_at IS NULL"
-            ).map_err(|e| e.to_string())?;
+            )?;
             let rows = stmt.query_map([], |row| {
                 Ok((
                     row.get::<_, String>(0)?,
                     row.get::<_, String>(1)?,
-                    row.get::<_, Option<String>>(2)?,
-                    row.get::<_, i32>(3)?,
+                    row.get::<_, i32>(2)?,
+                    row.get::<_, String>(3)?,
                     row.get::<_, String>(4)?,
-                    row.get::<_, String>(5)?,
+                    row.get::<_, Option<String>>(5)?,
                     row.get::<_, Option<String>>(6)?,
-                    row.get::<_, Option<String>>(7)?,
-                    row.get::<_, i32>(8)?,
+                    row.get::<_, i32>(7)?,
+                    row.get::<_, String>(8)?,
                     row.get::<_, String>(9)?,
-                    row.get::<_, String>(10)?,
-                    row.get::<_, i64>(11)?,
+                    row.get::<_, i64>(10)?,
                 ))
-            }).map_err(|e| e.to_string())?;
+            })?;
             for row in rows {
-                let (id, topic_id, subtopic, question_number, question, answer, tags, difficulty, order_index, created_at, updated_at, sync_version) = row.map_err(|e| e.to_string())?;
+                let (id, topic_id, question_number, question, answer, tags, difficulty, order_index, created_at, updated_at, sync_version) = row?;
                 let mut data = serde_json::json!({
                     "topicSyncUuid": topic_id,
-                    "subtopic": subtopic,
                     "questionNumber": question_number,
                     "question": question,
                     "answer": answer,
@@ -285,12 +324,12 @@ impl SyncService {
         {
             let mut stmt = conn.prepare(
                 "SELECT question_id, sync_version FROM progress WHERE deleted = 1 AND synced_at IS NULL"
-            ).map_err(|e| e.to_string())?;
+            )?;
             let rows = stmt.query_map([], |row| {
                 Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
-            }).map_err(|e| e.to_string())?;
+            })?;
             for row in rows {
-                let (qid, version) = row.map_err(|e| e.to_string())?;
+                let (qid, version) = row?;
                 records.push(SyncRecord {
                     table_name: "progress".to_string(),
                     row_id: qid,
@@ -306,7 +345,7 @@ impl SyncService {
             let mut stmt = conn.prepare(
                 "SELECT question_id, topic_id, status, confidence_level, times_reviewed, times_correct, times_incorrect, last_reviewed_at, next_review_at, created_at, updated_at, sync_version
                  FROM progress WHERE deleted = 0 AND synced_at IS NULL"
-            ).map_err(|e| e.to_string())?;
+            )?;
             let rows = stmt.query_map([], |row| {
                 Ok((
                     row.get::<_, String>(0)?,
@@ -322,9 +361,9 @@ impl SyncService {
                     row.get::<_, String>(10)?,
                     row.get::<_, i64>(11)?,
                 ))
-            }).map_err(|e| e.to_string())?;
+            })?;
             for row in rows {
-                let (question_id, topic_id, status, confidence_level, times_reviewed, times_correct, times_incorrect, last_reviewed_at, next_review_at, created_at, updated_at, sync_version) = row.map_err(|e| e.to_string())?;
+                let (question_id, topic_id, status, confidence_level, times_reviewed, times_correct, times_incorrect, last_reviewed_at, next_review_at, created_at, updated_at, sync_version) = row?;
                 let mut data = serde_json::json!({
                     "questionSyncUuid": question_id,
                     "topicSyncUuid": topic_id,
@@ -355,12 +394,12 @@ impl SyncService {
         {
             let mut stmt = conn.prepare(
                 "SELECT id, sync_version FROM quiz_sessions WHERE deleted = 1 AND synced_at IS NULL"
-            ).map_err(|e| e.to_string())?;
+            )?;
             let rows = stmt.query_map([], |row| {
                 Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
-            }).map_err(|e| e.to_string())?;
+            })?;
             for row in rows {
-                let (id, version) = row.map_err(|e| e.to_string())?;
+                let (id, version) = row?;
                 records.push(SyncRecord {
                     table_name: "quiz_sessions".to_string(),
                     row_id: id,
@@ -376,7 +415,7 @@ impl SyncService {
             let mut stmt = conn.prepare(
                 "SELECT id, session_type, topic_ids, question_ids, current_index, started_at, completed_at, results, sync_version
                  FROM quiz_sessions WHERE deleted = 0 AND synced_at IS NULL"
-            ).map_err(|e| e.to_string())?;
+            )?;
             let rows = stmt.query_map([], |row| {
                 Ok((
                     row.get::<_, String>(0)?,
@@ -389,9 +428,9 @@ impl SyncService {
                     row.get::<_, Option<String>>(7)?,
                     row.get::<_, i64>(8)?,
                 ))
-            }).map_err(|e| e.to_string())?;
+            })?;
             for row in rows {
-                let (id, session_type, topic_ids, question_ids, current_index, started_at, completed_at, results, sync_version) = row.map_err(|e| e.to_string())?;
+                let (id, session_type, topic_ids, question_ids, current_index, started_at, completed_at, results, sync_version) = row?;
                 let mut data = serde_json::json!({
                     "sessionType": session_type,
                     "topicIds": topic_ids,
@@ -414,11 +453,137 @@ impl SyncService {
             }
         }
 
+        // Collect deleted annotations
+        {
+            let mut stmt = conn.prepare(
+                "SELECT id, sync_version FROM annotations WHERE deleted = 1 AND synced_at IS NULL"
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?;
+            for row in rows {
+                let (id, version) = row?;
+                records.push(SyncRecord {
+                    table_name: "annotations".to_string(),
+                    row_id: id,
+                    data: serde_json::json!({}),
+                    version,
+                    deleted: true,
+                });
+            }
+        }
+
+        // Collect unsynced active annotations
+        {
+            let mut stmt = conn.prepare(
+                "SELECT id, question_id, body, created_at, updated_at, sync_version
+                 FROM annotations WHERE deleted = 0 AND synced_at IS NULL"
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, i64>(5)?,
+                ))
+            })?;
+            for row in rows {
+                let (id, question_id, body, created_at, updated_at, sync_version) = row?;
+                records.push(SyncRecord {
+                    table_name: "annotations".to_string(),
+                    row_id: id,
+                    data: serde_json::json!({
+                        "questionSyncUuid": question_id,
+                        "body": body,
+                        "createdAt": created_at,
+                        "updatedAt": updated_at,
+                    }),
+                    version: sync_version,
+                    deleted: false,
+                });
+            }
+        }
+
+        // Collect deleted saved searches
+        {
+            let mut stmt = conn.prepare(
+                "SELECT id, sync_version FROM saved_searches WHERE deleted = 1 AND synced_at IS NULL"
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?;
+            for row in rows {
+                let (id, version) = row?;
+                records.push(SyncRecord {
+                    table_name: "saved_searches".to_string(),
+                    row_id: id,
+                    data: serde_json::json!({}),
+                    version,
+                    deleted: true,
+                });
+            }
+        }
+
+        // Collect unsynced active saved searches
+        {
+            let mut stmt = conn.prepare(
+                "SELECT id, name, filter, created_at, updated_at, sync_version
+                 FROM saved_searches WHERE deleted = 0 AND synced_at IS NULL"
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, i64>(5)?,
+                ))
+            })?;
+            for row in rows {
+                let (id, name, filter, created_at, updated_at, sync_version) = row?;
+                records.push(SyncRecord {
+                    table_name: "saved_searches".to_string(),
+                    row_id: id,
+                    data: serde_json::json!({
+                        "name": name,
+                        "filter": serde_json::from_str::<serde_json::Value>(&filter).unwrap_or(serde_json::json!({})),
+                        "createdAt": created_at,
+                        "updatedAt": updated_at,
+                    }),
+                    version: sync_version,
+                    deleted: false,
+                });
+            }
+        }
+
+        // Collect purged rows whose deletion hasn't been pushed yet
+        {
+            let mut stmt = conn.prepare(
+                "SELECT table_name, row_id, sync_version FROM sync_tombstones"
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?))
+            })?;
+            for row in rows {
+                let (table_name, id, version) = row?;
+                records.push(SyncRecord {
+                    table_name,
+                    row_id: id,
+                    data: serde_json::json!({}),
+                    version,
+                    deleted: true,
+                });
+            }
+        }
+
         Ok(records)
     }
 
     /// Apply remote changes to local database
-    fn apply_remote_changes(&self, records: &[SyncRecord]) -> Result<(), String> {
+    fn apply_remote_changes(&self, records: &[SyncRecord]) -> Result<(), AppError> {
         let mut non_deleted: Vec<&SyncRecord> = records.iter().filter(|r| !r.deleted).collect();
         let mut deleted: Vec<&SyncRecord> = records.iter().filter(|r| r.deleted).collect();
 
@@ -426,19 +591,19 @@ impl SyncService {
         non_deleted.sort_by_key(|r| match r.table_name.as_str() {
             "topics" => 0,
             "questions" => 1,
-            "progress" | "quiz_sessions" => 2,
+            "progress" | "quiz_sessions" | "annotations" => 2,
             _ => 3,
         });
 
         // Sort: children first for deletes
         deleted.sort_by_key(|r| match r.table_name.as_str() {
-            "progress" | "quiz_sessions" => 0,
+            "progress" | "quiz_sessions" | "annotations" => 0,
             "questions" => 1,
             "topics" => 2,
             _ => 3,
         });
 
-        let conn = self.db.get_connection();
+        let conn = self.db.get_connection()?;
         let conn = conn.lock().unwrap();
         let now = chrono::Utc::now().timestamp();
 
@@ -454,25 +619,26 @@ impl SyncService {
 
                     if exists {
                         conn.execute(
-                            "UPDATE topics SET name=?, description=?, slug=?, icon=?, color=?, subtopics=?, order_index=?, created_at=?, updated_at=?, sync_version=?, synced_at=?, deleted=0 WHERE id=?",
+                            "UPDATE topics SET name=?, description=?, slug=?, icon=?, color=?, parent_id=?, order_index=?, created_at=?, updated_at=?, archived=?, sync_version=?, synced_at=?, deleted=0 WHERE id=?",
                             rusqlite::params![
                                 data["name"].as_str().unwrap_or(""),
                                 data["description"].as_str(),
                                 data["slug"].as_str().unwrap_or(""),
                                 data["icon"].as_str(),
                                 data["color"].as_str(),
-                                data["subtopics"].as_str(),
+                                data["parentSyncUuid"].as_str(),
                                 data["orderIndex"].as_i64().unwrap_or(0),
                                 data["createdAt"].as_str().unwrap_or(""),
                                 data["updatedAt"].as_str().unwrap_or(""),
+                                data["archived"].as_bool().unwrap_or(false),
                                 record.version,
                                 now,
                                 record.row_id,
                             ],
-                        ).map_err(|e| e.to_string())?;
+                        )?;
                     } else {
                         conn.execute(
-                            "INSERT INTO topics (id, name, description, slug, icon, color, subtopics, order_index, created_at, updated_at, sync_version, synced_at, deleted) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0)",
+                            "INSERT INTO topics (id, name, description, slug, icon, color, parent_id, order_index, created_at, updated_at, archived, sync_version, synced_at, deleted) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0)",
                             rusqlite::params![
                                 record.row_id,
                                 data["name"].as_str().unwrap_or(""),
@@ -480,18 +646,22 @@ impl SyncService {
                                 data["slug"].as_str().unwrap_or(""),
                                 data["icon"].as_str(),
                                 data["color"].as_str(),
-                                data["subtopics"].as_str(),
+                                data["parentSyncUuid"].as_str(),
                                 data["orderIndex"].as_i64().unwrap_or(0),
                                 data["createdAt"].as_str().unwrap_or(""),
                                 data["updatedAt"].as_str().unwrap_or(""),
+                                data["archived"].as_bool().unwrap_or(false),
                                 record.version,
                                 now,
                             ],
-                        ).map_err(|e| e.to_string())?;
+                        )?;
                     }
                 }
                 "questions" => {
                     let data = &record.data;
+                    // Another device may have levels this one doesn't know
+                    let difficulty = Difficulty::parse(data["difficulty"].as_str().unwrap_or(""));
+                    difficulty_repo::ensure_level(&conn, &difficulty)?;
                     let exists: bool = conn.query_row(
                         "SELECT COUNT(*) FROM questions WHERE id = ?",
                         rusqlite::params![record.row_id],
@@ -500,15 +670,14 @@ impl SyncService {
 
                     if exists {
                         conn.execute(
-                            "UPDATE questions SET topic_id=?, subtopic=?, question_number=?, question=?, answer=?, tags=?, difficulty=?, order_index=?, created_at=?, updated_at=?, sync_version=?, synced_at=?, deleted=0 WHERE id=?",
+                            "UPDATE questions SET topic_id=?, question_number=?, question=?, answer=?, tags=?, difficulty=?, order_index=?, created_at=?, updated_at=?, sync_version=?, synced_at=?, deleted=0 WHERE id=?",
                             rusqlite::params![
                                 data["topicSyncUuid"].as_str().unwrap_or(""),
-                                data["subtopic"].as_str(),
                                 data["questionNumber"].as_i64().unwrap_or(0),
                                 data["question"].as_str().unwrap_or(""),
                                 data["answer"].as_str().unwrap_or(""),
                                 data["tags"].as_str(),
-                                data["difficulty"].as_str(),
+                                difficulty,
                                 data["orderIndex"].as_i64().unwrap_or(0),
                                 data["createdAt"].as_str().unwrap_or(""),
                                 data["updatedAt"].as_str().unwrap_or(""),
@@ -516,26 +685,25 @@ impl SyncService {
                                 now,
                                 record.row_id,
                             ],
-                        ).map_err(|e| e.to_string())?;
+                        )?;
                     } else {
                         conn.execute(
-                            "INSERT INTO questions (id, topic_id, subtopic, question_number, question, answer, tags, difficulty, order_index, created_at, updated_at, sync_version, synced_at, deleted) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0)",
+                            "INSERT INTO questions (id, topic_id, question_number, question, answer, tags, difficulty, order_index, created_at, updated_at, sync_version, synced_at, deleted) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0)",
                             rusqlite::params![
                                 record.row_id,
                                 data["topicSyncUuid"].as_str().unwrap_or(""),
-                                data["subtopic"].as_str(),
                                 data["questionNumber"].as_i64().unwrap_or(0),
                                 data["question"].as_str().unwrap_or(""),
                                 data["answer"].as_str().unwrap_or(""),
                                 data["tags"].as_str(),
-                                data["difficulty"].as_str(),
+                                difficulty,
                                 data["orderIndex"].as_i64().unwrap_or(0),
                                 data["createdAt"].as_str().unwrap_or(""),
                                 data["updatedAt"].as_str().unwrap_or(""),
                                 record.version,
                                 now,
                             ],
-                        ).map_err(|e| e.to_string())?;
+                        )?;
                     }
                 }
                 "progress" => {
@@ -565,7 +733,7 @@ impl SyncService {
                                 now,
                                 question_id,
                             ],
-                        ).map_err(|e| e.to_string())?;
+                        )?;
                     } else {
                         let new_id = uuid::Uuid::new_v4().to_string();
                         conn.execute(
@@ -586,7 +754,7 @@ impl SyncService {
                                 record.version,
                                 now,
                             ],
-                        ).map_err(|e| e.to_string())?;
+                        )?;
                     }
                 }
                 "quiz_sessions" => {
@@ -612,7 +780,7 @@ impl SyncService {
                                 now,
                                 record.row_id,
                             ],
-                        ).map_err(|e| e.to_string())?;
+                        )?;
                     } else {
                         conn.execute(
                             "INSERT INTO quiz_sessions (id, session_type, topic_ids, question_ids, current_index, started_at, completed_at, results, sync_version, synced_at, deleted) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0)",
@@ -628,9 +796,49 @@ impl SyncService {
                                 record.version,
                                 now,
                             ],
-                        ).map_err(|e| e.to_string())?;
+                        )?;
                     }
                 }
+                "annotations" => {
+                    // No foreign key: an annotation may arrive before its question
+                    let data = &record.data;
+                    conn.execute(
+                        "INSERT INTO annotations (id, question_id, body, created_at, updated_at, sync_version, synced_at, deleted)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0)
+                         ON CONFLICT(id) DO UPDATE SET question_id=?2, body=?3, created_at=?4, updated_at=?5, sync_version=?6, synced_at=?7, deleted=0, deleted_at=NULL",
+                        rusqlite::params![
+                            record.row_id,
+                            data["questionSyncUuid"].as_str().unwrap_or(""),
+                            data["body"].as_str().unwrap_or(""),
+                            data["createdAt"].as_str().unwrap_or(""),
+                            data["updatedAt"].as_str().unwrap_or(""),
+                            record.version,
+                            now,
+                        ],
+                    )?;
+                }
+                "saved_searches" => {
+                    let data = &record.data;
+                    let filter = if data["filter"].is_object() {
+                        data["filter"].to_string()
+                    } else {
+                        "{}".to_string()
+                    };
+                    conn.execute(
+                        "INSERT INTO saved_searches (id, name, filter, created_at, updated_at, sync_version, synced_at, deleted)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0)
+                         ON CONFLICT(id) DO UPDATE SET name=?2, filter=?3, created_at=?4, updated_at=?5, sync_version=?6, synced_at=?7, deleted=0, deleted_at=NULL",
+                        rusqlite::params![
+                            record.row_id,
+                            data["name"].as_str().unwrap_or(""),
+                            filter,
+                            data["createdAt"].as_str().unwrap_or(""),
+                            data["updatedAt"].as_str().unwrap_or(""),
+                            record.version,
+                            now,
+                        ],
+                    )?;
+                }
                 _ => {
                     eprintln!("Unknown table: {}", record.table_name);
                 }
@@ -641,20 +849,22 @@ impl SyncService {
         for record in deleted {
             match record.table_name.as_str() {
                 "topics" => {
-                    conn.execute("DELETE FROM topics WHERE id = ?", rusqlite::params![record.row_id])
-                        .map_err(|e| e.to_string())?;
+                    conn.execute("DELETE FROM topics WHERE id = ?", rusqlite::params![record.row_id])?;
                 }
                 "questions" => {
-                    conn.execute("DELETE FROM questions WHERE id = ?", rusqlite::params![record.row_id])
-                        .map_err(|e| e.to_string())?;
+                    conn.execute("DELETE FROM questions WHERE id = ?", rusqlite::params![record.row_id])?;
                 }
                 "progress" => {
-                    conn.execute("DELETE FROM progress WHERE question_id = ?", rusqlite::params![record.row_id])
-                        .map_err(|e| e.to_string())?;
+                    conn.execute("DELETE FROM progress WHERE question_id = ?", rusqlite::params![record.row_id])?;
                 }
                 "quiz_sessions" => {
-                    conn.execute("DELETE FROM quiz_sessions WHERE id = ?", rusqlite::params![record.row_id])
-                        .map_err(|e| e.to_string())?;
+                    conn.execute("DELETE FROM quiz_sessions WHERE id = ?", rusqlite::params![record.row_id])?;
+                }
+                "annotations" => {
+                    conn.execute("DELETE FROM annotations WHERE id = ?", rusqlite::params![record.row_id])?;
+                }
+                "saved_searches" => {
+                    conn.execute("DELETE FROM saved_searches WHERE id = ?", rusqlite::params![record.row_id])?;
                 }
                 _ => {
                     eprintln!("Unknown table: {}", record.table_name);
@@ -666,60 +876,58 @@ impl SyncService {
     }
 
     /// Mark records as synced
-    fn mark_records_synced(&self, records: &[SyncRecord], synced_at: i64) -> Result<(), String> {
-        let conn = self.db.get_connection();
+    fn mark_records_synced(&self, records: &[SyncRecord], synced_at: i64) -> Result<(), AppError> {
+        let conn = self.db.get_connection()?;
         let conn = conn.lock().unwrap();
 
         for record in records {
             if record.deleted {
+                // Purged rows are gone already; forget their tombstone
+                conn.execute(
+                    "DELETE FROM sync_tombstones WHERE table_name = ? AND row_id = ?",
+                    rusqlite::params![record.table_name, record.row_id],
+                )?;
+            }
+            if record.deleted
+                && matches!(record.table_name.as_str(), "quiz_sessions" | "annotations" | "saved_searches")
+            {
                 // Hard-delete locally after successful push
-                match record.table_name.as_str() {
-                    "topics" => {
-                        conn.execute("DELETE FROM topics WHERE id = ?", rusqlite::params![record.row_id])
-                            .map_err(|e| e.to_string())?;
-                    }
-                    "questions" => {
-                        conn.execute("DELETE FROM questions WHERE id = ?", rusqlite::params![record.row_id])
-                            .map_err(|e| e.to_string())?;
-                    }
-                    "progress" => {
-                        conn.execute("DELETE FROM progress WHERE question_id = ?", rusqlite::params![record.row_id])
-                            .map_err(|e| e.to_string())?;
-                    }
-                    "quiz_sessions" => {
-                        conn.execute("DELETE FROM quiz_sessions WHERE id = ?", rusqlite::params![record.row_id])
-                            .map_err(|e| e.to_string())?;
-                    }
-                    _ => {}
-                }
+                conn.execute(
+                    &format!("DELETE FROM {} WHERE id = ?", record.table_name),
+                    rusqlite::params![record.row_id],
+                )?;
             } else {
-                // Update synced_at for active records
+                // Update synced_at; soft-deleted topics, questions and progress
+                // stay in the trash until purged
                 let (table, pk_col) = match record.table_name.as_str() {
                     "topics" => ("topics", "id"),
                     "questions" => ("questions", "id"),
                     "progress" => ("progress", "question_id"),
                     "quiz_sessions" => ("quiz_sessions", "id"),
+                    "annotations" => ("annotations", "id"),
+                    "saved_searches" => ("saved_searches", "id"),
                     _ => continue,
                 };
                 let query = format!(
                     "UPDATE {} SET synced_at = ? WHERE {} = ?",
                     table, pk_col
                 );
-                conn.execute(&query, rusqlite::params![synced_at, record.row_id])
-                    .map_err(|e| e.to_string())?;
+                conn.execute(&query, rusqlite::params![synced_at, record.row_id])?;
             }
         }
         Ok(())
     }
 
     /// Get checkpoint from database
-    fn get_checkpoint(&self) -> Result<Option<Checkpoint>, String> {
+    fn get_checkpoint(&self) -> Result<Option<Checkpoint>, AppError> {
         let result = self.db.get_checkpoint()?;
         match result {
             Some((updated_at_str, id)) => {
                 let updated_at = DateTime::parse_from_rfc3339(&updated_at_str)
                     .map(|dt| dt.with_timezone(&Utc))
-                    .map_err(|e| format!("Failed to parse checkpoint timestamp: {}", e))?;
+                    .map_err(|e| {
+                        AppError::internal(format!("Failed to parse checkpoint timestamp: {}", e))
+                    })?;
                 Ok(Some(Checkpoint::new(updated_at, id)))
             }
             None => Ok(None),
@@ -727,19 +935,21 @@ impl SyncService {
     }
 
     /// Save checkpoint to database
-    fn save_checkpoint(&self, checkpoint: &Checkpoint) -> Result<(), String> {
+    fn save_checkpoint(&self, checkpoint: &Checkpoint) -> Result<(), AppError> {
         let updated_at_str = checkpoint.updated_at.to_rfc3339();
         self.db.save_checkpoint(&updated_at_str, &checkpoint.id)
     }
 
     /// Get last sync timestamp
-    fn get_last_sync_timestamp(&self) -> Result<Option<i64>, String> {
+    fn get_last_sync_timestamp(&self) -> Result<Option<i64>, AppError> {
         let result = self.db.get_checkpoint()?;
         match result {
             Some((updated_at_str, _)) => {
                 let updated_at = DateTime::parse_from_rfc3339(&updated_at_str)
                     .map(|dt| dt.with_timezone(&Utc))
-                    .map_err(|e| format!("Failed to parse checkpoint timestamp: {}", e))?;
+                    .map_err(|e| {
+                        AppError::internal(format!("Failed to parse checkpoint timestamp: {}", e))
+                    })?;
                 Ok(Some(updated_at.timestamp()))
             }
             None => Ok(None),
@@ -747,26 +957,29 @@ impl SyncService {
     }
 
     /// Count pending changes
-    fn count_pending_changes(&self) -> Result<usize, String> {
+    fn count_pending_changes(&self) -> Result<usize, AppError> {
         let mut count = 0;
         count += self.db.query_count("SELECT COUNT(*) FROM topics WHERE synced_at IS NULL")?;
         count += self.db.query_count("SELECT COUNT(*) FROM questions WHERE synced_at IS NULL")?;
         count += self.db.query_count("SELECT COUNT(*) FROM progress WHERE synced_at IS NULL")?;
         count += self.db.query_count("SELECT COUNT(*) FROM quiz_sessions WHERE synced_at IS NULL")?;
+        count += self.db.query_count("SELECT COUNT(*) FROM annotations WHERE synced_at IS NULL")?;
+        count += self.db.query_count("SELECT COUNT(*) FROM saved_searches WHERE synced_at IS NULL")?;
+        count += self.db.query_count("SELECT COUNT(*) FROM sync_tombstones")?;
         Ok(count)
     }
 
     /// Get app_id from stored auth data
-    async fn get_app_id(&self, app_handle: &tauri::AppHandle) -> Result<String, String> {
+    async fn get_app_id(&self, app_handle: &tauri::AppHandle) -> Result<String, AppError> {
         use tauri_plugin_store::StoreExt;
 
         let store = app_handle
-            .store("auth.json")
-            .map_err(|e| format!("Failed to access store: {}", e))?;
+            .store(crate::auth::store_path(app_handle))
+            .map_err(|e| crate::auth::store_error("Failed to access store", e))?;
 
         store
             .get("app_id")
             .and_then(|v| v.as_str().map(|s| s.to_string()))
-            .ok_or_else(|| "No app ID found".to_string())
+            .ok_or_else(|| AppError::new(ErrorCode::Unauthenticated, "No app ID found"))
     }
 }
@@ -0,0 +1,73 @@
+use similar::{ChangeTag, TextDiff};
+
+use crate::database::models::{DiffLine, DiffOp};
+
+/// Line-based diff of two texts, in reading order
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            op: match change.tag() {
+                ChangeTag::Equal => DiffOp::Equal,
+                ChangeTag::Insert => DiffOp::Insert,
+                ChangeTag::Delete => DiffOp::Delete,
+            },
+            text: change.value().trim_end_matches(['\r', '\n']).to_string(),
+        })
+        .collect()
+}
+
+/// Tags in `new` but not `old`, and tags in `old` but not `new`,
+/// compared case-insensitively like the `tags` table
+pub fn diff_tags(old: &[String], new: &[String]) -> (Vec<String>, Vec<String>) {
+    let missing_from = |list: &[String], tag: &String| {
+        !list
+            .iter()
+            .any(|t| t.trim().eq_ignore_ascii_case(tag.trim()))
+    };
+    let added = new
+        .iter()
+        .filter(|t| missing_from(old, t))
+        .cloned()
+        .collect();
+    let removed = old
+        .iter()
+        .filter(|t| missing_from(new, t))
+        .cloned()
+        .collect();
+    (added, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines() {
+        let diff = diff_lines("a\nb\nc", "a\nc\nd");
+        let ops: Vec<(DiffOp, &str)> = diff
+            .iter()
+            .map(|l| (l.op.clone(), l.text.as_str()))
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                (DiffOp::Equal, "a"),
+                (DiffOp::Delete, "b"),
+                (DiffOp::Delete, "c"),
+                (DiffOp::Insert, "c"),
+                (DiffOp::Insert, "d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_tags() {
+        let old = vec!["rust".to_string(), "Memory".to_string()];
+        let new = vec!["memory".to_string(), "ownership".to_string()];
+        assert_eq!(
+            diff_tags(&old, &new),
+            (vec!["ownership".to_string()], vec!["rust".to_string()])
+        );
+    }
+}
@@ -11,6 +11,8 @@ pub struct ParsedQuestion {
     pub question_number: i32,
     pub question: String,
     pub answer: ParsedAnswer,
+    /// From a `**Difficulty:**` line before the answer, as written
+    pub difficulty: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -73,11 +75,21 @@ pub fn parse_markdown_file(content: &str) -> Result<Vec<ParsedTopic>, String> {
                     answer: ParsedAnswer {
                         markdown: String::new(),
                     },
+                    difficulty: None,
                 });
                 current_answer_content.clear();
                 in_answer_section = false;
             }
         }
+        // Parse difficulty marker
+        else if line.starts_with("**Difficulty:**") && !in_answer_section {
+            if let Some(question) = current_question.as_mut() {
+                let level = line["**Difficulty:**".len()..].trim();
+                if !level.is_empty() {
+                    question.difficulty = Some(level.to_string());
+                }
+            }
+        }
         // Parse answer marker
         else if line.starts_with("**Answer:**") {
             in_answer_section = true;
@@ -154,4 +166,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_difficulty() {
+        let content = "## Rust\n\n### 1. What is a trait?\n**Difficulty:** Advanced\n\n**Answer:**\nShared behaviour.\n\n### 2. What is a crate?\n\n**Answer:**\nA compilation unit.\n";
+        let topics = parse_markdown_file(content).unwrap();
+        let questions = &topics[0].questions;
+        assert_eq!(questions[0].difficulty.as_deref(), Some("Advanced"));
+        assert_eq!(questions[0].answer.markdown, "Shared behaviour.");
+        assert_eq!(questions[1].difficulty, None);
+    }
+
 }
@@ -0,0 +1,35 @@
+/// MIME type for an attachment, guessed from its file extension
+pub fn mime_from_file_name(file_name: &str) -> &'static str {
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "pdf" => "application/pdf",
+        "txt" | "log" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "json" => "application/json",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mime_from_file_name() {
+        assert_eq!(mime_from_file_name("diagram.PNG"), "image/png");
+        assert_eq!(mime_from_file_name("photo.final.jpeg"), "image/jpeg");
+        assert_eq!(mime_from_file_name("notes"), "application/octet-stream");
+    }
+}
@@ -1 +1,5 @@
+pub mod diff;
 pub mod markdown_parser;
+pub mod mime;
+pub mod search_query;
+pub mod similarity;
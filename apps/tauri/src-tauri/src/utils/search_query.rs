@@ -0,0 +1,432 @@
+//! Parser for the search box query language.
+//!
+//! ```text
+//! tag:rust diff:advanced -tag:legacy topic:"Spring Boot" (lifetimes OR borrow) is:due
+//! ```
+//!
+//! - Plain words match question, answer, and tags by prefix; `"quoted
+//!   phrases"` match exactly.
+//! - `tag:`, `diff:` (or `difficulty:`), `status:`, and `topic:` take a word
+//!   or a quoted value; `is:due` and `is:new` select by review state.
+//! - Terms side by side must all match. `OR` (upper case) between two terms
+//!   matches either and binds tighter, so `a b OR c` means `a AND (b OR c)`;
+//!   parentheses group explicitly.
+//! - A leading `-` negates a term or a group.
+
+use serde::Serialize;
+use std::fmt;
+
+use crate::database::models::ProgressStatus;
+
+const FIELDS: &str = "tag, diff, status, topic, or is";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchQuery {
+    And(Vec<SearchQuery>),
+    Or(Vec<SearchQuery>),
+    Not(Box<SearchQuery>),
+    /// A single term and the position it starts at
+    Term(SearchTerm, usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchTerm {
+    /// Prefix match in the question text
+    Word(String),
+    /// Exact phrase in the question text
+    Phrase(String),
+    Tag(String),
+    /// Level key as typed; checked against the defined levels when compiled
+    Difficulty(String),
+    Status(ProgressStatus),
+    /// Topic name; includes the topics below it
+    Topic(String),
+    /// Scheduled for review now or earlier
+    Due,
+    /// Not studied yet
+    New,
+}
+
+/// A query that doesn't parse or refers to something that doesn't exist
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchQueryError {
+    pub message: String,
+    /// Character offset into the query, from 0
+    pub position: usize,
+}
+
+impl SearchQueryError {
+    pub fn new(message: impl Into<String>, position: usize) -> Self {
+        Self {
+            message: message.into(),
+            position,
+        }
+    }
+}
+
+impl fmt::Display for SearchQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+impl SearchQuery {
+    /// Text terms that count toward a match, i.e. not under a `-`. Used to
+    /// rank results.
+    pub fn text_terms(&self) -> Vec<&SearchTerm> {
+        let mut terms = Vec::new();
+        self.collect_text_terms(&mut terms);
+        terms
+    }
+
+    fn collect_text_terms<'a>(&'a self, terms: &mut Vec<&'a SearchTerm>) {
+        match self {
+            SearchQuery::And(parts) | SearchQuery::Or(parts) => {
+                for part in parts {
+                    part.collect_text_terms(terms);
+                }
+            }
+            SearchQuery::Not(_) => {}
+            SearchQuery::Term(term @ (SearchTerm::Word(_) | SearchTerm::Phrase(_)), _) => {
+                terms.push(term)
+            }
+            SearchQuery::Term(..) => {}
+        }
+    }
+}
+
+/// Parse a query. Returns `None` when there is nothing to search for.
+pub fn parse_search_query(input: &str) -> Result<Option<SearchQuery>, SearchQueryError> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+    };
+    let query = parser.parse_and(0)?;
+    Ok(query)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Whether `OR` starts at the current position as a word of its own
+    fn at_or(&self) -> bool {
+        self.chars[self.pos..].starts_with(&['O', 'R'])
+            && self
+                .chars
+                .get(self.pos + 2)
+                .is_none_or(|c| c.is_whitespace() || matches!(c, '(' | ')' | '"'))
+    }
+
+    /// Terms up to the end of the input or the `)` closing this group
+    fn parse_and(&mut self, depth: usize) -> Result<Option<SearchQuery>, SearchQueryError> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => break,
+                Some(')') if depth > 0 => break,
+                Some(')') => return Err(SearchQueryError::new("Unmatched ')'", self.pos)),
+                _ if self.at_or() => {
+                    return Err(SearchQueryError::new(
+                        "OR must come between two terms",
+                        self.pos,
+                    ))
+                }
+                _ => parts.push(self.parse_or(depth)?),
+            }
+        }
+        Ok(match parts.len() {
+            0 => None,
+            1 => parts.pop(),
+            _ => Some(SearchQuery::And(parts)),
+        })
+    }
+
+    fn parse_or(&mut self, depth: usize) -> Result<SearchQuery, SearchQueryError> {
+        let mut parts = vec![self.parse_unary(depth)?];
+        loop {
+            let before = self.pos;
+            self.skip_whitespace();
+            if !self.at_or() {
+                self.pos = before;
+                break;
+            }
+            let or_pos = self.pos;
+            self.pos += 2;
+            self.skip_whitespace();
+            match self.peek() {
+                None | Some(')') => {
+                    return Err(SearchQueryError::new(
+                        "OR must come between two terms",
+                        or_pos,
+                    ))
+                }
+                _ if self.at_or() => {
+                    return Err(SearchQueryError::new(
+                        "OR must come between two terms",
+                        self.pos,
+                    ))
+                }
+                _ => parts.push(self.parse_unary(depth)?),
+            }
+        }
+        Ok(if parts.len() == 1 {
+            parts.pop().unwrap()
+        } else {
+            SearchQuery::Or(parts)
+        })
+    }
+
+    fn parse_unary(&mut self, depth: usize) -> Result<SearchQuery, SearchQueryError> {
+        if self.peek() != Some('-') {
+            return self.parse_atom(depth);
+        }
+        let start = self.pos;
+        self.pos += 1;
+        match self.peek() {
+            None => Err(SearchQueryError::new("Expected a term after '-'", start)),
+            Some(c) if c.is_whitespace() || c == ')' || c == '-' => {
+                Err(SearchQueryError::new("Expected a term after '-'", start))
+            }
+            _ => Ok(SearchQuery::Not(Box::new(self.parse_atom(depth)?))),
+        }
+    }
+
+    fn parse_atom(&mut self, depth: usize) -> Result<SearchQuery, SearchQueryError> {
+        let start = self.pos;
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.parse_and(depth + 1)?;
+                if self.peek() != Some(')') {
+                    return Err(SearchQueryError::new("Missing ')'", start));
+                }
+                self.pos += 1;
+                inner.ok_or(SearchQueryError::new("Empty group", start))
+            }
+            Some('"') => Ok(SearchQuery::Term(
+                SearchTerm::Phrase(self.parse_phrase()?),
+                start,
+            )),
+            _ => {
+                if let Some(field) = self.parse_field_name() {
+                    return self.parse_field(&field, start, depth);
+                }
+                let word = self.parse_word(depth);
+                Ok(SearchQuery::Term(SearchTerm::Word(word), start))
+            }
+        }
+    }
+
+    /// `"..."`, not empty
+    fn parse_phrase(&mut self) -> Result<String, SearchQueryError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut phrase = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(SearchQueryError::new("Unterminated quote", start)),
+                Some('"') => break,
+                Some(c) => phrase.push(c),
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+        if phrase.trim().is_empty() {
+            return Err(SearchQueryError::new("Empty phrase", start));
+        }
+        Ok(phrase.trim().to_string())
+    }
+
+    /// A run of letters followed by `:`, consumed with the colon
+    fn parse_field_name(&mut self) -> Option<String> {
+        let mut end = self.pos;
+        while self.chars.get(end).is_some_and(|c| c.is_ascii_alphabetic()) {
+            end += 1;
+        }
+        if end == self.pos || self.chars.get(end) != Some(&':') {
+            return None;
+        }
+        let name: String = self.chars[self.pos..end].iter().collect();
+        self.pos = end + 1;
+        Some(name.to_ascii_lowercase())
+    }
+
+    /// Everything up to whitespace or a quote; also up to `)` inside a
+    /// group, so `fn()` on its own is still a word
+    fn parse_word(&mut self, depth: usize) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || c == '"' || (c == ')' && depth > 0) {
+                break;
+            }
+            word.push(c);
+            self.pos += 1;
+        }
+        word
+    }
+
+    fn parse_field(
+        &mut self,
+        field: &str,
+        start: usize,
+        depth: usize,
+    ) -> Result<SearchQuery, SearchQueryError> {
+        if !matches!(
+            field,
+            "tag" | "diff" | "difficulty" | "status" | "topic" | "is"
+        ) {
+            return Err(SearchQueryError::new(
+                format!("Unknown field \"{}\"; expected {}", field, FIELDS),
+                start,
+            ));
+        }
+        let value_pos = self.pos;
+        let value = match self.peek() {
+            Some('"') => self.parse_phrase()?,
+            _ => self.parse_word(depth),
+        };
+        if value.is_empty() {
+            return Err(SearchQueryError::new(
+                format!("Missing value after '{}:'", field),
+                start,
+            ));
+        }
+
+        let term = match field {
+            "tag" => SearchTerm::Tag(value),
+            "diff" | "difficulty" => SearchTerm::Difficulty(value),
+            "topic" => SearchTerm::Topic(value),
+            "status" => SearchTerm::Status(parse_status(&value).ok_or_else(|| {
+                SearchQueryError::new(
+                    format!(
+                        "Unknown status \"{}\"; expected NotStudied, Studying, Mastered, or NeedsReview",
+                        value
+                    ),
+                    value_pos,
+                )
+            })?),
+            "is" => match value.to_ascii_lowercase().as_str() {
+                "due" => SearchTerm::Due,
+                "new" => SearchTerm::New,
+                _ => {
+                    return Err(SearchQueryError::new(
+                        format!("Unknown \"is:{}\"; expected is:due or is:new", value),
+                        value_pos,
+                    ))
+                }
+            },
+            _ => unreachable!(),
+        };
+        Ok(SearchQuery::Term(term, start))
+    }
+}
+
+/// Status names ignoring case, spaces, dashes, and underscores
+fn parse_status(value: &str) -> Option<ProgressStatus> {
+    let key: String = value
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match key.as_str() {
+        "notstudied" => Some(ProgressStatus::NotStudied),
+        "studying" => Some(ProgressStatus::Studying),
+        "mastered" => Some(ProgressStatus::Mastered),
+        "needsreview" => Some(ProgressStatus::NeedsReview),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(term: SearchTerm, position: usize) -> SearchQuery {
+        SearchQuery::Term(term, position)
+    }
+
+    #[test]
+    fn test_parse_fields_and_words() {
+        let query = parse_search_query(
+            "tag:rust diff:advanced status:NeedsReview topic:\"Spring Boot\" -tag:legacy lifetimes",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            query,
+            SearchQuery::And(vec![
+                term(SearchTerm::Tag("rust".into()), 0),
+                term(SearchTerm::Difficulty("advanced".into()), 9),
+                term(SearchTerm::Status(ProgressStatus::NeedsReview), 23),
+                term(SearchTerm::Topic("Spring Boot".into()), 42),
+                SearchQuery::Not(Box::new(term(SearchTerm::Tag("legacy".into()), 63))),
+                term(SearchTerm::Word("lifetimes".into()), 74),
+            ])
+        );
+        assert_eq!(
+            query.text_terms(),
+            vec![&SearchTerm::Word("lifetimes".into())]
+        );
+    }
+
+    #[test]
+    fn test_parse_or_groups() {
+        let query = parse_search_query("is:due a OR \"b c\" -(d OR e) fn()")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            query,
+            SearchQuery::And(vec![
+                term(SearchTerm::Due, 0),
+                SearchQuery::Or(vec![
+                    term(SearchTerm::Word("a".into()), 7),
+                    term(SearchTerm::Phrase("b c".into()), 12),
+                ]),
+                SearchQuery::Not(Box::new(SearchQuery::Or(vec![
+                    term(SearchTerm::Word("d".into()), 20),
+                    term(SearchTerm::Word("e".into()), 25),
+                ]))),
+                term(SearchTerm::Word("fn()".into()), 28),
+            ])
+        );
+        assert_eq!(
+            parse_search_query("fn() x:").unwrap_err().message,
+            "Unknown field \"x\"; expected tag, diff, status, topic, or is"
+        );
+        assert_eq!(parse_search_query("   ").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_errors_have_positions() {
+        let cases = [
+            ("rust \"unclosed", 5),
+            ("a OR", 2),
+            ("OR a", 0),
+            ("(a b", 0),
+            ("a )", 2),
+            ("tag:", 0),
+            ("status:done", 7),
+            ("is:old", 3),
+            ("a - b", 2),
+            ("()", 0),
+        ];
+        for (input, position) in cases {
+            let err = parse_search_query(input).unwrap_err();
+            assert_eq!(err.position, position, "{}: {}", input, err);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Characters per shingle
+const SHINGLE_SIZE: usize = 3;
+
+/// Lower-case `text`, turn punctuation and markdown into spaces, and collapse
+/// whitespace, so wording that differs only in formatting compares equal
+pub fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_lowercase().next().unwrap_or(c)
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Hashed character shingles of the normalized text, sorted and unique.
+/// Character shingles tolerate small rewordings and typos better than
+/// word shingles on short questions.
+pub fn shingles(text: &str) -> Vec<u64> {
+    let chars: Vec<char> = normalize(text).chars().collect();
+    let mut hashes: Vec<u64> = if chars.len() <= SHINGLE_SIZE {
+        if chars.is_empty() {
+            Vec::new()
+        } else {
+            vec![hash(&chars)]
+        }
+    } else {
+        chars.windows(SHINGLE_SIZE).map(hash).collect()
+    };
+    hashes.sort_unstable();
+    hashes.dedup();
+    hashes
+}
+
+fn hash(chars: &[char]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    chars.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Jaccard similarity of two shingle sets from `shingles`, from 0 to 1
+pub fn jaccard(a: &[u64], b: &[u64]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let (mut i, mut j, mut shared) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                shared += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+/// Group `0..n` into connected components of `edges`, dropping singletons.
+/// Each group is sorted, and groups are ordered by their first member.
+pub fn clusters(n: usize, edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut parent: Vec<usize> = (0..n).collect();
+    fn find(parent: &mut [usize], mut x: usize) -> usize {
+        while parent[x] != x {
+            parent[x] = parent[parent[x]];
+            x = parent[x];
+        }
+        x
+    }
+    for &(a, b) in edges {
+        let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+        if ra != rb {
+            parent[ra.max(rb)] = ra.min(rb);
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for i in 0..n {
+        let root = find(&mut parent, i);
+        groups[root].push(i);
+    }
+    groups.retain(|g| g.len() > 1);
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize("  What's **Ownership**\nin `Rust`?  "),
+            "what s ownership in rust"
+        );
+    }
+
+    #[test]
+    fn test_jaccard_scores_rewordings_high() {
+        let a = shingles("What is the difference between a process and a thread?");
+        let b = shingles("What's the difference between process and thread?");
+        let c = shingles("Explain how the borrow checker works");
+        assert_eq!(jaccard(&a, &a), 1.0);
+        assert!(jaccard(&a, &b) > 0.6, "{}", jaccard(&a, &b));
+        assert!(jaccard(&a, &c) < 0.2, "{}", jaccard(&a, &c));
+        assert_eq!(jaccard(&a, &shingles("?!")), 0.0);
+    }
+
+    #[test]
+    fn test_clusters() {
+        assert_eq!(
+            clusters(6, &[(4, 1), (1, 3), (0, 5)]),
+            vec![vec![0, 5], vec![1, 3, 4]]
+        );
+        assert!(clusters(3, &[]).is_empty());
+    }
+}